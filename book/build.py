@@ -253,6 +253,10 @@ list_of_functions(
                 "title": "Error handling",
                 "modules": ["core::error"],
             },
+            {
+                "title": "Debugging",
+                "modules": ["core::debugging"],
+            },
             {
                 "title": "Floating point",
                 "modules": ["core::numbers"],
@@ -277,6 +281,14 @@ list_of_functions(
                 "title": "Color format conversion",
                 "modules": ["extra::color"],
             },
+            {
+                "title": "Scripting",
+                "modules": ["extra::scripting"],
+            },
+            {
+                "title": "Data files",
+                "modules": ["extra::data"],
+            },
         ],
     },
 )
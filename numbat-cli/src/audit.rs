@@ -0,0 +1,116 @@
+use std::path::PathBuf;
+
+use anyhow::{bail, Context as AnyhowContext, Result};
+use colored::control::SHOULD_COLORIZE;
+use colored::Colorize;
+use numbat::module_importer::{BuiltinModuleImporter, ChainedImporter, FileSystemImporter};
+use numbat::resolver::CodeSource;
+use numbat::{Context, NumbatError, Span, UnitReading};
+
+use crate::{Cli, ExitStatus};
+
+#[derive(clap::Args, Debug)]
+pub struct AuditUnitsArgs {
+    /// Files with unit definitions to audit, in addition to the prelude.
+    files: Vec<PathBuf>,
+
+    /// Audit all modules of the standard library, not just the prelude.
+    #[arg(long)]
+    all_modules: bool,
+}
+
+/// Where a unit was defined, e.g. 'units::time' or the path of a file.
+fn origin(ctx: &Context, span: Span) -> String {
+    match ctx.resolver().get_code_source(span.code_source_id) {
+        CodeSource::Module(module_path, _) => module_path.to_string(),
+        CodeSource::File(path) => path.to_string_lossy().into_owned(),
+        CodeSource::Text | CodeSource::Internal => "<input>".into(),
+    }
+}
+
+fn print_reading(ctx: &Context, reading: &UnitReading) {
+    let note = if reading.valid {
+        "".normal()
+    } else {
+        format!(
+            " (but '{}' does not accept the prefix '{}')",
+            reading.unit_name, reading.prefix
+        )
+        .dimmed()
+    };
+    println!(
+        "    {} {}{note}",
+        reading.to_string().cyan(),
+        format!("[{}]", origin(ctx, reading.definition_span)).dimmed()
+    );
+}
+
+fn interpret(ctx: &mut Context, code: &str, code_source: CodeSource) -> bool {
+    match ctx.interpret(code, code_source) {
+        Ok(_) => true,
+        Err(error) => {
+            match *error {
+                NumbatError::ResolverError(e) => ctx.print_diagnostic(e),
+                NumbatError::NameResolutionError(e) => ctx.print_diagnostic(e),
+                NumbatError::TypeCheckError(e) => ctx.print_diagnostic(e),
+                NumbatError::RuntimeError(e) => ctx.print_diagnostic(e),
+            }
+            false
+        }
+    }
+}
+
+/// Lists all identifiers that are parsed as a unit, but could also be read as a
+/// prefix and a different unit.
+pub fn run(args: AuditUnitsArgs) -> Result<ExitStatus> {
+    let mut fs_importer = FileSystemImporter::default();
+    for path in Cli::get_modules_paths() {
+        fs_importer.add_path(path);
+    }
+    let importer = ChainedImporter::new(
+        Box::new(fs_importer),
+        Box::<BuiltinModuleImporter>::default(),
+    );
+
+    let mut ctx = Context::new(importer);
+    ctx.set_colored_diagnostics(SHOULD_COLORIZE.should_colorize());
+
+    let import = if args.all_modules {
+        "use all"
+    } else {
+        "use prelude"
+    };
+    if let Err(e) = ctx.interpret(import, CodeSource::Internal) {
+        bail!("Could not load the standard library: {e}");
+    }
+
+    for path in &args.files {
+        let code = std::fs::read_to_string(path)
+            .with_context(|| format!("Could not read '{}'", path.to_string_lossy()))?;
+        if !interpret(&mut ctx, &code, CodeSource::File(path.clone())) {
+            return Ok(ExitStatus::Error);
+        }
+    }
+
+    // The warnings are reported by this audit in a more complete form
+    ctx.take_warnings();
+
+    let ambiguities = ctx.ambiguous_unit_identifiers();
+    for (identifier, readings) in &ambiguities {
+        println!("{}", identifier.bold());
+        for reading in readings {
+            print_reading(&ctx, reading);
+        }
+    }
+
+    if !ambiguities.is_empty() {
+        println!();
+    }
+    println!(
+        "found {} ambiguous unit identifier{}",
+        ambiguities.len(),
+        if ambiguities.len() == 1 { "" } else { "s" }
+    );
+
+    Ok(ExitStatus::Success)
+}
@@ -57,7 +57,7 @@ impl Completer for NumbatCompleter {
 
             return Ok((
                 0,
-                ["functions", "dimensions", "units", "variables"]
+                ["functions", "dimensions", "units", "variables", "modules"]
                     .iter()
                     .map(|category| {
                         let line = format!("{command} {category}");
@@ -120,17 +120,17 @@ impl Completer for NumbatCompleter {
             .iter()
             .any(|&s| line[..pos].contains(s));
 
-        let candidates = self
-            .context
-            .lock()
-            .unwrap()
-            .get_completions_for(word_part, add_paren);
+        let context = self.context.lock().unwrap();
+        let candidates = context.get_completions_for(word_part, add_paren);
 
         Ok((
             pos_word,
             candidates
                 .map(|w| Pair {
-                    display: w.to_string(),
+                    display: match context.deprecation_message(w.trim_end_matches('(')) {
+                        Some(_) => format!("{w} (deprecated)"),
+                        None => w.to_string(),
+                    },
                     replacement: w,
                 })
                 .collect(),
@@ -1,5 +1,6 @@
 use clap::ValueEnum;
 use numbat::compact_str::CompactString;
+use numbat::warning::WarningCategory;
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, PartialEq, Eq, Default, Debug, Clone, Copy, ValueEnum)]
@@ -65,6 +66,13 @@ pub struct Config {
 
     #[serde(skip_serializing)]
     pub load_user_init: bool,
+
+    /// Only load prelude modules when one of their identifiers is first used
+    pub lazy_prelude: bool,
+    /// Cache the state after loading the prelude in a snapshot on disk
+    pub prelude_cache: bool,
+    /// Categories of warnings that are not reported
+    pub suppressed_warnings: Vec<WarningCategory>,
     pub exchange_rates: ExchangeRateConfig,
 }
 
@@ -77,6 +85,9 @@ impl Default for Config {
             color: ColorMode::default(),
             load_prelude: true,
             load_user_init: true,
+            lazy_prelude: false,
+            prelude_cache: true,
+            suppressed_warnings: vec![],
             exchange_rates: Default::default(),
             enter_repl: true,
         }
@@ -0,0 +1,151 @@
+use std::io::Write;
+
+use numbat::debugger::{DebugCommand, DebugHandler, PausedState};
+use numbat::pretty_print::PrettyPrint;
+use numbat::Span;
+
+use crate::ansi_formatter::ansi_format;
+
+const HELP: &str = "\
+Commands:
+  step, s            Run until the next location, stepping into function calls
+  next, n            Run until the next location in the current function
+  finish, f          Run until the current function returns
+  continue, c        Run until the next breakpoint
+  break, b <line>    Set a breakpoint at the given line of the script
+  delete, d <line>   Remove the breakpoint at the given line of the script
+  backtrace, bt      Show the call stack
+  locals, l          Show the local variables of the current function
+  print, p <name>    Show the value of a variable
+  quit, q            Stop the program
+  help, h            Show this help text";
+
+/// A debug handler that reads commands from standard input.
+pub struct CliDebugHandler {
+    /// The code source ID and the source code of the script that is being debugged
+    code_source_id: usize,
+    code: String,
+}
+
+impl CliDebugHandler {
+    pub fn new(code_source_id: usize, code: String) -> Self {
+        Self {
+            code_source_id,
+            code,
+        }
+    }
+
+    fn line_span(&self, line: &str) -> Result<Span, String> {
+        let line_number: usize = line
+            .parse()
+            .map_err(|_| format!("Invalid line number '{line}'"))?;
+
+        let mut start = 0;
+        for (index, text) in self.code.split('\n').enumerate() {
+            if index + 1 == line_number {
+                return Ok(Span {
+                    start: (start as u32).into(),
+                    end: ((start + text.len()) as u32).into(),
+                    code_source_id: self.code_source_id,
+                });
+            }
+            start += text.len() + 1;
+        }
+
+        Err(format!("The script does not have a line {line_number}"))
+    }
+
+    fn print_location(state: &PausedState, span: Span, function_name: &str) {
+        match state.source_location(span) {
+            Some(location) => {
+                println!(
+                    "{}:{}:{} (in {function_name})",
+                    location.file_name, location.line, location.column
+                );
+                println!("{:>5} | {}", location.line, location.line_text);
+            }
+            None => println!("(in {function_name})"),
+        }
+    }
+}
+
+impl DebugHandler for CliDebugHandler {
+    fn on_pause(&mut self, state: &PausedState, breakpoints: &mut Vec<Span>) -> DebugCommand {
+        Self::print_location(state, state.span(), &state.current_frame().function_name);
+
+        loop {
+            print!("(debug) ");
+            std::io::stdout().flush().ok();
+
+            let mut input = String::new();
+            match std::io::stdin().read_line(&mut input) {
+                Ok(0) | Err(_) => {
+                    // Run the rest of the program when the input is closed
+                    breakpoints.clear();
+                    return DebugCommand::Continue;
+                }
+                Ok(_) => {}
+            }
+
+            let (command, argument) = match input.trim().split_once(char::is_whitespace) {
+                Some((command, argument)) => (command, argument.trim()),
+                None => (input.trim(), ""),
+            };
+
+            match command {
+                "" => {}
+                "step" | "s" => return DebugCommand::StepIn,
+                "next" | "n" => return DebugCommand::StepOver,
+                "finish" | "f" => return DebugCommand::StepOut,
+                "continue" | "c" => return DebugCommand::Continue,
+                "break" | "b" => match self.line_span(argument) {
+                    Ok(span) => {
+                        if !breakpoints.contains(&span) {
+                            breakpoints.push(span);
+                        }
+                        println!("Breakpoint set at line {argument}");
+                    }
+                    Err(e) => println!("{e}"),
+                },
+                "delete" | "d" => match self.line_span(argument) {
+                    Ok(span) => {
+                        if breakpoints.contains(&span) {
+                            breakpoints.retain(|b| b != &span);
+                            println!("Breakpoint at line {argument} removed");
+                        } else {
+                            println!("There is no breakpoint at line {argument}");
+                        }
+                    }
+                    Err(e) => println!("{e}"),
+                },
+                "backtrace" | "bt" => {
+                    for (index, frame) in state.call_stack().iter().rev().enumerate() {
+                        print!("#{index} ");
+                        match frame.span {
+                            Some(span) => Self::print_location(state, span, &frame.function_name),
+                            None => println!("(in {})", frame.function_name),
+                        }
+                    }
+                }
+                "locals" | "l" => {
+                    let locals = &state.current_frame().locals;
+                    if locals.is_empty() {
+                        println!("No local variables");
+                    }
+                    for (name, value) in locals {
+                        println!("{name} = {}", ansi_format(&value.pretty_print(), false));
+                    }
+                }
+                "print" | "p" => match state.lookup(argument) {
+                    Some(value) => {
+                        println!("{argument} = {}", ansi_format(&value.pretty_print(), false))
+                    }
+                    None => println!("Unknown variable '{argument}'"),
+                },
+                "quit" | "q" => std::process::exit(1),
+                "help" | "h" => println!("{HELP}"),
+                _ => println!("Unknown command '{command}'. Type 'help' for a list of commands."),
+            }
+        }
+    }
+}
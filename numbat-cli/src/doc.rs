@@ -0,0 +1,580 @@
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context as AnyhowContext, Result};
+use clap::ValueEnum;
+use itertools::Itertools;
+use numbat::compact_str::CompactString;
+use numbat::help::evaluate_example;
+use numbat::markup::plain_text_format;
+use numbat::module_importer::{BuiltinModuleImporter, ChainedImporter, FileSystemImporter};
+use numbat::resolver::CodeSource;
+use numbat::{Context, NumbatError};
+
+use crate::Cli;
+
+#[derive(clap::Args, Debug)]
+pub struct DocArgs {
+    /// Root directory of the module tree to document.
+    path: PathBuf,
+
+    /// Directory to write the documentation to.
+    #[arg(short, long, value_name = "DIR", default_value = "doc")]
+    output: PathBuf,
+
+    /// Format of the generated documentation.
+    #[arg(long, value_enum, default_value_t = DocFormat::Markdown)]
+    format: DocFormat,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+enum DocFormat {
+    Markdown,
+    Html,
+}
+
+impl DocFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            DocFormat::Markdown => "md",
+            DocFormat::Html => "html",
+        }
+    }
+}
+
+struct ExampleDoc {
+    description: Option<CompactString>,
+    code: CompactString,
+    output: String,
+}
+
+struct FunctionDoc {
+    fn_name: CompactString,
+    name: Option<CompactString>,
+    signature: CompactString,
+    description: Option<CompactString>,
+    url: Option<CompactString>,
+    examples: Vec<ExampleDoc>,
+}
+
+struct UnitDoc {
+    identifier: CompactString,
+    name: CompactString,
+    aliases: Vec<CompactString>,
+    url: Option<CompactString>,
+    description: Option<CompactString>,
+    dimension: String,
+}
+
+struct ModuleDoc {
+    path: String,
+    description: Option<CompactString>,
+    functions: Vec<FunctionDoc>,
+    units: Vec<UnitDoc>,
+}
+
+impl ModuleDoc {
+    fn file_name(&self, format: DocFormat) -> String {
+        format!("{}.{}", self.path.replace("::", "-"), format.extension())
+    }
+}
+
+/// Links from identifiers to the place where they are documented.
+type Links = HashMap<CompactString, String>;
+
+/// Finds all modules below `root`, as `a::b` paths.
+fn find_modules(root: &Path, dir: &Path, modules: &mut Vec<String>) -> Result<()> {
+    let read_error = || format!("Could not read '{}'", dir.to_string_lossy());
+    let mut entries = fs::read_dir(dir)
+        .with_context(read_error)?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<std::io::Result<Vec<_>>>()
+        .with_context(read_error)?;
+    entries.sort();
+
+    for entry in entries {
+        if entry.is_dir() {
+            find_modules(root, &entry, modules)?;
+        } else if entry.extension().is_some_and(|e| e == "nbt") {
+            let relative = entry.strip_prefix(root)?.with_extension("");
+            modules.push(
+                relative
+                    .components()
+                    .map(|c| c.as_os_str().to_string_lossy())
+                    .join("::"),
+            );
+        }
+    }
+
+    Ok(())
+}
+
+fn check(
+    ctx: &Context,
+    result: std::result::Result<(), Box<NumbatError>>,
+    what: impl FnOnce() -> String,
+) -> Result<()> {
+    let Err(error) = result else {
+        return Ok(());
+    };
+    match *error {
+        NumbatError::ResolverError(e) => ctx.print_diagnostic(e),
+        NumbatError::NameResolutionError(e) => ctx.print_diagnostic(e),
+        NumbatError::TypeCheckError(e) => ctx.print_diagnostic(e),
+        NumbatError::RuntimeError(e) => ctx.print_diagnostic(e),
+    }
+    bail!("{}", what())
+}
+
+fn module_of(code_source: CodeSource) -> Option<String> {
+    match code_source {
+        CodeSource::Module(module_path, _) => Some(module_path.to_string()),
+        _ => None,
+    }
+}
+
+fn document_modules(
+    ctx: &Context,
+    prelude_ctx: &Context,
+    module_paths: &[String],
+) -> Result<Vec<ModuleDoc>> {
+    let mut modules: Vec<ModuleDoc> = module_paths
+        .iter()
+        .map(|path| ModuleDoc {
+            path: path.clone(),
+            description: ctx.module_info(path).and_then(|info| info.description),
+            functions: vec![],
+            units: vec![],
+        })
+        .collect();
+    let index: HashMap<String, usize> = module_paths
+        .iter()
+        .enumerate()
+        .map(|(i, path)| (path.clone(), i))
+        .collect();
+
+    let mut example_contexts = HashMap::new();
+    for (fn_name, name, signature, description, url, examples, code_source) in
+        ctx.functions().unique_by(|(fn_name, ..)| fn_name.clone())
+    {
+        let Some(&i) = module_of(code_source).and_then(|m| index.get(&m)) else {
+            continue;
+        };
+        let module = &module_paths[i];
+
+        // Examples are evaluated in a context that only has the prelude and the
+        // module of the function loaded. It is shared by all examples of a module.
+        let examples = if examples.is_empty() {
+            vec![]
+        } else {
+            let example_ctx = match example_contexts.entry(i) {
+                Entry::Occupied(entry) => entry.into_mut(),
+                Entry::Vacant(entry) => {
+                    let mut module_ctx = prelude_ctx.clone();
+                    let result = module_ctx
+                        .interpret(&format!("use {module}"), CodeSource::Internal)
+                        .map(|_| ());
+                    check(&module_ctx, result, || {
+                        format!("Could not load module '{module}'")
+                    })?;
+                    entry.insert(module_ctx)
+                }
+            };
+
+            examples
+                .into_iter()
+                .map(|(code, description)| {
+                    evaluate_example(example_ctx, &code)
+                        .map(|output| ExampleDoc {
+                            description,
+                            output: plain_text_format(&output, false).trim().to_string(),
+                            code: code.clone(),
+                        })
+                        .map_err(|e| {
+                            anyhow::anyhow!("Example '{code}' of function '{fn_name}' failed: {e}")
+                        })
+                })
+                .collect::<Result<_>>()?
+        };
+
+        modules[i].functions.push(FunctionDoc {
+            fn_name,
+            name,
+            signature,
+            description,
+            url,
+            examples,
+        });
+    }
+
+    for (identifier, (_, metadata)) in ctx.unit_representations() {
+        let Some(&i) = ctx
+            .definition_span(&identifier)
+            .and_then(|span| module_of(ctx.resolver().get_code_source(span.code_source_id)))
+            .and_then(|m| index.get(&m))
+        else {
+            continue;
+        };
+
+        modules[i].units.push(UnitDoc {
+            name: metadata.name.unwrap_or(identifier.clone()),
+            aliases: metadata
+                .aliases
+                .into_iter()
+                .map(|(alias, _)| alias)
+                .sorted_by_key(|alias| alias.to_lowercase())
+                .collect(),
+            url: metadata.url,
+            description: metadata.description,
+            dimension: plain_text_format(&metadata.readable_type, false).to_string(),
+            identifier,
+        });
+    }
+
+    for module in &mut modules {
+        module
+            .units
+            .sort_by_key(|unit| (unit.dimension.clone(), unit.name.to_lowercase()));
+    }
+
+    Ok(modules)
+}
+
+fn collect_links(modules: &[ModuleDoc], format: DocFormat) -> Links {
+    let mut links = Links::new();
+    for module in modules {
+        let file_name = module.file_name(format);
+        for function in &module.functions {
+            links.insert(
+                function.fn_name.clone(),
+                format!("{file_name}#{}", function.fn_name),
+            );
+        }
+        for unit in &module.units {
+            for alias in &unit.aliases {
+                links
+                    .entry(alias.clone())
+                    .or_insert_with(|| format!("{file_name}#unit-{}", unit.identifier));
+            }
+        }
+    }
+    links
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Formats a description, and links all `code spans` that refer to documented
+/// functions or units.
+fn format_text(text: &str, links: &Links, format: DocFormat) -> String {
+    let mut output = String::new();
+    for (i, part) in text.trim().split('`').enumerate() {
+        let is_code = i % 2 == 1;
+        match (format, is_code, links.get(part)) {
+            (DocFormat::Markdown, false, _) => output.push_str(part),
+            (DocFormat::Markdown, true, None) => output.push_str(&format!("`{part}`")),
+            (DocFormat::Markdown, true, Some(link)) => {
+                output.push_str(&format!("[`{part}`]({link})"))
+            }
+            (DocFormat::Html, false, _) => output.push_str(&escape_html(part)),
+            (DocFormat::Html, true, None) => {
+                output.push_str(&format!("<code>{}</code>", escape_html(part)))
+            }
+            (DocFormat::Html, true, Some(link)) => output.push_str(&format!(
+                "<a href=\"{link}\"><code>{}</code></a>",
+                escape_html(part)
+            )),
+        }
+    }
+    output
+}
+
+fn count(n: usize, noun: &str) -> String {
+    format!("{n} {noun}{}", if n == 1 { "" } else { "s" })
+}
+
+fn markdown_index(modules: &[ModuleDoc]) -> String {
+    let mut out = String::from("# Modules\n\n");
+    for module in modules {
+        let _ = writeln!(
+            out,
+            "- [`{}`]({}): {}, {}",
+            module.path,
+            module.file_name(DocFormat::Markdown),
+            count(module.functions.len(), "function"),
+            count(module.units.len(), "unit")
+        );
+    }
+    out
+}
+
+fn markdown_module(module: &ModuleDoc, links: &Links) -> String {
+    let format = DocFormat::Markdown;
+    let mut out = format!("# Module `{}`\n\n", module.path);
+    if let Some(description) = &module.description {
+        let _ = writeln!(out, "{description}\n");
+    }
+    let _ = writeln!(out, "```nbt\nuse {}\n```\n", module.path);
+    let _ = writeln!(out, "[All modules](index.md)\n");
+
+    if !module.functions.is_empty() {
+        out.push_str("## Functions\n\n");
+    }
+    for function in &module.functions {
+        let _ = writeln!(out, "<a id=\"{}\"></a>", function.fn_name);
+        match &function.name {
+            Some(name) => {
+                let _ = writeln!(out, "### `{}` ({name})\n", function.fn_name);
+            }
+            None => {
+                let _ = writeln!(out, "### `{}`\n", function.fn_name);
+            }
+        }
+        if let Some(description) = &function.description {
+            let _ = writeln!(out, "{}", format_text(description, links, format));
+        }
+        if let Some(url) = &function.url {
+            let _ = writeln!(out, "More information [here]({url}).");
+        }
+        let _ = writeln!(out, "\n```nbt\n{}\n```\n", function.signature);
+
+        for example in &function.examples {
+            if let Some(description) = &example.description {
+                let _ = writeln!(out, "{}\n", format_text(description, links, format));
+            }
+            let _ = writeln!(out, "```nbt\n>>> {}\n", example.code.trim());
+            for line in example.output.lines() {
+                let _ = writeln!(out, "    {line}");
+            }
+            out.push_str("```\n\n");
+        }
+    }
+
+    if !module.units.is_empty() {
+        out.push_str("## Units\n\n");
+    }
+    for (dimension, units) in &module.units.iter().group_by(|unit| &unit.dimension) {
+        let _ = writeln!(out, "### {dimension}\n");
+        out.push_str("| Unit | Identifier(s) | Description |\n| --- | --- | --- |\n");
+        for unit in units {
+            let name = match &unit.url {
+                Some(url) => format!("[{}]({url})", unit.name),
+                None => unit.name.to_string(),
+            };
+            let _ = writeln!(
+                out,
+                "| <a id=\"unit-{}\"></a>{name} | `{}` | {} |",
+                unit.identifier,
+                unit.aliases.iter().join("`, `"),
+                unit.description
+                    .as_deref()
+                    .map(|d| format_text(d, links, format).replace('\n', " "))
+                    .unwrap_or_default()
+            );
+        }
+        out.push('\n');
+    }
+
+    out
+}
+
+fn html_page(title: &str, body: &str) -> String {
+    format!(
+        "<!DOCTYPE html>
+<html>
+<head>
+<meta charset=\"utf-8\">
+<title>{title}</title>
+<style>
+body {{ font-family: sans-serif; max-width: 60em; margin: 2em auto; padding: 0 1em; line-height: 1.5; }}
+pre {{ background: #f6f8fa; padding: 0.8em; overflow-x: auto; }}
+code {{ font-size: 0.95em; }}
+table {{ border-collapse: collapse; }}
+th, td {{ border: 1px solid #ddd; padding: 0.3em 0.6em; text-align: left; }}
+.output {{ color: #555; }}
+</style>
+</head>
+<body>
+{body}</body>
+</html>
+"
+    )
+}
+
+fn html_index(modules: &[ModuleDoc]) -> String {
+    let mut body = String::from("<h1>Modules</h1>\n<ul>\n");
+    for module in modules {
+        let _ = writeln!(
+            body,
+            "<li><a href=\"{}\"><code>{}</code></a>: {}, {}</li>",
+            module.file_name(DocFormat::Html),
+            escape_html(&module.path),
+            count(module.functions.len(), "function"),
+            count(module.units.len(), "unit")
+        );
+    }
+    body.push_str("</ul>\n");
+    html_page("Modules", &body)
+}
+
+fn html_module(module: &ModuleDoc, links: &Links) -> String {
+    let format = DocFormat::Html;
+    let path = escape_html(&module.path);
+    let mut body = format!("<h1>Module <code>{path}</code></h1>\n");
+    if let Some(description) = &module.description {
+        let _ = writeln!(body, "<p>{}</p>", escape_html(description));
+    }
+    let _ = writeln!(body, "<pre><code>use {path}</code></pre>");
+    let _ = writeln!(body, "<p><a href=\"index.html\">All modules</a></p>");
+
+    if !module.functions.is_empty() {
+        body.push_str("<h2>Functions</h2>\n");
+    }
+    for function in &module.functions {
+        let fn_name = escape_html(&function.fn_name);
+        let _ = write!(body, "<h3 id=\"{fn_name}\"><code>{fn_name}</code>");
+        if let Some(name) = &function.name {
+            let _ = write!(body, " ({})", escape_html(name));
+        }
+        body.push_str("</h3>\n");
+        if let Some(description) = &function.description {
+            let _ = writeln!(body, "<p>{}</p>", format_text(description, links, format));
+        }
+        if let Some(url) = &function.url {
+            let _ = writeln!(
+                body,
+                "<p>More information <a href=\"{}\">here</a>.</p>",
+                escape_html(url)
+            );
+        }
+        let _ = writeln!(
+            body,
+            "<pre><code>{}</code></pre>",
+            escape_html(&function.signature)
+        );
+
+        for example in &function.examples {
+            if let Some(description) = &example.description {
+                let _ = writeln!(body, "<p>{}</p>", format_text(description, links, format));
+            }
+            let _ = writeln!(
+                body,
+                "<pre><code>&gt;&gt;&gt; {}\n<span class=\"output\">{}</span></code></pre>",
+                escape_html(example.code.trim()),
+                escape_html(&example.output)
+            );
+        }
+    }
+
+    if !module.units.is_empty() {
+        body.push_str("<h2>Units</h2>\n");
+    }
+    for (dimension, units) in &module.units.iter().group_by(|unit| &unit.dimension) {
+        let _ = writeln!(body, "<h3>{}</h3>", escape_html(dimension));
+        body.push_str(
+            "<table>\n<tr><th>Unit</th><th>Identifier(s)</th><th>Description</th></tr>\n",
+        );
+        for unit in units {
+            let name = match &unit.url {
+                Some(url) => format!(
+                    "<a href=\"{}\">{}</a>",
+                    escape_html(url),
+                    escape_html(&unit.name)
+                ),
+                None => escape_html(&unit.name),
+            };
+            let _ = writeln!(
+                body,
+                "<tr id=\"unit-{}\"><td>{name}</td><td>{}</td><td>{}</td></tr>",
+                escape_html(&unit.identifier),
+                unit.aliases
+                    .iter()
+                    .map(|alias| format!("<code>{}</code>", escape_html(alias)))
+                    .join(", "),
+                unit.description
+                    .as_deref()
+                    .map(|d| format_text(d, links, format))
+                    .unwrap_or_default()
+            );
+        }
+        body.push_str("</table>\n");
+    }
+
+    html_page(&format!("Module {path}"), &body)
+}
+
+pub fn run(args: DocArgs) -> Result<()> {
+    if !args.path.is_dir() {
+        bail!("'{}' is not a directory", args.path.to_string_lossy());
+    }
+
+    let mut module_paths = vec![];
+    find_modules(&args.path, &args.path, &mut module_paths)?;
+
+    let mut fs_importer = FileSystemImporter::default();
+    fs_importer.add_path(&args.path);
+    for path in Cli::get_modules_paths() {
+        fs_importer.add_path(path);
+    }
+    let importer = ChainedImporter::new(
+        Box::new(fs_importer),
+        Box::<BuiltinModuleImporter>::default(),
+    );
+
+    let mut prelude_ctx = Context::new(importer);
+    let result = prelude_ctx
+        .interpret("use prelude", CodeSource::Internal)
+        .map(|_| ());
+    check(&prelude_ctx, result, || "Could not load the prelude".into())?;
+
+    let mut ctx = prelude_ctx.clone();
+    for module in &module_paths {
+        let result = ctx
+            .interpret(&format!("use {module}"), CodeSource::Internal)
+            .map(|_| ());
+        check(&ctx, result, || format!("Could not load module '{module}'"))?;
+    }
+
+    let modules = document_modules(&ctx, &prelude_ctx, &module_paths)?;
+    let links = collect_links(&modules, args.format);
+
+    fs::create_dir_all(&args.output).with_context(|| {
+        format!(
+            "Could not create directory '{}'",
+            args.output.to_string_lossy()
+        )
+    })?;
+    let write = |file_name: &str, content: String| {
+        let path = args.output.join(file_name);
+        fs::write(&path, content)
+            .with_context(|| format!("Could not write '{}'", path.to_string_lossy()))
+    };
+
+    for module in &modules {
+        let content = match args.format {
+            DocFormat::Markdown => markdown_module(module, &links),
+            DocFormat::Html => html_module(module, &links),
+        };
+        write(&module.file_name(args.format), content)?;
+    }
+    let index = match args.format {
+        DocFormat::Markdown => markdown_index(&modules),
+        DocFormat::Html => html_index(&modules),
+    };
+    write(&format!("index.{}", args.format.extension()), index)?;
+
+    println!(
+        "Documented {} modules in '{}'",
+        modules.len(),
+        args.output.to_string_lossy()
+    );
+
+    Ok(())
+}
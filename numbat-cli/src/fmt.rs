@@ -0,0 +1,130 @@
+use std::io::Read;
+use std::path::PathBuf;
+
+use anyhow::{Context as AnyhowContext, Result};
+use clap::ValueEnum;
+use codespan_reporting::files::SimpleFiles;
+use codespan_reporting::term::{
+    self,
+    termcolor::{ColorChoice, StandardStream},
+    Config,
+};
+use numbat::diagnostic::ErrorDiagnostic;
+use numbat::formatter::{format, FormatOptions, MultiplicationOperator};
+
+use crate::ExitStatus;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum MultiplicationOperatorArg {
+    /// Keep the operator as written
+    #[default]
+    Preserve,
+    /// Use '*'
+    Asterisk,
+    /// Use '×'
+    Times,
+    /// Use '·'
+    Dot,
+}
+
+impl From<MultiplicationOperatorArg> for MultiplicationOperator {
+    fn from(value: MultiplicationOperatorArg) -> Self {
+        match value {
+            MultiplicationOperatorArg::Preserve => MultiplicationOperator::Preserve,
+            MultiplicationOperatorArg::Asterisk => MultiplicationOperator::Asterisk,
+            MultiplicationOperatorArg::Times => MultiplicationOperator::Times,
+            MultiplicationOperatorArg::Dot => MultiplicationOperator::Dot,
+        }
+    }
+}
+
+#[derive(clap::Args, Debug)]
+pub struct FmtArgs {
+    /// Numbat source files to format. If none are given, the code is read
+    /// from standard input and the formatted code is written to standard output.
+    files: Vec<PathBuf>,
+
+    /// Overwrite the files with the formatted code instead of printing it.
+    #[arg(short, long, conflicts_with = "check")]
+    write: bool,
+
+    /// Do not print the formatted code, but fail if any of the inputs is not
+    /// formatted canonically.
+    #[arg(long)]
+    check: bool,
+
+    /// Which symbol to use for explicit multiplications.
+    #[arg(long, value_name = "OPERATOR", default_value = "preserve")]
+    multiplication_operator: MultiplicationOperatorArg,
+}
+
+fn print_diagnostic(name: String, code: String, error: impl ErrorDiagnostic) {
+    let mut files = SimpleFiles::new();
+    files.add(name, code);
+
+    let writer = StandardStream::stderr(ColorChoice::Auto);
+    let config = Config::default();
+
+    let mut writer = writer.lock();
+    for diagnostic in error.diagnostics() {
+        term::emit(&mut writer, &config, &files, &diagnostic).unwrap();
+    }
+}
+
+pub fn run(args: FmtArgs) -> Result<ExitStatus> {
+    let options = FormatOptions {
+        multiplication_operator: args.multiplication_operator.into(),
+    };
+
+    let inputs = if args.files.is_empty() {
+        let mut code = String::new();
+        std::io::stdin()
+            .read_to_string(&mut code)
+            .context("Could not read from standard input")?;
+        vec![(None, code)]
+    } else {
+        args.files
+            .into_iter()
+            .map(|path| {
+                let code = std::fs::read_to_string(&path).context(format!(
+                    "Could not load source file '{}'",
+                    path.to_string_lossy()
+                ))?;
+                Ok((Some(path), code))
+            })
+            .collect::<Result<Vec<_>>>()?
+    };
+
+    let mut exit_status = ExitStatus::Success;
+
+    for (path, code) in inputs {
+        let name = path
+            .as_ref()
+            .map(|p| p.to_string_lossy().to_string())
+            .unwrap_or_else(|| "<stdin>".into());
+
+        let formatted = match format(&code, &options) {
+            Ok(formatted) => formatted,
+            Err(e) => {
+                print_diagnostic(name, code, e);
+                exit_status = ExitStatus::Error;
+                continue;
+            }
+        };
+
+        if args.check {
+            if formatted != code {
+                eprintln!("'{name}' is not formatted");
+                exit_status = ExitStatus::Error;
+            }
+        } else if let (true, Some(path)) = (args.write, &path) {
+            if formatted != code {
+                std::fs::write(path, formatted).context(format!("Could not write to '{name}'"))?;
+            }
+        } else {
+            print!("{formatted}");
+        }
+    }
+
+    Ok(exit_status)
+}
@@ -1,7 +1,12 @@
 mod ansi_formatter;
+mod audit;
 mod completer;
 mod config;
+mod debugger;
+mod doc;
+mod fmt;
 mod highlighter;
+mod test_runner;
 
 use ansi_formatter::ansi_format;
 use colored::control::SHOULD_COLORIZE;
@@ -12,25 +17,29 @@ use highlighter::NumbatHighlighter;
 use itertools::Itertools;
 use numbat::command::{CommandControlFlow, CommandRunner};
 use numbat::compact_str::CompactString;
+use numbat::debugger::Debugger;
 use numbat::diagnostic::ErrorDiagnostic;
 use numbat::markup as m;
 use numbat::module_importer::{BuiltinModuleImporter, ChainedImporter, FileSystemImporter};
 use numbat::pretty_print::PrettyPrint;
 use numbat::resolver::CodeSource;
 use numbat::session_history::{ParseEvaluationResult, SessionHistory};
+use numbat::value::Value;
+use numbat::warning::WarningCategory;
 use numbat::{Context, NumbatError};
-use numbat::{InterpreterSettings, NameResolutionError};
+use numbat::{InterpreterSettings, NameResolutionError, RuntimeError};
 
 use anyhow::{bail, Context as AnyhowContext, Result};
-use clap::Parser;
+use clap::{Parser, Subcommand, ValueEnum};
 use rustyline::config::Configurer;
 use rustyline::{
     error::ReadlineError, history::DefaultHistory, Completer, Editor, Helper, Hinter, Validator,
 };
 use rustyline::{EventHandler, Highlighter, KeyCode, KeyEvent, Modifiers};
 
-use std::io::IsTerminal;
+use std::io::{BufRead, IsTerminal};
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 use std::{fs, thread};
 
@@ -38,17 +47,39 @@ use std::{fs, thread};
 pub enum ExitStatus {
     Success,
     Error,
+    /// The program called `exit` with the given exit code
+    Code(i32),
 }
 
 type ControlFlow = std::ops::ControlFlow<ExitStatus>;
 
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Format Numbat source files.
+    Fmt(fmt::FmtArgs),
+    /// Run the tests of Numbat projects.
+    Test(test_runner::TestArgs),
+    /// Generate documentation for a tree of Numbat modules.
+    Doc(doc::DocArgs),
+    /// List unit identifiers that could also be read as a prefix and a different unit.
+    AuditUnits(audit::AuditUnitsArgs),
+}
+
 #[derive(Parser, Debug)]
 #[command(version, about, name("numbat"), max_term_width = 90)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// Path to source file with Numbat code. If none is given, an interactive
     /// session is started.
     file: Option<PathBuf>,
 
+    /// Arguments that are passed to the script. They can be accessed with the
+    /// 'args' function from the 'extra::scripting' module.
+    #[arg(requires = "file", trailing_var_arg = true, allow_hyphen_values = true)]
+    arguments: Vec<String>,
+
     /// Evaluate a single expression. Can be specified multiple times to evaluate several expressions in sequence.
     #[arg(
         short,
@@ -62,6 +93,32 @@ struct Args {
     #[arg(short, long)]
     inspect_interactively: bool,
 
+    /// Read code from standard input and evaluate it line by line, printing one
+    /// plain result per line. Evaluation continues after errors, but the exit
+    /// status is non-zero if any line failed.
+    #[arg(long, conflicts_with_all = ["inspect_interactively", "debug_step"])]
+    filter: bool,
+
+    /// Only parse and type check the given file or expressions, without evaluating
+    /// them. Errors are reported as usual, and the exit status is non-zero if any
+    /// were found.
+    #[arg(
+        long,
+        conflicts_with_all = ["inspect_interactively", "debug_step", "filter", "profile", "profile_folded"]
+    )]
+    check: bool,
+
+    /// Do not report warnings of the given categories. Use 'all' to turn off all
+    /// warnings.
+    #[arg(
+        long,
+        value_name = "CATEGORY",
+        value_delimiter = ',',
+        value_parser = warning_category_names(),
+        action = clap::ArgAction::Append
+    )]
+    suppress_warnings: Vec<String>,
+
     /// Do not load the user configuration file.
     #[arg(long, hide_short_help = true)]
     no_config: bool,
@@ -74,10 +131,19 @@ struct Args {
     #[arg(long, hide_short_help = true)]
     no_init: bool,
 
+    /// Do not read or write the cached state after loading the prelude.
+    #[arg(long, hide_short_help = true)]
+    no_prelude_cache: bool,
+
     /// Whether or not to pretty-print every input expression.
     #[arg(long, value_name = "WHEN")]
     pretty_print: Option<PrettyPrintMode>,
 
+    /// Output format for results. With 'json', one JSON object is printed per
+    /// line for each evaluated expression, printed output, and error.
+    #[arg(long, value_name = "FORMAT", default_value = "text")]
+    output: OutputFormat,
+
     /// Whether or not coloring should occur.
     #[arg(long, value_name = "WHEN")]
     color: Option<ColorMode>,
@@ -93,6 +159,27 @@ struct Args {
     /// Turn on debug mode and print disassembler output (hidden, mainly for development)
     #[arg(long, short, hide = true)]
     debug: bool,
+
+    /// Run the given file in an interactive step debugger.
+    #[arg(long, requires = "file")]
+    debug_step: bool,
+
+    /// Measure the time spent in each function and print a report to stderr.
+    #[arg(long)]
+    profile: bool,
+
+    /// Write profiling data to the given file, using the folded-stacks format
+    /// that is understood by flamegraph tools.
+    #[arg(long, value_name = "FILE")]
+    profile_folded: Option<PathBuf>,
+}
+
+fn warning_category_names() -> Vec<&'static str> {
+    WarningCategory::ALL
+        .iter()
+        .map(|category| category.name())
+        .chain(["all"])
+        .collect()
 }
 
 struct ParseEvaluationOutcome {
@@ -100,10 +187,18 @@ struct ParseEvaluationOutcome {
     result: ParseEvaluationResult,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+enum OutputFormat {
+    Text,
+    Json,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 enum ExecutionMode {
     Normal,
     Interactive,
+    /// Evaluate lines from standard input independently (`--filter`)
+    Filter,
 }
 
 impl ExecutionMode {
@@ -128,22 +223,43 @@ struct Cli {
     config: Config,
     context: Arc<Mutex<Context>>,
     file: Option<PathBuf>,
+    /// Command-line arguments that are passed to the script
+    arguments: Vec<CompactString>,
     expression: Option<Vec<String>>,
+    debug_step: bool,
+
+    /// A debugger that will be attached to the next evaluation
+    debugger: Option<Debugger>,
+
+    profile: bool,
+    profile_folded: Option<PathBuf>,
+
+    /// Set by the SIGINT handler in interactive mode to stop the current evaluation
+    interrupt: Arc<AtomicBool>,
+
+    output: OutputFormat,
+
+    filter: bool,
+
+    /// Only type check the given code, without evaluating it (`--check`)
+    check: bool,
 }
 
 impl Cli {
-    fn make_fresh_context() -> Context {
+    fn make_importer() -> ChainedImporter {
         let mut fs_importer = FileSystemImporter::default();
         for path in Self::get_modules_paths() {
             fs_importer.add_path(path);
         }
 
-        let importer = ChainedImporter::new(
+        ChainedImporter::new(
             Box::new(fs_importer),
             Box::<BuiltinModuleImporter>::default(),
-        );
+        )
+    }
 
-        let mut context = Context::new(importer);
+    fn make_fresh_context() -> Context {
+        let mut context = Context::new(Self::make_importer());
 
         context.set_terminal_width(
             terminal_size::terminal_size().map(|(terminal_size::Width(w), _)| w as usize),
@@ -168,13 +284,33 @@ impl Cli {
 
         config.load_prelude &= !args.no_prelude;
         config.load_user_init &= !(args.no_prelude || args.no_init);
+        config.prelude_cache &= !(args.no_prelude_cache || args.debug);
 
         config.intro_banner = args.intro_banner.unwrap_or(config.intro_banner);
         config.pretty_print = args.pretty_print.unwrap_or(config.pretty_print);
-        config.color = args.color.unwrap_or(config.color);
+        config.color = args.color.unwrap_or(if args.filter {
+            ColorMode::Never
+        } else {
+            config.color
+        });
+
+        for name in &args.suppress_warnings {
+            if name == "all" {
+                config.suppressed_warnings = WarningCategory::ALL.to_vec();
+            } else if let Ok(category) = name.parse() {
+                if !config.suppressed_warnings.contains(&category) {
+                    config.suppressed_warnings.push(category);
+                }
+            }
+        }
+
+        if args.check && args.file.is_none() && args.expression.is_none() {
+            bail!("--check requires a file or an expression");
+        }
 
         config.enter_repl =
-            (args.file.is_none() && args.expression.is_none()) || args.inspect_interactively;
+            (args.file.is_none() && args.expression.is_none() && !args.filter && !args.check)
+                || args.inspect_interactively;
 
         let mut context = Self::make_fresh_context();
         context.set_debug(args.debug);
@@ -183,11 +319,24 @@ impl Cli {
             context: Arc::new(Mutex::new(context)),
             config,
             file: args.file,
+            arguments: args
+                .arguments
+                .into_iter()
+                .map(CompactString::from)
+                .collect(),
             expression: args.expression,
+            debug_step: args.debug_step,
+            debugger: None,
+            profile: args.profile,
+            profile_folded: args.profile_folded,
+            interrupt: Arc::new(AtomicBool::new(false)),
+            output: args.output,
+            filter: args.filter,
+            check: args.check,
         })
     }
 
-    fn run(&mut self) -> Result<()> {
+    fn run(&mut self) -> Result<ExitStatus> {
         // Enabled ANSI colors on Windows 10
         #[cfg(windows)]
         colored::control::set_virtual_terminal(true).unwrap();
@@ -197,8 +346,18 @@ impl Cli {
             ColorMode::Always => SHOULD_COLORIZE.set_override(true),
             ColorMode::Auto => (), // Let colored itself decide whether coloring should occur or not
         }
+        self.configure_diagnostics(&mut self.context.lock().unwrap());
 
-        if self.config.load_prelude {
+        if self.config.load_prelude && self.config.lazy_prelude {
+            let result = self
+                .context
+                .lock()
+                .unwrap()
+                .load_module_on_demand("prelude");
+            if let Err(e) = result {
+                bail!("Could not load the prelude: {e}")
+            }
+        } else if self.config.load_prelude && !self.load_prelude_from_cache() {
             let result = self.parse_and_evaluate(
                 "use prelude",
                 CodeSource::Internal,
@@ -208,6 +367,8 @@ impl Cli {
             if result.control_flow.is_break() {
                 bail!("Interpreter error in Prelude code")
             }
+
+            self.write_prelude_cache();
         }
 
         if self.config.load_user_init {
@@ -235,6 +396,11 @@ impl Cli {
                 .load_currency_module_on_demand(true);
         }
 
+        // Profiling starts after the prelude and the user init file have been loaded
+        if self.profile || self.profile_folded.is_some() {
+            self.context.lock().unwrap().set_profiling(true);
+        }
+
         let mut code_and_source = Vec::new();
 
         if let Some(ref path) = self.file {
@@ -255,15 +421,34 @@ impl Cli {
 
         if !code_and_source.is_empty() {
             for (code, code_source) in code_and_source {
-                let result = self.parse_and_evaluate(
-                    &code,
-                    code_source,
-                    ExecutionMode::Normal,
-                    self.config.pretty_print,
-                );
+                if self.debug_step && matches!(code_source, CodeSource::File(_)) {
+                    let code_source_id = self
+                        .context
+                        .lock()
+                        .unwrap()
+                        .resolver()
+                        .next_code_source_id();
+                    self.debugger = Some(Debugger::new(debugger::CliDebugHandler::new(
+                        code_source_id,
+                        code.clone(),
+                    )));
+                }
+
+                // Check all inputs, such that all errors are reported
+                if self.check {
+                    let result = self.check_input(&code, code_source);
+                    run_result = run_result.and(result);
+                    continue;
+                }
+
+                let result = self.evaluate_input(&code, code_source, ExecutionMode::Normal);
 
                 let result_status = match result.control_flow {
                     std::ops::ControlFlow::Continue(()) => Ok(()),
+                    std::ops::ControlFlow::Break(ExitStatus::Code(code)) => {
+                        self.write_profile()?;
+                        return Ok(ExitStatus::Code(code));
+                    }
                     std::ops::ControlFlow::Break(_) => {
                         bail!("Interpreter stopped")
                     }
@@ -273,6 +458,16 @@ impl Cli {
             }
         }
 
+        if self.filter {
+            match self.filter() {
+                Ok(ExitStatus::Code(code)) => {
+                    self.write_profile()?;
+                    return Ok(ExitStatus::Code(code));
+                }
+                result => run_result = run_result.and(result.map(|_| ())),
+            }
+        }
+
         if self.config.enter_repl {
             let mut currency_fetch_thread = if self.config.load_prelude
                 && self.config.exchange_rates.fetching_policy
@@ -289,13 +484,88 @@ impl Cli {
             if let Some(thread) = currency_fetch_thread.take() {
                 let _ = thread.join();
             }
-            run_result = run_result.and(repl_result);
+            match repl_result {
+                Ok(ExitStatus::Code(code)) => {
+                    self.write_profile()?;
+                    return Ok(ExitStatus::Code(code));
+                }
+                result => run_result = run_result.and(result.map(|_| ())),
+            }
         }
 
-        run_result
+        self.write_profile()?;
+
+        run_result.map(|()| ExitStatus::Success)
     }
 
-    fn repl(&mut self) -> Result<()> {
+    fn write_profile(&self) -> Result<()> {
+        let ctx = self.context.lock().unwrap();
+        let Some(profiler) = ctx.profiler() else {
+            return Ok(());
+        };
+
+        if self.profile {
+            eprint!("{}", ansi_format(&profiler.report(), false));
+        }
+
+        if let Some(path) = &self.profile_folded {
+            fs::write(path, profiler.folded_stacks()).context(format!(
+                "Could not write profiling data to '{}'",
+                path.to_string_lossy()
+            ))?;
+        }
+
+        Ok(())
+    }
+
+    fn filter(&mut self) -> Result<ExitStatus> {
+        let mut success = true;
+
+        for line in std::io::stdin().lock().lines() {
+            let line = line.context("Error while reading from standard input")?;
+            let outcome = self.evaluate_input(&line, CodeSource::Text, ExecutionMode::Filter);
+            if let std::ops::ControlFlow::Break(ExitStatus::Code(code)) = outcome.control_flow {
+                return Ok(ExitStatus::Code(code));
+            }
+            success &= outcome.result.is_ok();
+        }
+
+        if !success {
+            bail!("Some lines could not be evaluated")
+        }
+
+        Ok(ExitStatus::Success)
+    }
+
+    /// Parse and type check the given code, but do not evaluate it.
+    fn check_input(&mut self, input: &str, code_source: CodeSource) -> Result<()> {
+        let mut ctx = self.context.lock().unwrap();
+        let result = ctx.check(input, code_source);
+        Self::print_warnings(&mut ctx, self.output);
+        let Err(error) = result else {
+            return Ok(());
+        };
+
+        match self.output {
+            OutputFormat::Text => match *error {
+                NumbatError::ResolverError(e) => ctx.print_diagnostic(e),
+                NumbatError::NameResolutionError(e) => ctx.print_diagnostic(e),
+                NumbatError::TypeCheckError(e) => ctx.print_diagnostic(e),
+                NumbatError::RuntimeError(e) => ctx.print_diagnostic(e),
+            },
+            OutputFormat::Json => {
+                let object = serde_json::json!({
+                    "error": error.to_string(),
+                    "diagnostics": ctx.json_diagnostics(&*error),
+                });
+                println!("{object}");
+            }
+        }
+
+        bail!("Check failed")
+    }
+
+    fn repl(&mut self) -> Result<ExitStatus> {
         let interactive = std::io::stdin().is_terminal();
         let history_path = self.get_history_path()?;
 
@@ -322,6 +592,12 @@ impl Cli {
         );
         rl.load_history(&history_path).ok();
 
+        // Ctrl-C while an evaluation is running returns to the prompt instead of
+        // terminating the session. While reading input, it is handled by rustyline.
+        let interrupt = self.interrupt.clone();
+        ctrlc::set_handler(move || interrupt.store(true, Ordering::Relaxed))
+            .context("Error while setting up the Ctrl-C handler")?;
+
         if interactive {
             match self.config.intro_banner {
                 IntroBanner::Long => {
@@ -359,7 +635,7 @@ impl Cli {
         &mut self,
         rl: &mut Editor<NumbatHelper, DefaultHistory>,
         interactive: bool,
-    ) -> Result<()> {
+    ) -> Result<ExitStatus> {
         let mut cmd_runner = CommandRunner::<Editor<NumbatHelper, DefaultHistory>>::new()
             .print_with(|m| println!("{}", ansi_format(m, true)))
             .enable_clear(|rl| match rl.clear_screen() {
@@ -384,7 +660,7 @@ impl Cli {
                     match cmd_runner.try_run_command(&line, &mut ctx, rl) {
                         Ok(cf) => match cf {
                             CommandControlFlow::Continue => continue,
-                            CommandControlFlow::Return => return Ok(()),
+                            CommandControlFlow::Return => return Ok(ExitStatus::Success),
                             CommandControlFlow::NotACommand => {}
                         },
                         Err(err) => {
@@ -397,7 +673,7 @@ impl Cli {
                     let ParseEvaluationOutcome {
                         control_flow,
                         result,
-                    } = self.parse_and_evaluate(
+                    } = self.evaluate_input(
                         &line,
                         CodeSource::Text,
                         if interactive {
@@ -405,24 +681,26 @@ impl Cli {
                         } else {
                             ExecutionMode::Normal
                         },
-                        self.config.pretty_print,
                     );
 
                     match control_flow {
                         std::ops::ControlFlow::Continue(()) => {}
                         std::ops::ControlFlow::Break(ExitStatus::Success) => {
-                            return Ok(());
+                            return Ok(ExitStatus::Success);
                         }
                         std::ops::ControlFlow::Break(ExitStatus::Error) => {
                             bail!("Interpreter stopped due to error")
                         }
+                        std::ops::ControlFlow::Break(ExitStatus::Code(code)) => {
+                            return Ok(ExitStatus::Code(code));
+                        }
                     }
 
                     cmd_runner.push_to_history(&line, result);
                 }
                 Err(ReadlineError::Interrupted) => {}
                 Err(ReadlineError::Eof) => {
-                    return Ok(());
+                    return Ok(ExitStatus::Success);
                 }
                 Err(err) => {
                     bail!(err);
@@ -431,6 +709,107 @@ impl Cli {
         }
     }
 
+    /// Evaluate code that has been entered by the user, in the selected output format.
+    fn evaluate_input(
+        &mut self,
+        input: &str,
+        code_source: CodeSource,
+        execution_mode: ExecutionMode,
+    ) -> ParseEvaluationOutcome {
+        match self.output {
+            OutputFormat::Text => self.parse_and_evaluate(
+                input,
+                code_source,
+                execution_mode,
+                self.config.pretty_print,
+            ),
+            OutputFormat::Json => self.parse_and_evaluate_json(input, code_source, execution_mode),
+        }
+    }
+
+    /// Like [`Self::parse_and_evaluate`], but prints one JSON object per line: one for each
+    /// evaluated expression statement, one for each piece of printed output, and one for
+    /// an error, if any. Objects are printed in the order in which they occur.
+    #[must_use]
+    fn parse_and_evaluate_json(
+        &mut self,
+        input: &str,
+        code_source: CodeSource,
+        execution_mode: ExecutionMode,
+    ) -> ParseEvaluationOutcome {
+        enum Event {
+            Output(m::Markup),
+            Value(Value),
+        }
+
+        let events: Arc<Mutex<Vec<Event>>> = Arc::new(Mutex::new(vec![]));
+        let events_print = events.clone();
+        let events_result = events.clone();
+
+        let mut read_stdin = InterpreterSettings::default().read_line_fn;
+
+        let mut settings = InterpreterSettings {
+            print_fn: Box::new(move |s: &m::Markup| {
+                events_print.lock().unwrap().push(Event::Output(s.clone()));
+            }),
+            result_fn: Some(Box::new(move |v: &Value| {
+                events_result.lock().unwrap().push(Event::Value(v.clone()));
+            })),
+            // Keep standard output reserved for JSON objects
+            read_line_fn: Box::new(move |prompt: &str| {
+                eprint!("{prompt}");
+                read_stdin("")
+            }),
+            arguments: self.arguments.clone(),
+            debugger: self.debugger.take(),
+            interrupt: Some(self.interrupt.clone()),
+            ..Default::default()
+        };
+
+        self.interrupt.store(false, Ordering::Relaxed);
+        let mut ctx = self.context.lock().unwrap();
+        let interpretation_result = ctx.interpret_with_settings(&mut settings, input, code_source);
+        Self::print_warnings(&mut ctx, OutputFormat::Json);
+
+        let (statements, error) = match interpretation_result {
+            Ok((statements, _)) => (statements, None),
+            Err(error) => (vec![], Some(error)),
+        };
+        let mut expressions = statements.iter().filter(|s| s.as_expression().is_some());
+
+        for event in events.lock().unwrap().drain(..) {
+            let object = match event {
+                Event::Output(output) => serde_json::json!({ "output": output.to_string() }),
+                Event::Value(value) => {
+                    serde_json::json!(ctx.json_result(expressions.next(), &value))
+                }
+            };
+            println!("{object}");
+        }
+
+        let (control_flow, result) = match error {
+            None => (ControlFlow::Continue(()), Ok(())),
+            Some(error) => match *error {
+                NumbatError::RuntimeError(RuntimeError::Exit(code)) => {
+                    (ControlFlow::Break(ExitStatus::Code(code)), Err(()))
+                }
+                error => {
+                    let object = serde_json::json!({
+                        "error": error.to_string(),
+                        "diagnostics": ctx.json_diagnostics(&error),
+                    });
+                    println!("{object}");
+                    (execution_mode.exit_status_in_case_of_error(), Err(()))
+                }
+            },
+        };
+
+        ParseEvaluationOutcome {
+            control_flow,
+            result,
+        }
+    }
+
     #[must_use]
     fn parse_and_evaluate(
         &mut self,
@@ -441,17 +820,41 @@ impl Cli {
     ) -> ParseEvaluationOutcome {
         let to_be_printed: Arc<Mutex<Vec<m::Markup>>> = Arc::new(Mutex::new(vec![]));
         let to_be_printed_c = to_be_printed.clone();
+        let to_be_printed_r = to_be_printed.clone();
+        let mut read_stdin = InterpreterSettings::default().read_line_fn;
+
+        // When debugging, we print output immediately instead of after the evaluation.
+        let debugger = self.debugger.take();
+        let print_immediately = debugger.is_some();
+
         let mut settings = InterpreterSettings {
             print_fn: Box::new(move |s: &m::Markup| {
-                to_be_printed_c.lock().unwrap().push(s.clone());
+                if print_immediately {
+                    println!("{}", ansi_format(s, false));
+                } else {
+                    to_be_printed_c.lock().unwrap().push(s.clone());
+                }
             }),
+            read_line_fn: Box::new(move |prompt: &str| {
+                // Show everything that was printed so far before asking for input
+                for s in to_be_printed_r.lock().unwrap().drain(..) {
+                    println!("{}", ansi_format(&s, false));
+                }
+                read_stdin(prompt)
+            }),
+            arguments: self.arguments.clone(),
+            debugger,
+            interrupt: Some(self.interrupt.clone()),
+            ..Default::default()
         };
 
-        let interpretation_result =
-            self.context
-                .lock()
-                .unwrap()
-                .interpret_with_settings(&mut settings, input, code_source);
+        self.interrupt.store(false, Ordering::Relaxed);
+        let interpretation_result = {
+            let mut ctx = self.context.lock().unwrap();
+            let result = ctx.interpret_with_settings(&mut settings, input, code_source);
+            Self::print_warnings(&mut ctx, OutputFormat::Text);
+            result
+        };
 
         let interactive = execution_mode == ExecutionMode::Interactive;
 
@@ -502,6 +905,14 @@ impl Cli {
                     println!();
                 }
 
+                // Keep the output aligned with the input, at least one line per line
+                if execution_mode == ExecutionMode::Filter
+                    && !interpreter_result.is_value()
+                    && to_be_printed.is_empty()
+                {
+                    println!();
+                }
+
                 ControlFlow::Continue(())
             }
             Err(NumbatError::ResolverError(e)) => {
@@ -519,12 +930,22 @@ impl Cli {
                 self.print_diagnostic(e);
                 execution_mode.exit_status_in_case_of_error()
             }
+            Err(NumbatError::RuntimeError(RuntimeError::Exit(code))) => {
+                for s in to_be_printed.lock().unwrap().iter() {
+                    println!("{}", ansi_format(s, interactive));
+                }
+                ControlFlow::Break(ExitStatus::Code(code))
+            }
             Err(NumbatError::RuntimeError(e)) => {
                 self.print_diagnostic(e);
                 execution_mode.exit_status_in_case_of_error()
             }
         };
 
+        if execution_mode == ExecutionMode::Filter && parse_eval_result.is_err() {
+            println!();
+        }
+
         ParseEvaluationOutcome {
             control_flow,
             result: parse_eval_result,
@@ -535,11 +956,84 @@ impl Cli {
         self.context.lock().unwrap().print_diagnostic(error)
     }
 
+    fn configure_diagnostics(&self, ctx: &mut Context) {
+        ctx.set_colored_diagnostics(self.config.color != ColorMode::Never);
+        for category in &self.config.suppressed_warnings {
+            ctx.set_warning_enabled(*category, false);
+        }
+    }
+
+    /// Print the warnings that were found while running or checking code.
+    fn print_warnings(ctx: &mut Context, output: OutputFormat) {
+        for warning in ctx.take_warnings() {
+            match output {
+                OutputFormat::Text => ctx.print_diagnostic(warning),
+                OutputFormat::Json => {
+                    let object = serde_json::json!({
+                        "warning": warning.to_string(),
+                        "diagnostics": ctx.json_diagnostics(&warning),
+                    });
+                    println!("{object}");
+                }
+            }
+        }
+    }
+
     fn get_config_path() -> PathBuf {
         let config_dir = dirs::config_dir().unwrap_or_else(|| PathBuf::from("."));
         config_dir.join("numbat")
     }
 
+    fn get_prelude_cache_path() -> Option<PathBuf> {
+        Some(dirs::cache_dir()?.join("numbat").join("prelude.snapshot"))
+    }
+
+    /// Restore the state after loading the prelude from a snapshot on disk. The
+    /// snapshot is rejected if it was created by a different version of Numbat or
+    /// if one of the modules has been modified since.
+    fn load_prelude_from_cache(&mut self) -> bool {
+        if !self.config.prelude_cache {
+            return false;
+        }
+
+        let Some(snapshot) = Self::get_prelude_cache_path().and_then(|path| fs::read(path).ok())
+        else {
+            return false;
+        };
+        let Ok(mut context) = Context::from_snapshot(&snapshot, Self::make_importer()) else {
+            return false;
+        };
+
+        context.set_terminal_width(
+            terminal_size::terminal_size().map(|(terminal_size::Width(w), _)| w as usize),
+        );
+        self.configure_diagnostics(&mut context);
+        *self.context.lock().unwrap() = context;
+
+        true
+    }
+
+    fn write_prelude_cache(&self) {
+        let Some(path) = Self::get_prelude_cache_path().filter(|_| self.config.prelude_cache)
+        else {
+            return;
+        };
+
+        let snapshot = self.context.lock().unwrap().snapshot();
+
+        // Write to a temporary file first, such that other instances of numbat
+        // never read an incomplete snapshot.
+        let temp_path = path.with_extension(format!("{}.tmp", std::process::id()));
+        let written = path
+            .parent()
+            .is_some_and(|dir| fs::create_dir_all(dir).is_ok())
+            && fs::write(&temp_path, snapshot).is_ok()
+            && fs::rename(&temp_path, &path).is_ok();
+        if !written {
+            fs::remove_file(&temp_path).ok();
+        }
+    }
+
     fn get_modules_paths() -> Vec<PathBuf> {
         let mut paths = vec![];
 
@@ -608,6 +1102,24 @@ fn generate_config() -> Result<()> {
 fn main() {
     let args = Args::parse();
 
+    if let Some(command) = args.command {
+        let result = match command {
+            Command::Fmt(fmt_args) => fmt::run(fmt_args),
+            Command::Test(test_args) => test_runner::run(test_args),
+            Command::Doc(doc_args) => doc::run(doc_args).map(|_| ExitStatus::Success),
+            Command::AuditUnits(audit_args) => audit::run(audit_args),
+        };
+        match result {
+            Ok(ExitStatus::Success) => std::process::exit(0),
+            Ok(ExitStatus::Error) => std::process::exit(1),
+            Ok(ExitStatus::Code(code)) => std::process::exit(code),
+            Err(e) => {
+                eprintln!("{e:#}");
+                std::process::exit(1);
+            }
+        }
+    }
+
     if args.generate_config {
         if let Err(e) = generate_config() {
             eprintln!("{e:#}");
@@ -616,8 +1128,12 @@ fn main() {
         std::process::exit(0);
     }
 
-    if let Err(e) = Cli::new(args).and_then(|mut cli| cli.run()) {
-        eprintln!("{e:#}");
-        std::process::exit(1);
+    match Cli::new(args).and_then(|mut cli| cli.run()) {
+        Ok(ExitStatus::Code(code)) => std::process::exit(code),
+        Ok(_) => {}
+        Err(e) => {
+            eprintln!("{e:#}");
+            std::process::exit(1);
+        }
     }
 }
@@ -0,0 +1,266 @@
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, Context as AnyhowContext, Result};
+use colored::control::SHOULD_COLORIZE;
+use colored::Colorize;
+use numbat::compact_str::CompactString;
+use numbat::module_importer::{BuiltinModuleImporter, ChainedImporter, FileSystemImporter};
+use numbat::resolver::CodeSource;
+use numbat::value::Value;
+use numbat::{Context, InterpreterResult, InterpreterSettings, NumbatError};
+
+use crate::{Cli, ExitStatus};
+
+#[derive(clap::Args, Debug)]
+pub struct TestArgs {
+    /// Directories to search for tests, or test files. Defaults to the current directory.
+    paths: Vec<PathBuf>,
+
+    /// Only run tests whose name contains the given string.
+    #[arg(short, long, value_name = "PATTERN")]
+    filter: Option<String>,
+}
+
+/// A file with tests. Files without `@test` functions are run as a single test.
+struct TestFile {
+    path: PathBuf,
+    code: String,
+}
+
+struct TestCase<'a> {
+    name: String,
+    file: &'a TestFile,
+    /// The `@test` function to call after running the file
+    function: Option<CompactString>,
+}
+
+fn is_test_file_name(path: &Path) -> bool {
+    let Some(stem) = path.file_stem().and_then(|s| s.to_str()) else {
+        return false;
+    };
+    path.extension().is_some_and(|e| e == "nbt")
+        && (stem.starts_with("test_") || stem.ends_with("_test"))
+}
+
+/// Finds `*.nbt` files that are named like `test_*.nbt` or `*_test.nbt`, or that
+/// contain `@test` functions.
+fn discover(path: &Path, files: &mut Vec<TestFile>) -> Result<()> {
+    let read_error = || format!("Could not read '{}'", path.to_string_lossy());
+
+    if path.is_dir() {
+        let mut entries = std::fs::read_dir(path)
+            .with_context(read_error)?
+            .map(|entry| entry.map(|e| e.path()))
+            .collect::<std::io::Result<Vec<_>>>()
+            .with_context(read_error)?;
+        entries.sort();
+
+        for entry in entries {
+            if entry.is_dir() || entry.extension().is_some_and(|e| e == "nbt") {
+                discover(&entry, files)?;
+            }
+        }
+    } else {
+        let code = std::fs::read_to_string(path).with_context(read_error)?;
+        if is_test_file_name(path) || code.contains("@test") {
+            files.push(TestFile {
+                path: path.to_owned(),
+                code,
+            });
+        }
+    }
+
+    Ok(())
+}
+
+enum Failure {
+    Error(Box<Context>, Box<NumbatError>),
+    /// A test function returned `false`
+    ReturnedFalse,
+    /// A test function returned a value that is not a boolean
+    ReturnedNonBoolean(String),
+}
+
+/// Runs a test file in a copy of the base context, and calls the given test function.
+/// Test functions pass if they return `true` (or no value) without errors.
+fn run_test(
+    base_context: &Context,
+    file: &TestFile,
+    function: Option<&str>,
+) -> std::result::Result<Context, Failure> {
+    let mut ctx = base_context.clone();
+    let mut settings = InterpreterSettings {
+        print_fn: Box::new(|_| {}),
+        ..Default::default()
+    };
+
+    let result = ctx
+        .interpret_with_settings(
+            &mut settings,
+            &file.code,
+            CodeSource::File(file.path.clone()),
+        )
+        .and_then(|_| match function {
+            Some(function) => ctx
+                .interpret_with_settings(
+                    &mut settings,
+                    &format!("{function}()"),
+                    CodeSource::Internal,
+                )
+                .map(|(_, result)| match result {
+                    InterpreterResult::Value(value) => Some(value),
+                    InterpreterResult::Continue => None,
+                }),
+            None => Ok(None),
+        });
+
+    match result {
+        Ok(Some(Value::Boolean(false))) => Err(Failure::ReturnedFalse),
+        Ok(Some(Value::Boolean(true)) | None) => Ok(ctx),
+        Ok(Some(value)) => Err(Failure::ReturnedNonBoolean(value.to_string())),
+        Err(e) => Err(Failure::Error(Box::new(ctx), e)),
+    }
+}
+
+fn print_failure(failure: Failure) {
+    match failure {
+        Failure::Error(ctx, error) => match *error {
+            NumbatError::ResolverError(e) => ctx.print_diagnostic(e),
+            NumbatError::NameResolutionError(e) => ctx.print_diagnostic(e),
+            NumbatError::TypeCheckError(e) => ctx.print_diagnostic(e),
+            NumbatError::RuntimeError(e) => ctx.print_diagnostic(e),
+        },
+        Failure::ReturnedFalse => eprintln!("{}: test function returned false", "error".red()),
+        Failure::ReturnedNonBoolean(value) => eprintln!(
+            "{}: test function returned '{value}' instead of a boolean",
+            "error".red()
+        ),
+    }
+}
+
+fn format_duration(duration: Duration) -> String {
+    format!("{:.2}s", duration.as_secs_f64())
+}
+
+pub fn run(args: TestArgs) -> Result<ExitStatus> {
+    let paths = if args.paths.is_empty() {
+        vec![PathBuf::from(".")]
+    } else {
+        args.paths
+    };
+
+    let mut files = vec![];
+    for path in &paths {
+        if !path.exists() {
+            bail!("'{}' does not exist", path.to_string_lossy());
+        }
+        discover(path, &mut files)?;
+    }
+
+    // Modules can be imported relative to the given directories
+    let mut fs_importer = FileSystemImporter::default();
+    for path in paths.iter().chain(&Cli::get_modules_paths()) {
+        fs_importer.add_path(if path.is_dir() {
+            path
+        } else {
+            path.parent().unwrap_or(Path::new("."))
+        });
+    }
+    let importer = ChainedImporter::new(
+        Box::new(fs_importer),
+        Box::<BuiltinModuleImporter>::default(),
+    );
+
+    let mut base_context = Context::new(importer);
+    base_context.set_colored_diagnostics(SHOULD_COLORIZE.should_colorize());
+    if let Err(e) = base_context.interpret("use prelude", CodeSource::Internal) {
+        bail!("Could not load the prelude: {e}");
+    }
+
+    let start = Instant::now();
+
+    // Find out which files contain test functions. Files that can not be run are
+    // reported as a single failing test.
+    let mut tests = vec![];
+    for file in &files {
+        let name = file.path.to_string_lossy().into_owned();
+        let functions: Vec<_> = match run_test(&base_context, file, None) {
+            Ok(ctx) => ctx.test_functions().collect(),
+            Err(_) => vec![],
+        };
+
+        if functions.is_empty() {
+            tests.push(TestCase {
+                name,
+                file,
+                function: None,
+            });
+        } else {
+            tests.extend(functions.into_iter().map(|function| TestCase {
+                name: format!("{name}::{function}"),
+                file,
+                function: Some(function),
+            }));
+        }
+    }
+
+    let num_tests = tests.len();
+    if let Some(filter) = &args.filter {
+        tests.retain(|test| test.name.contains(filter.as_str()));
+    }
+    let num_filtered_out = num_tests - tests.len();
+
+    println!();
+    println!(
+        "running {} test{}",
+        tests.len(),
+        if tests.len() == 1 { "" } else { "s" }
+    );
+
+    let mut failures = vec![];
+    for test in &tests {
+        let test_start = Instant::now();
+        let result = run_test(&base_context, test.file, test.function.as_deref());
+        let elapsed = format_duration(test_start.elapsed());
+
+        match result {
+            Ok(_) => {
+                println!("test {} ... {} ({elapsed})", test.name, "ok".green());
+            }
+            Err(failure) => {
+                println!("test {} ... {} ({elapsed})", test.name, "FAILED".red());
+                print_failure(failure);
+                failures.push(&test.name);
+            }
+        }
+    }
+
+    if !failures.is_empty() {
+        println!();
+        println!("failures:");
+        for name in &failures {
+            println!("    {name}");
+        }
+    }
+
+    println!();
+    println!(
+        "test result: {}. {} passed; {} failed; {} filtered out; finished in {}",
+        if failures.is_empty() {
+            "ok".green()
+        } else {
+            "FAILED".red()
+        },
+        tests.len() - failures.len(),
+        failures.len(),
+        num_filtered_out,
+        format_duration(start.elapsed())
+    );
+
+    Ok(if failures.is_empty() {
+        ExitStatus::Success
+    } else {
+        ExitStatus::Error
+    })
+}
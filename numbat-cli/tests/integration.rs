@@ -14,6 +14,7 @@ fn numbat() -> Command {
     let mut cmd = Command::cargo_bin("numbat").unwrap();
     cmd.arg("--no-init");
     cmd.arg("--no-config");
+    cmd.arg("--no-prelude-cache");
     cmd
 }
 
@@ -77,6 +78,68 @@ fn read_code_from_file() {
         .stderr(predicates::str::contains("while parsing"));
 }
 
+#[test]
+fn report_all_type_errors() {
+    numbat()
+        .arg("tests/examples/type_errors.nbt")
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains(
+            "incompatible dimensions in variable definition",
+        ))
+        .stderr(predicates::str::contains(
+            "incompatible dimensions in function return type",
+        ))
+        .stderr(predicates::str::contains(
+            "Expected dimension type, got Bool instead",
+        ))
+        .stderr(predicates::str::contains("kg").not());
+}
+
+#[test]
+fn warnings() {
+    numbat()
+        .arg("--expression")
+        .arg("let pi = 3")
+        .assert()
+        .success()
+        .stderr(predicates::str::contains("Redefinition of constant 'pi'"));
+
+    numbat()
+        .arg("--suppress-warnings")
+        .arg("redefinition")
+        .arg("--expression")
+        .arg("let pi = 3")
+        .assert()
+        .success()
+        .stderr("");
+
+    numbat()
+        .arg("--suppress-warnings")
+        .arg("all")
+        .arg("--expression")
+        .arg("12345678901234567891")
+        .assert()
+        .success()
+        .stderr("");
+
+    numbat()
+        .arg("--check")
+        .arg("--expression")
+        .arg("12345678901234567891")
+        .assert()
+        .success()
+        .stderr(predicates::str::contains("precision-loss"));
+
+    numbat()
+        .arg("--suppress-warnings")
+        .arg("unknown")
+        .arg("--expression")
+        .arg("1")
+        .assert()
+        .failure();
+}
+
 #[test]
 fn process_code_from_file_and_cli_expression() {
     numbat()
@@ -106,6 +169,61 @@ fn print_calls() {
         ));
 }
 
+#[test]
+fn check_without_evaluating() {
+    numbat()
+        .arg("--check")
+        .arg("tests/examples/print.nbt")
+        .assert()
+        .success()
+        .stdout("");
+
+    numbat()
+        .arg("--check")
+        .arg("--expression")
+        .arg("error(\"not evaluated\")")
+        .assert()
+        .success()
+        .stdout("");
+
+    // Currency units can be used without fetching exchange rates
+    numbat()
+        .arg("--check")
+        .arg("--expression")
+        .arg("20 USD -> EUR")
+        .assert()
+        .success()
+        .stdout("");
+
+    numbat()
+        .arg("--check")
+        .arg("tests/examples/parser_error.nbt")
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains("while parsing"));
+
+    numbat()
+        .arg("--check")
+        .arg("--expression")
+        .arg("2 m + 3 s")
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains("incompatible dimensions"));
+
+    // Errors in all inputs are reported
+    numbat()
+        .arg("--check")
+        .arg("tests/examples/parser_error.nbt")
+        .arg("--expression")
+        .arg("2 m + 3 s")
+        .assert()
+        .failure()
+        .stderr(
+            predicates::str::contains("while parsing")
+                .and(predicates::str::contains("incompatible dimensions")),
+        );
+}
+
 #[test]
 fn without_prelude() {
     numbat()
@@ -138,6 +256,73 @@ fn pretty_printing() {
         ));
 }
 
+#[test]
+fn filter_mode() {
+    numbat()
+        .arg("--filter")
+        .write_stdin("3 ft -> cm\nlet x = 2 m\nx^2\n")
+        .assert()
+        .success()
+        .stdout("91.44 cm\n\n4 m²\n");
+
+    numbat()
+        .arg("--filter")
+        .write_stdin("print(\"hi\")\n1 + 1\n")
+        .assert()
+        .success()
+        .stdout("hi\n2\n");
+
+    numbat()
+        .arg("--filter")
+        .write_stdin("1 m + 1 s\n2 + 3\n")
+        .assert()
+        .failure()
+        .stdout("\n5\n")
+        .stderr(predicates::str::contains("while type checking"));
+}
+
+#[test]
+fn script_arguments_and_exit_code() {
+    numbat()
+        .arg("tests/examples/script.nbt")
+        .arg("first")
+        .write_stdin("Alice\n")
+        .assert()
+        .success()
+        .stdout("Name: Hello Alice, the arguments are [\"first\"]\n");
+
+    numbat()
+        .arg("tests/examples/script.nbt")
+        .arg("--")
+        .arg("test")
+        .arg("-v")
+        .write_stdin("Bob\n")
+        .assert()
+        .code(3)
+        .stdout("Name: Hello Bob, the arguments are [\"test\", \"-v\"]\n")
+        .stderr("");
+
+    numbat()
+        .arg("tests/examples/script.nbt")
+        .write_stdin("")
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains("Could not read a line of input"));
+
+    numbat()
+        .arg("--expression")
+        .arg("use extra::scripting")
+        .arg("--expression")
+        .arg("print(1)")
+        .arg("--expression")
+        .arg("exit(7)")
+        .arg("--expression")
+        .arg("print(2)")
+        .assert()
+        .code(7)
+        .stdout("1\n");
+}
+
 #[test]
 fn help_text() {
     numbat()
@@ -168,4 +353,197 @@ fn info_text() {
             predicates::str::contains("Round")
                 .and(predicates::str::contains("Round to the nearest integer.")),
         );
+
+    numbat()
+        .write_stdin("@deprecated(\"use 'meter'\") unit old_meter = 1 m\ninfo old_meter")
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("Deprecated: use 'meter'"));
+
+    numbat()
+        .write_stdin("info units::imperial")
+        .assert()
+        .success()
+        .stdout(
+            predicates::str::contains("Module: units::imperial")
+                .and(predicates::str::contains(
+                    "Description: Units of the imperial system",
+                ))
+                .and(predicates::str::contains("furlong").not())
+                .and(predicates::str::contains("fathom")),
+        );
+
+    numbat()
+        .write_stdin("list modules")
+        .assert()
+        .success()
+        .stdout(
+            predicates::str::contains("extra::cooking: (Inverse) densities")
+                .and(predicates::str::contains("gcd, lcm")),
+        );
+}
+
+#[test]
+fn format_source_code() {
+    numbat()
+        .arg("fmt")
+        .write_stdin("let x=2  m\nfn f(y)=x+y*y\n  where z=1\n\n\n")
+        .assert()
+        .success()
+        .stdout("let x = 2 m\nfn f(y) = x + y * y\n  where z = 1\n");
+
+    numbat()
+        .arg("fmt")
+        .arg("--multiplication-operator=times")
+        .write_stdin("2*3")
+        .assert()
+        .success()
+        .stdout("2 × 3\n");
+
+    numbat()
+        .arg("fmt")
+        .arg("--check")
+        .write_stdin("1+2\n")
+        .assert()
+        .failure();
+
+    numbat()
+        .arg("fmt")
+        .arg("--check")
+        .write_stdin("1 + 2\n")
+        .assert()
+        .success()
+        .stdout("");
+
+    numbat()
+        .arg("fmt")
+        .write_stdin("let x =")
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains("while parsing"));
+}
+
+#[test]
+fn run_tests() {
+    numbat()
+        .arg("test")
+        .arg("tests/examples/tests")
+        .assert()
+        .failure()
+        .stdout(
+            predicates::str::contains("running 5 tests")
+                .and(predicates::str::contains(
+                    "test tests/examples/tests/test_units.nbt ... ok",
+                ))
+                .and(predicates::str::contains(
+                    "geometry.nbt::circle_area_of_unit_circle ... ok",
+                ))
+                .and(predicates::str::contains(
+                    "geometry.nbt::circle_area_is_wrong ... FAILED",
+                ))
+                .and(predicates::str::contains(
+                    "geometry.nbt::circle_area_as_number ... FAILED",
+                ))
+                .and(predicates::str::contains("2 passed; 3 failed")),
+        )
+        .stderr(
+            predicates::str::contains("User error: not implemented").and(
+                predicates::str::contains("test function returned '3.14159' instead of a boolean"),
+            ),
+        );
+
+    numbat()
+        .arg("test")
+        .arg("tests/examples/tests")
+        .arg("--filter")
+        .arg("unit")
+        .assert()
+        .success()
+        .stdout(predicates::str::contains(
+            "2 passed; 0 failed; 3 filtered out",
+        ));
+}
+
+#[test]
+fn generate_documentation() {
+    let output = std::env::temp_dir().join(format!("numbat-doc-test-{}", std::process::id()));
+
+    numbat()
+        .arg("doc")
+        .arg("tests/examples/docs")
+        .arg("--output")
+        .arg(&output)
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("Documented 2 modules"));
+
+    let index = std::fs::read_to_string(output.join("index.md")).unwrap();
+    assert!(index.contains("- [`geometry::units`](geometry-units.md): 0 functions, 2 units"));
+
+    let circles = std::fs::read_to_string(output.join("geometry-circles.md")).unwrap();
+    assert!(circles.contains("### `circle_area` (Circle area)"));
+    assert!(circles
+        .contains("See also [`circle_circumference`](geometry-circles.md#circle_circumference)."));
+    assert!(circles.contains(">>> circle_area(2 m)\n\n    = 12.5664 m²    [Area]"));
+
+    let units = std::fs::read_to_string(output.join("geometry-units.md")).unwrap();
+    assert!(units.contains("# Module `geometry::units`\n\nAncient units of length and area."));
+    assert!(units.contains("### Area"));
+    assert!(units.contains("| `aroura`, `arouras` | An ancient Egyptian unit of area |"));
+
+    numbat()
+        .arg("doc")
+        .arg("tests/examples/docs")
+        .arg("--output")
+        .arg(&output)
+        .arg("--format=html")
+        .assert()
+        .success();
+
+    let circles = std::fs::read_to_string(output.join("geometry-circles.html")).unwrap();
+    assert!(
+        circles.contains("<a href=\"geometry-units.html#unit-stadion\"><code>stadia</code></a>")
+    );
+
+    std::fs::remove_dir_all(output).unwrap();
+}
+
+#[test]
+fn audit_units() {
+    numbat()
+        .arg("audit-units")
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("milli-inch"))
+        .stdout(predicates::str::contains("ambiguous unit identifiers"));
+}
+
+#[test]
+fn step_debugger() {
+    numbat()
+        .arg("--debug-step")
+        .arg("tests/examples/debugger.nbt")
+        .write_stdin("b 2\nc\nlocals\nbt\nfinish\np b\nc\n")
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("debugger.nbt:2:13 (in f)"))
+        .stdout(predicates::str::contains("x = 3\n"))
+        .stdout(predicates::str::contains(
+            "#1 File tests/examples/debugger.nbt:4:9 (in <main>)",
+        ))
+        .stdout(predicates::str::contains("b = 7\n"))
+        .stdout(predicates::str::contains("The result is 7"));
+}
+
+#[test]
+fn profiler() {
+    numbat()
+        .arg("--profile")
+        .arg("--expression")
+        .arg("fn square(x) = x^2\nsquare(3) + square(4)")
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("25"))
+        .stderr(predicates::str::contains("instructions"))
+        .stderr(predicates::str::is_match(r"square\s+2\s").unwrap());
 }
@@ -0,0 +1,41 @@
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+
+/// The contents of the connection file that Jupyter passes to the kernel on startup.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ConnectionInfo {
+    pub transport: String,
+    pub ip: String,
+    pub shell_port: u16,
+    pub iopub_port: u16,
+    pub stdin_port: u16,
+    pub control_port: u16,
+    pub hb_port: u16,
+    pub key: String,
+    pub signature_scheme: String,
+}
+
+impl ConnectionInfo {
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path).context(format!(
+            "Could not read connection file '{}'",
+            path.to_string_lossy()
+        ))?;
+        let info: Self = serde_json::from_str(&contents).context("Invalid connection file")?;
+
+        if info.transport != "tcp" {
+            anyhow::bail!("Unsupported transport '{}'", info.transport);
+        }
+        if info.signature_scheme != "hmac-sha256" {
+            anyhow::bail!("Unsupported signature scheme '{}'", info.signature_scheme);
+        }
+
+        Ok(info)
+    }
+
+    pub fn endpoint(&self, port: u16) -> String {
+        format!("{}://{}:{}", self.transport, self.ip, port)
+    }
+}
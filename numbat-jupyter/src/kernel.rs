@@ -0,0 +1,284 @@
+//! The Numbat-specific part of the kernel, independent of the messaging protocol.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use anyhow::Result;
+use codespan_reporting::term::{self, termcolor::Ansi, Config};
+use numbat::diagnostic::ErrorDiagnostic;
+use numbat::html_formatter::HtmlFormatter;
+use numbat::markup::{plain_text_format, Formatter, Markup};
+use numbat::module_importer::{BuiltinModuleImporter, ChainedImporter, FileSystemImporter};
+use numbat::resolver::CodeSource;
+use numbat::value::Value;
+use numbat::{Context, InterpreterResult, InterpreterSettings, NumbatError};
+use serde_json::json;
+
+/// Colors for the HTML output, based on the "One Light" color scheme.
+const STYLE: &str = "<style>\
+.numbat-emphasized { font-weight: bold; }\
+.numbat-dimmed { color: #a0a1a7; }\
+.numbat-string { color: #50a14f; }\
+.numbat-keyword { color: #a626a4; font-weight: bold; }\
+.numbat-value { color: #986801; }\
+.numbat-unit { color: #0184bc; }\
+.numbat-type-identifier { color: #4078f2; font-style: italic; }\
+.numbat-operator { font-weight: bold; }\
+.numbat-decorator { color: #50a14f; }\
+</style>";
+
+/// Output that is produced while code is executed, in order.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Output {
+    /// Text from `print` and similar procedures
+    Stdout(String),
+    /// Rich output, like plots, as a map from MIME types to data
+    Display(serde_json::Value),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExecutionError {
+    pub ename: String,
+    pub evalue: String,
+    /// The rendered diagnostics, with ANSI escape sequences for colors
+    pub traceback: Vec<String>,
+}
+
+pub struct Execution {
+    pub outputs: Vec<Output>,
+    /// The value of the last statement as a map from MIME types to data, if any
+    pub result: std::result::Result<Option<serde_json::Value>, ExecutionError>,
+}
+
+pub struct Kernel {
+    ctx: Context,
+    interrupt: Arc<AtomicBool>,
+}
+
+impl Kernel {
+    pub fn new(interrupt: Arc<AtomicBool>) -> Result<Self> {
+        let mut fs_importer = FileSystemImporter::default();
+        if let Some(modules_path) = std::env::var_os("NUMBAT_MODULES_PATH") {
+            for path in modules_path.to_string_lossy().split(':') {
+                fs_importer.add_path(path);
+            }
+        }
+        let importer = ChainedImporter::new(
+            Box::new(fs_importer),
+            Box::<BuiltinModuleImporter>::default(),
+        );
+
+        let mut ctx = Context::new(importer);
+        let _ = ctx
+            .interpret("use prelude", CodeSource::Internal)
+            .map_err(|e| anyhow::anyhow!("Could not load the prelude: {e}"))?;
+        ctx.load_currency_module_on_demand(true);
+
+        Ok(Self { ctx, interrupt })
+    }
+
+    pub fn execute(&mut self, code: &str) -> Execution {
+        let outputs = Arc::new(Mutex::new(vec![]));
+
+        let outputs_c = outputs.clone();
+        let outputs_p = outputs.clone();
+        let mut settings = InterpreterSettings {
+            print_fn: Box::new(move |s: &Markup| {
+                let text = format!("{}\n", plain_text_format(s, false));
+                outputs_c.lock().unwrap().push(Output::Stdout(text));
+            }),
+            plot_fn: Some(Box::new(move |html: &str| {
+                let data = json!({ "text/html": html });
+                outputs_p.lock().unwrap().push(Output::Display(data));
+            })),
+            // The kernel does not have access to the standard input of the notebook
+            read_line_fn: Box::new(|_| None),
+            interrupt: Some(self.interrupt.clone()),
+            ..Default::default()
+        };
+
+        self.interrupt.store(false, Ordering::Relaxed);
+        let result = self
+            .ctx
+            .interpret_with_settings(&mut settings, code, CodeSource::Text)
+            .map_err(|b| *b);
+
+        let result = match result {
+            // `show` returns an empty string if the plot is displayed inline
+            Ok((_, InterpreterResult::Value(Value::String(s)))) if s.is_empty() => Ok(None),
+            Ok((statements, result)) => {
+                let markup = result.to_markup(
+                    statements.last(),
+                    self.ctx.dimension_registry(),
+                    true,
+                    false,
+                );
+                Ok(Self::mime_bundle(&markup))
+            }
+            Err(NumbatError::ResolverError(e)) => Err(self.execution_error("ResolverError", e)),
+            Err(NumbatError::NameResolutionError(e)) => {
+                Err(self.execution_error("NameResolutionError", e))
+            }
+            Err(NumbatError::TypeCheckError(e)) => Err(self.execution_error("TypeCheckError", e)),
+            Err(NumbatError::RuntimeError(e)) => Err(self.execution_error("RuntimeError", e)),
+        };
+
+        drop(settings);
+        Execution {
+            outputs: Arc::try_unwrap(outputs).unwrap().into_inner().unwrap(),
+            result,
+        }
+    }
+
+    /// Returns the completions for the word in front of the cursor, together with
+    /// the range of characters that they replace.
+    pub fn complete(&self, code: &str, cursor_pos: usize) -> (Vec<String>, usize, usize) {
+        let chars: Vec<char> = code.chars().collect();
+        let end = cursor_pos.min(chars.len());
+        let start = word_start(&chars, end);
+
+        let word: String = chars[start..end].iter().collect();
+        let matches = self
+            .ctx
+            .get_completions_for(&word, true)
+            .map(|s| s.trim().to_string())
+            .collect();
+
+        (matches, start, end)
+    }
+
+    /// Returns information about the identifier at the cursor, if it is known.
+    pub fn inspect(&mut self, code: &str, cursor_pos: usize) -> Option<serde_json::Value> {
+        let chars: Vec<char> = code.chars().collect();
+        let pos = cursor_pos.min(chars.len());
+        let start = word_start(&chars, pos);
+        let end = pos
+            + chars[pos..]
+                .iter()
+                .take_while(|c| is_word_char(**c))
+                .count();
+
+        let word: String = chars[start..end].iter().collect();
+        if word.is_empty() {
+            return None;
+        }
+
+        let markup = self.ctx.print_info_for_keyword(&word);
+        if plain_text_format(&markup, false).trim() == "Not found" {
+            return None;
+        }
+
+        Self::mime_bundle(&markup)
+    }
+
+    fn mime_bundle(markup: &Markup) -> Option<serde_json::Value> {
+        let plain = plain_text_format(markup, false);
+        let plain = plain.trim_end();
+        if plain.is_empty() {
+            return None;
+        }
+
+        let html = HtmlFormatter.format(markup, false);
+        Some(json!({
+            "text/plain": plain,
+            "text/html": format!("{STYLE}<pre>{}</pre>", html.trim_end()),
+        }))
+    }
+
+    fn execution_error(
+        &self,
+        ename: &str,
+        error: impl ErrorDiagnostic + ToString,
+    ) -> ExecutionError {
+        let mut writer = Ansi::new(vec![]);
+        let config = Config::default();
+        for diagnostic in error.diagnostics() {
+            term::emit(
+                &mut writer,
+                &config,
+                &self.ctx.resolver().files,
+                &diagnostic,
+            )
+            .unwrap();
+        }
+        let rendered = String::from_utf8_lossy(&writer.into_inner()).into_owned();
+
+        ExecutionError {
+            ename: ename.into(),
+            evalue: error.to_string(),
+            traceback: rendered.lines().map(str::to_owned).collect(),
+        }
+    }
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '\\'
+}
+
+fn word_start(chars: &[char], end: usize) -> usize {
+    end - chars[..end]
+        .iter()
+        .rev()
+        .take_while(|c| is_word_char(**c))
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kernel() -> Kernel {
+        Kernel::new(Arc::new(AtomicBool::new(false))).unwrap()
+    }
+
+    #[test]
+    fn execute() {
+        let mut kernel = kernel();
+
+        let execution = kernel.execute("print(\"hello\")\nlet x = 2 m\nx + 3 m");
+        assert_eq!(execution.outputs, vec![Output::Stdout("hello\n".into())]);
+        let result = execution.result.unwrap().unwrap();
+        assert_eq!(result["text/plain"], "5 m    [Length]");
+        assert!(result["text/html"]
+            .as_str()
+            .unwrap()
+            .contains("<span class=\"numbat-unit\">m</span>"));
+
+        assert_eq!(kernel.execute("let y = x").result, Ok(None));
+
+        let error = kernel.execute("x + 1 s").result.unwrap_err();
+        assert_eq!(error.ename, "TypeCheckError");
+        assert!(error
+            .traceback
+            .join("\n")
+            .contains("incompatible dimensions"));
+    }
+
+    #[test]
+    fn plots_are_shown_inline() {
+        let mut kernel = kernel();
+        let execution =
+            kernel.execute("use plot::line_plot\nfn f(x) = x^2\nshow(line_plot(f, 0, 1))");
+        let [Output::Display(data)] = execution.outputs.as_slice() else {
+            panic!("Expected a single plot");
+        };
+        assert!(data["text/html"].as_str().unwrap().contains("plotly"));
+        assert_eq!(execution.result, Ok(None));
+    }
+
+    #[test]
+    fn complete_and_inspect() {
+        let mut kernel = kernel();
+
+        let (matches, start, end) = kernel.complete("2 kilomet", 9);
+        assert!(matches.contains(&"kilometer".to_string()));
+        assert_eq!((start, end), (2, 9));
+
+        let (matches, _, _) = kernel.complete("sqr", 3);
+        assert!(matches.contains(&"sqrt(".to_string()));
+
+        let info = kernel.inspect("2 meter", 4).unwrap();
+        assert!(info["text/plain"].as_str().unwrap().contains("Unit: Metre"));
+        assert!(kernel.inspect("2 + foo", 6).is_none());
+    }
+}
@@ -0,0 +1,94 @@
+mod connection;
+mod kernel;
+mod message;
+mod server;
+
+use std::fs;
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use clap::{Parser, Subcommand};
+use serde_json::json;
+
+use connection::ConnectionInfo;
+
+#[derive(Parser, Debug)]
+#[command(version, about, name("numbat-jupyter"))]
+struct Args {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Start the kernel. This is called by Jupyter.
+    Run {
+        /// Path to the connection file that is provided by Jupyter
+        connection_file: PathBuf,
+    },
+    /// Install the kernel specification, such that Jupyter can find the kernel.
+    Install {
+        /// Directory with the kernel specifications of Jupyter. Defaults to the
+        /// directory of the current user.
+        #[arg(long, value_name = "DIR")]
+        kernels_dir: Option<PathBuf>,
+    },
+}
+
+fn default_kernels_dir() -> Option<PathBuf> {
+    if cfg!(target_os = "macos") {
+        dirs::home_dir().map(|home| home.join("Library").join("Jupyter").join("kernels"))
+    } else {
+        dirs::data_dir().map(|data| data.join("jupyter").join("kernels"))
+    }
+}
+
+fn install(kernels_dir: Option<PathBuf>) -> Result<()> {
+    let Some(kernels_dir) = kernels_dir.or_else(default_kernels_dir) else {
+        anyhow::bail!("Could not determine the Jupyter data directory, please use --kernels-dir");
+    };
+    let kernel_dir = kernels_dir.join("numbat");
+    fs::create_dir_all(&kernel_dir).context(format!(
+        "Could not create directory '{}'",
+        kernel_dir.to_string_lossy()
+    ))?;
+
+    let executable = std::env::current_exe().context("Could not find the kernel executable")?;
+    let kernel_spec = json!({
+        "argv": [executable, "run", "{connection_file}"],
+        "display_name": "Numbat",
+        "language": "numbat",
+        "interrupt_mode": "message",
+    });
+
+    let path = kernel_dir.join("kernel.json");
+    fs::write(&path, serde_json::to_string_pretty(&kernel_spec)?).context(format!(
+        "Could not write kernel specification to '{}'",
+        path.to_string_lossy()
+    ))?;
+    println!(
+        "Installed the Numbat kernel in '{}'",
+        kernel_dir.to_string_lossy()
+    );
+
+    Ok(())
+}
+
+fn run(connection_file: PathBuf) -> Result<()> {
+    let info = ConnectionInfo::from_file(&connection_file)?;
+    tokio::runtime::Runtime::new()?.block_on(server::run(info))
+}
+
+fn main() {
+    let args = Args::parse();
+
+    let result = match args.command {
+        Command::Run { connection_file } => run(connection_file),
+        Command::Install { kernels_dir } => install(kernels_dir),
+    };
+
+    if let Err(e) = result {
+        eprintln!("{e:#}");
+        std::process::exit(1);
+    }
+}
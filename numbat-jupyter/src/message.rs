@@ -0,0 +1,181 @@
+//! Messages of the Jupyter messaging protocol and their representation on the wire, see
+//! <https://jupyter-client.readthedocs.io/en/latest/messaging.html>.
+
+use anyhow::{bail, Context, Result};
+use bytes::Bytes;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::Sha256;
+use zeromq::ZmqMessage;
+
+const DELIMITER: &[u8] = b"<IDS|MSG>";
+
+pub const PROTOCOL_VERSION: &str = "5.3";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Header {
+    pub msg_id: String,
+    pub session: String,
+    pub username: String,
+    pub date: String,
+    pub msg_type: String,
+    pub version: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct Message {
+    /// Routing identities of the sender (on ROUTER sockets) or the topic (on the IOPub socket)
+    pub identities: Vec<Bytes>,
+    pub header: Header,
+    /// The header of the request that caused this message, or an empty object
+    pub parent_header: Value,
+    pub metadata: Value,
+    pub content: Value,
+}
+
+impl Message {
+    pub fn msg_type(&self) -> &str {
+        &self.header.msg_type
+    }
+}
+
+/// Creates, signs and verifies messages on behalf of the kernel.
+pub struct Session {
+    id: String,
+    /// Messages are not signed if the connection file contains an empty key
+    mac: Option<Hmac<Sha256>>,
+}
+
+impl Session {
+    pub fn new(key: &str) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            mac: (!key.is_empty()).then(|| {
+                Hmac::<Sha256>::new_from_slice(key.as_bytes()).expect("HMAC accepts any key size")
+            }),
+        }
+    }
+
+    fn header(&self, msg_type: &str) -> Header {
+        Header {
+            msg_id: uuid::Uuid::new_v4().to_string(),
+            session: self.id.clone(),
+            username: "kernel".into(),
+            date: jiff::Timestamp::now().to_string(),
+            msg_type: msg_type.into(),
+            version: PROTOCOL_VERSION.into(),
+        }
+    }
+
+    /// A message that is sent back to the sender of `parent`.
+    pub fn reply(&self, parent: &Message, msg_type: &str, content: Value) -> Message {
+        Message {
+            identities: parent.identities.clone(),
+            ..self.broadcast(parent, msg_type, content)
+        }
+    }
+
+    /// A message for the IOPub socket that was caused by `parent`.
+    pub fn broadcast(&self, parent: &Message, msg_type: &str, content: Value) -> Message {
+        Message {
+            identities: vec![Bytes::from(msg_type.to_owned())],
+            header: self.header(msg_type),
+            parent_header: serde_json::to_value(&parent.header).unwrap(),
+            metadata: Value::Object(Default::default()),
+            content,
+        }
+    }
+
+    fn signature(&self, parts: &[&[u8]]) -> String {
+        let Some(mac) = &self.mac else {
+            return String::new();
+        };
+
+        let mut mac = mac.clone();
+        for part in parts {
+            mac.update(part);
+        }
+        mac.finalize()
+            .into_bytes()
+            .iter()
+            .map(|b| format!("{b:02x}"))
+            .collect()
+    }
+
+    pub fn encode(&self, message: Message) -> ZmqMessage {
+        let parts = [
+            serde_json::to_vec(&message.header).unwrap(),
+            serde_json::to_vec(&message.parent_header).unwrap(),
+            serde_json::to_vec(&message.metadata).unwrap(),
+            serde_json::to_vec(&message.content).unwrap(),
+        ];
+        let signature = self.signature(&parts.iter().map(Vec::as_slice).collect::<Vec<_>>());
+
+        let frames = message
+            .identities
+            .into_iter()
+            .chain([Bytes::from_static(DELIMITER), Bytes::from(signature)])
+            .chain(parts.map(Bytes::from))
+            .collect::<Vec<_>>();
+        ZmqMessage::try_from(frames).expect("Message has at least one frame")
+    }
+
+    pub fn decode(&self, message: ZmqMessage) -> Result<Message> {
+        let mut frames = message.into_vec();
+        let Some(delimiter) = frames.iter().position(|f| f.as_ref() == DELIMITER) else {
+            bail!("Missing delimiter in message");
+        };
+        if frames.len() < delimiter + 6 {
+            bail!("Incomplete message");
+        }
+
+        // Additional binary buffers after the content are not used by this kernel
+        frames.truncate(delimiter + 6);
+        let parts = frames.split_off(delimiter + 2);
+        let signature = frames[delimiter + 1].clone();
+        frames.truncate(delimiter);
+
+        let expected_signature = self.signature(&[&parts[0], &parts[1], &parts[2], &parts[3]]);
+        if self.mac.is_some() && signature.as_ref() != expected_signature.as_bytes() {
+            bail!("Invalid message signature");
+        }
+
+        let parse = |part: &Bytes| serde_json::from_slice::<Value>(part);
+        Ok(Message {
+            identities: frames,
+            header: serde_json::from_slice(&parts[0]).context("Invalid message header")?,
+            parent_header: parse(&parts[1])?,
+            metadata: parse(&parts[2])?,
+            content: parse(&parts[3])?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signed_roundtrip() {
+        let session = Session::new("secret");
+        let request = Message {
+            identities: vec![Bytes::from_static(b"client")],
+            header: session.header("kernel_info_request"),
+            parent_header: Value::Object(Default::default()),
+            metadata: Value::Object(Default::default()),
+            content: serde_json::json!({}),
+        };
+        let reply = session.reply(&request, "kernel_info_reply", serde_json::json!({"a": 1}));
+
+        let decoded = session.decode(session.encode(reply)).unwrap();
+        assert_eq!(decoded.identities, vec![Bytes::from_static(b"client")]);
+        assert_eq!(decoded.msg_type(), "kernel_info_reply");
+        assert_eq!(decoded.parent_header["msg_type"], "kernel_info_request");
+        assert_eq!(decoded.content["a"], 1);
+
+        let other_session = Session::new("other secret");
+        let reply = session.reply(&request, "kernel_info_reply", serde_json::json!({}));
+        assert!(other_session.decode(session.encode(reply)).is_err());
+    }
+}
@@ -0,0 +1,264 @@
+use std::ops::ControlFlow;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use anyhow::Result;
+use serde_json::{json, Value};
+use zeromq::{PubSocket, RepSocket, RouterSocket, Socket, SocketRecv, SocketSend};
+
+use crate::connection::ConnectionInfo;
+use crate::kernel::{Kernel, Output};
+use crate::message::{Message, Session, PROTOCOL_VERSION};
+
+struct Server {
+    session: Arc<Session>,
+    kernel: Kernel,
+    shell: RouterSocket,
+    iopub: PubSocket,
+    execution_count: u64,
+}
+
+pub async fn run(info: ConnectionInfo) -> Result<()> {
+    let session = Arc::new(Session::new(&info.key));
+    let interrupt = Arc::new(AtomicBool::new(false));
+
+    let mut shell = RouterSocket::new();
+    shell.bind(&info.endpoint(info.shell_port)).await?;
+    let mut control = RouterSocket::new();
+    control.bind(&info.endpoint(info.control_port)).await?;
+    let mut iopub = PubSocket::new();
+    iopub.bind(&info.endpoint(info.iopub_port)).await?;
+    let mut heartbeat = RepSocket::new();
+    heartbeat.bind(&info.endpoint(info.hb_port)).await?;
+    // Input requests are not supported, but clients expect to be able to connect
+    let mut stdin = RouterSocket::new();
+    stdin.bind(&info.endpoint(info.stdin_port)).await?;
+
+    tokio::spawn(async move {
+        while let Ok(ping) = heartbeat.recv().await {
+            if heartbeat.send(ping).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    // The control channel is served on a separate task, such that running
+    // evaluations can be interrupted.
+    let control_session = session.clone();
+    let control_interrupt = interrupt.clone();
+    tokio::spawn(async move {
+        while let Ok(message) = control.recv().await {
+            let Ok(request) = control_session.decode(message) else {
+                continue;
+            };
+            let (msg_type, content) = match request.msg_type() {
+                "interrupt_request" => {
+                    control_interrupt.store(true, Ordering::Relaxed);
+                    ("interrupt_reply", json!({ "status": "ok" }))
+                }
+                "shutdown_request" => ("shutdown_reply", shutdown_reply(&request)),
+                "kernel_info_request" => ("kernel_info_reply", kernel_info()),
+                _ => continue,
+            };
+
+            let reply = control_session.reply(&request, msg_type, content);
+            let _ = control.send(control_session.encode(reply)).await;
+
+            if msg_type == "shutdown_reply" {
+                std::process::exit(0);
+            }
+        }
+    });
+
+    let mut server = Server {
+        session,
+        kernel: Kernel::new(interrupt)?,
+        shell,
+        iopub,
+        execution_count: 0,
+    };
+
+    loop {
+        let request = match server.session.decode(server.shell.recv().await?) {
+            Ok(request) => request,
+            Err(e) => {
+                eprintln!("Ignoring invalid message: {e:#}");
+                continue;
+            }
+        };
+
+        server
+            .publish(&request, "status", json!({ "execution_state": "busy" }))
+            .await?;
+        let control_flow = server.handle_shell_request(&request).await?;
+        server
+            .publish(&request, "status", json!({ "execution_state": "idle" }))
+            .await?;
+
+        if control_flow.is_break() {
+            return Ok(());
+        }
+    }
+}
+
+impl Server {
+    async fn publish(&mut self, parent: &Message, msg_type: &str, content: Value) -> Result<()> {
+        let message = self.session.broadcast(parent, msg_type, content);
+        self.iopub.send(self.session.encode(message)).await?;
+        Ok(())
+    }
+
+    async fn reply(&mut self, request: &Message, msg_type: &str, content: Value) -> Result<()> {
+        let message = self.session.reply(request, msg_type, content);
+        self.shell.send(self.session.encode(message)).await?;
+        Ok(())
+    }
+
+    async fn handle_shell_request(&mut self, request: &Message) -> Result<ControlFlow<()>> {
+        let content = &request.content;
+        match request.msg_type() {
+            "kernel_info_request" => {
+                self.reply(request, "kernel_info_reply", kernel_info())
+                    .await?;
+            }
+            "execute_request" => {
+                let code = content["code"].as_str().unwrap_or_default();
+                let silent = content["silent"].as_bool().unwrap_or(false);
+                self.execute(request, code, silent).await?;
+            }
+            "complete_request" => {
+                let code = content["code"].as_str().unwrap_or_default();
+                let cursor_pos = content["cursor_pos"].as_u64().unwrap_or_default() as usize;
+                let (matches, cursor_start, cursor_end) = self.kernel.complete(code, cursor_pos);
+                let reply = json!({
+                    "status": "ok",
+                    "matches": matches,
+                    "cursor_start": cursor_start,
+                    "cursor_end": cursor_end,
+                    "metadata": {},
+                });
+                self.reply(request, "complete_reply", reply).await?;
+            }
+            "inspect_request" => {
+                let code = content["code"].as_str().unwrap_or_default();
+                let cursor_pos = content["cursor_pos"].as_u64().unwrap_or_default() as usize;
+                let data = self.kernel.inspect(code, cursor_pos);
+                let reply = json!({
+                    "status": "ok",
+                    "found": data.is_some(),
+                    "data": data.unwrap_or_else(|| json!({})),
+                    "metadata": {},
+                });
+                self.reply(request, "inspect_reply", reply).await?;
+            }
+            "is_complete_request" => {
+                let reply = json!({ "status": "complete" });
+                self.reply(request, "is_complete_reply", reply).await?;
+            }
+            "comm_info_request" => {
+                let reply = json!({ "status": "ok", "comms": {} });
+                self.reply(request, "comm_info_reply", reply).await?;
+            }
+            "history_request" => {
+                let reply = json!({ "status": "ok", "history": [] });
+                self.reply(request, "history_reply", reply).await?;
+            }
+            "shutdown_request" => {
+                self.reply(request, "shutdown_reply", shutdown_reply(request))
+                    .await?;
+                return Ok(ControlFlow::Break(()));
+            }
+            _ => {}
+        }
+
+        Ok(ControlFlow::Continue(()))
+    }
+
+    async fn execute(&mut self, request: &Message, code: &str, silent: bool) -> Result<()> {
+        if !silent {
+            self.execution_count += 1;
+            let content = json!({ "code": code, "execution_count": self.execution_count });
+            self.publish(request, "execute_input", content).await?;
+        }
+
+        let execution = tokio::task::block_in_place(|| self.kernel.execute(code));
+
+        if !silent {
+            for output in execution.outputs {
+                match output {
+                    Output::Stdout(text) => {
+                        let content = json!({ "name": "stdout", "text": text });
+                        self.publish(request, "stream", content).await?;
+                    }
+                    Output::Display(data) => {
+                        let content = json!({ "data": data, "metadata": {}, "transient": {} });
+                        self.publish(request, "display_data", content).await?;
+                    }
+                }
+            }
+        }
+
+        let reply = match execution.result {
+            Ok(result) => {
+                if let Some(data) = result.filter(|_| !silent) {
+                    let content = json!({
+                        "execution_count": self.execution_count,
+                        "data": data,
+                        "metadata": {},
+                    });
+                    self.publish(request, "execute_result", content).await?;
+                }
+
+                json!({
+                    "status": "ok",
+                    "execution_count": self.execution_count,
+                    "payload": [],
+                    "user_expressions": {},
+                })
+            }
+            Err(error) => {
+                let content = json!({
+                    "ename": error.ename,
+                    "evalue": error.evalue,
+                    "traceback": error.traceback,
+                });
+                if !silent {
+                    self.publish(request, "error", content.clone()).await?;
+                }
+
+                let mut reply = content;
+                reply["status"] = json!("error");
+                reply["execution_count"] = json!(self.execution_count);
+                reply
+            }
+        };
+
+        self.reply(request, "execute_reply", reply).await
+    }
+}
+
+fn kernel_info() -> Value {
+    json!({
+        "status": "ok",
+        "protocol_version": PROTOCOL_VERSION,
+        "implementation": "numbat",
+        "implementation_version": env!("CARGO_PKG_VERSION"),
+        "language_info": {
+            "name": "numbat",
+            "version": env!("CARGO_PKG_VERSION"),
+            "mimetype": "text/x-numbat",
+            "file_extension": ".nbt",
+        },
+        "banner": format!("Numbat {}", env!("CARGO_PKG_VERSION")),
+        "help_links": [
+            { "text": "Numbat documentation", "url": "https://numbat.dev/doc/" },
+        ],
+    })
+}
+
+fn shutdown_reply(request: &Message) -> Value {
+    json!({
+        "status": "ok",
+        "restart": request.content["restart"].as_bool().unwrap_or(false),
+    })
+}
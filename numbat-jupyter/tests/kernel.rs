@@ -0,0 +1,223 @@
+//! Runs the kernel and talks to it like a Jupyter client would.
+
+use std::process::{Child, Command};
+use std::time::Duration;
+
+use bytes::Bytes;
+use hmac::{Hmac, Mac};
+use serde_json::{json, Value};
+use sha2::Sha256;
+use zeromq::{DealerSocket, Socket, SocketRecv, SocketSend, SubSocket, ZmqMessage};
+
+const KEY: &str = "a0436f6c-1916-498b-8eb9-e81ab9368e84";
+
+struct Client {
+    kernel: Child,
+    shell: DealerSocket,
+    iopub: SubSocket,
+    num_requests: usize,
+}
+
+fn sign(parts: &[Vec<u8>]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(KEY.as_bytes()).unwrap();
+    for part in parts {
+        mac.update(part);
+    }
+    mac.finalize()
+        .into_bytes()
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+fn free_port() -> u16 {
+    std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port()
+}
+
+async fn connect<S: Socket>(socket: &mut S, port: u16) {
+    // The kernel might not be listening yet
+    for _ in 0..100 {
+        if socket
+            .connect(&format!("tcp://127.0.0.1:{port}"))
+            .await
+            .is_ok()
+        {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    panic!("Could not connect to the kernel");
+}
+
+impl Client {
+    async fn start() -> Self {
+        let [shell_port, iopub_port, stdin_port, control_port, hb_port] =
+            [(); 5].map(|_| free_port());
+        let connection_file =
+            std::env::temp_dir().join(format!("numbat-jupyter-test-{}.json", std::process::id()));
+        let connection_info = json!({
+            "transport": "tcp",
+            "ip": "127.0.0.1",
+            "shell_port": shell_port,
+            "iopub_port": iopub_port,
+            "stdin_port": stdin_port,
+            "control_port": control_port,
+            "hb_port": hb_port,
+            "key": KEY,
+            "signature_scheme": "hmac-sha256",
+        });
+        std::fs::write(&connection_file, connection_info.to_string()).unwrap();
+
+        let kernel = Command::new(env!("CARGO_BIN_EXE_numbat-jupyter"))
+            .arg("run")
+            .arg(&connection_file)
+            .spawn()
+            .unwrap();
+
+        let mut shell = DealerSocket::new();
+        connect(&mut shell, shell_port).await;
+        let mut iopub = SubSocket::new();
+        connect(&mut iopub, iopub_port).await;
+        iopub.subscribe("").await.unwrap();
+
+        Self {
+            kernel,
+            shell,
+            iopub,
+            num_requests: 0,
+        }
+    }
+
+    /// Sends a request on the shell channel and returns its message ID.
+    async fn send(&mut self, msg_type: &str, content: Value) -> String {
+        self.num_requests += 1;
+        let msg_id = format!("{msg_type}-{}", self.num_requests);
+        let header = json!({
+            "msg_id": msg_id,
+            "session": "test",
+            "username": "test",
+            "date": "2024-01-01T00:00:00Z",
+            "msg_type": msg_type,
+            "version": "5.3",
+        });
+        let parts = [header, json!({}), json!({}), content].map(|p| p.to_string().into_bytes());
+
+        let mut frames = vec![Bytes::from_static(b"<IDS|MSG>"), Bytes::from(sign(&parts))];
+        frames.extend(parts.map(Bytes::from));
+        self.shell
+            .send(ZmqMessage::try_from(frames).unwrap())
+            .await
+            .unwrap();
+
+        msg_id
+    }
+
+    async fn recv(socket: &mut impl SocketRecv) -> (Value, Value, Value) {
+        let message = tokio::time::timeout(Duration::from_secs(30), socket.recv())
+            .await
+            .expect("Timeout while waiting for a message")
+            .unwrap();
+        let frames = message.into_vec();
+        let delimiter = frames
+            .iter()
+            .position(|f| f.as_ref() == b"<IDS|MSG>")
+            .unwrap();
+
+        let parts: Vec<Vec<u8>> = frames[delimiter + 2..delimiter + 6]
+            .iter()
+            .map(|f| f.to_vec())
+            .collect();
+        assert_eq!(frames[delimiter + 1].as_ref(), sign(&parts).as_bytes());
+
+        let parse = |i: usize| serde_json::from_slice::<Value>(&parts[i]).unwrap();
+        (parse(0), parse(1), parse(3))
+    }
+
+    async fn request(&mut self, msg_type: &str, content: Value) -> (String, Value) {
+        let msg_id = self.send(msg_type, content).await;
+        let (header, parent_header, content) = Self::recv(&mut self.shell).await;
+        assert_eq!(parent_header["msg_id"], msg_id);
+        assert_eq!(header["msg_type"], msg_type.replace("_request", "_reply"));
+        (msg_id, content)
+    }
+
+    /// Collects all IOPub messages for the given request, until the kernel is idle.
+    async fn iopub_messages(&mut self, msg_id: &str) -> Vec<(String, Value)> {
+        let mut messages = vec![];
+        loop {
+            let (header, parent_header, content) = Self::recv(&mut self.iopub).await;
+            if parent_header["msg_id"] != msg_id {
+                continue;
+            }
+            if header["msg_type"] == "status" && content["execution_state"] == "idle" {
+                return messages;
+            }
+            messages.push((header["msg_type"].as_str().unwrap().to_owned(), content));
+        }
+    }
+}
+
+#[tokio::test]
+async fn kernel_session() {
+    let mut client = Client::start().await;
+
+    let (_, info) = client.request("kernel_info_request", json!({})).await;
+    assert_eq!(info["language_info"]["name"], "numbat");
+
+    let (msg_id, reply) = client
+        .request(
+            "execute_request",
+            json!({ "code": "print(\"hi\")\n2 m + 3 m", "silent": false }),
+        )
+        .await;
+    assert_eq!(reply["status"], "ok");
+    assert_eq!(reply["execution_count"], 1);
+
+    let messages = client.iopub_messages(&msg_id).await;
+    let msg_types: Vec<_> = messages.iter().map(|(t, _)| t.as_str()).collect();
+    assert_eq!(
+        msg_types,
+        ["status", "execute_input", "stream", "execute_result"]
+    );
+    assert_eq!(messages[2].1["text"], "hi\n");
+    assert_eq!(messages[3].1["data"]["text/plain"], "5 m    [Length]");
+
+    let (msg_id, reply) = client
+        .request("execute_request", json!({ "code": "1 + 1 s" }))
+        .await;
+    assert_eq!(reply["status"], "error");
+    assert_eq!(reply["ename"], "TypeCheckError");
+    let messages = client.iopub_messages(&msg_id).await;
+    assert!(messages.iter().any(|(t, _)| t == "error"));
+
+    let (_, reply) = client
+        .request(
+            "complete_request",
+            json!({ "code": "2 kilom", "cursor_pos": 7 }),
+        )
+        .await;
+    assert_eq!(reply["cursor_start"], 2);
+    assert!(reply["matches"]
+        .as_array()
+        .unwrap()
+        .contains(&json!("kilometer")));
+
+    let (_, reply) = client
+        .request(
+            "inspect_request",
+            json!({ "code": "sqrt(2)", "cursor_pos": 2, "detail_level": 0 }),
+        )
+        .await;
+    assert_eq!(reply["found"], true);
+
+    let (_, reply) = client
+        .request("shutdown_request", json!({ "restart": false }))
+        .await;
+    assert_eq!(reply["restart"], false);
+
+    assert!(client.kernel.wait().unwrap().success());
+}
@@ -0,0 +1,407 @@
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+use lsp_types::{
+    CompletionItem, CompletionItemKind, CompletionItemTag, Diagnostic,
+    DiagnosticRelatedInformation, DiagnosticSeverity, Hover, HoverContents, Location,
+    MarkupContent, MarkupKind, Position, Range, Url,
+};
+use numbat::diagnostic::{self, ErrorDiagnostic};
+use numbat::markup::plain_text_format;
+use numbat::resolver::CodeSource;
+use numbat::{Context, Span};
+
+use crate::position::{offset_to_position, position_to_offset};
+
+fn code_source_for(uri: &Url) -> CodeSource {
+    match uri.to_file_path() {
+        Ok(path) => CodeSource::File(path),
+        Err(_) => CodeSource::Text,
+    }
+}
+
+/// A context in which the given document has been type checked, together with
+/// the ID of the document's code source.
+fn checked_context(base: &Context, uri: &Url, text: &str) -> (Context, usize) {
+    let mut ctx = base.clone();
+    let code_source_id = ctx.resolver().next_code_source_id();
+    let _ = ctx.check(text, code_source_for(uri));
+    (ctx, code_source_id)
+}
+
+fn is_identifier_char(c: char) -> bool {
+    !(c.is_whitespace()
+        || "()[]{},:+-−*/÷^=<>!|&\"#.·⋅×→➞?@⩵≠≤≥".contains(c)
+        || "⁰¹²³⁴⁵⁶⁷⁸⁹⁻".contains(c))
+}
+
+/// The identifier around the given byte offset
+fn word_at(text: &str, offset: usize) -> Option<&str> {
+    let start = text[..offset]
+        .char_indices()
+        .rev()
+        .take_while(|(_, c)| is_identifier_char(*c))
+        .last()
+        .map(|(i, _)| i)
+        .unwrap_or(offset);
+    let end = offset
+        + text[offset..]
+            .char_indices()
+            .find(|(_, c)| !is_identifier_char(*c))
+            .map(|(i, _)| i)
+            .unwrap_or(text.len() - offset);
+
+    if start == end {
+        None
+    } else {
+        Some(&text[start..end])
+    }
+}
+
+/// The source text of a builtin module is not available on disk. To be able to
+/// jump to definitions in these modules, we write them to a temporary directory.
+fn materialize_builtin_module(path: &std::path::Path, source: &str) -> Option<PathBuf> {
+    let relative_path = path.strip_prefix("<builtin>").ok()?;
+    let target = std::env::temp_dir().join("numbat-lsp").join(relative_path);
+
+    if std::fs::read_to_string(&target).ok().as_deref() != Some(source) {
+        std::fs::create_dir_all(target.parent()?).ok()?;
+        std::fs::write(&target, source).ok()?;
+    }
+
+    Some(target)
+}
+
+fn location(
+    ctx: &Context,
+    code_source_id: usize,
+    uri: &Url,
+    file_id: usize,
+    range: std::ops::Range<usize>,
+) -> Option<Location> {
+    let source = ctx.resolver().files.get(file_id).ok()?.source();
+
+    let target_uri = if file_id == code_source_id {
+        uri.clone()
+    } else {
+        let path = match ctx.resolver().get_code_source(file_id) {
+            CodeSource::File(path) => path,
+            CodeSource::Module(_, Some(path)) if path.exists() => path,
+            CodeSource::Module(_, Some(path)) => materialize_builtin_module(&path, source)?,
+            _ => return None,
+        };
+        Url::from_file_path(std::fs::canonicalize(&path).unwrap_or(path)).ok()?
+    };
+
+    Some(Location::new(
+        target_uri,
+        Range::new(
+            offset_to_position(source, range.start),
+            offset_to_position(source, range.end),
+        ),
+    ))
+}
+
+pub fn diagnostics(base: &Context, uri: &Url, text: &str) -> Vec<Diagnostic> {
+    let mut ctx = base.clone();
+    let code_source_id = ctx.resolver().next_code_source_id();
+
+    let result = ctx.check(text, code_source_for(uri));
+
+    // Warnings in imported modules can not be fixed in this document
+    let warnings = ctx
+        .take_warnings()
+        .into_iter()
+        .filter(|w| w.span().code_source_id == code_source_id);
+
+    result
+        .err()
+        .into_iter()
+        .flat_map(|error| error.diagnostics())
+        .chain(warnings.flat_map(|w| w.diagnostics()))
+        .map(|d| convert_diagnostic(&ctx, code_source_id, uri, text, d))
+        .collect()
+}
+
+fn convert_diagnostic(
+    ctx: &Context,
+    code_source_id: usize,
+    uri: &Url,
+    text: &str,
+    diagnostic: diagnostic::Diagnostic,
+) -> Diagnostic {
+    use codespan_reporting::diagnostic::{LabelStyle, Severity};
+
+    let primary_label = diagnostic
+        .labels
+        .iter()
+        .find(|l| l.style == LabelStyle::Primary && l.file_id == code_source_id)
+        .or_else(|| {
+            diagnostic
+                .labels
+                .iter()
+                .find(|l| l.file_id == code_source_id)
+        });
+
+    let range = primary_label
+        .map(|l| {
+            Range::new(
+                offset_to_position(text, l.range.start),
+                offset_to_position(text, l.range.end),
+            )
+        })
+        .unwrap_or_default();
+
+    let mut message = diagnostic.message.clone();
+    if let Some(label) = primary_label.filter(|l| !l.message.is_empty()) {
+        message = format!("{message}: {}", label.message);
+    }
+    for note in &diagnostic.notes {
+        message = format!("{message}\n{note}");
+    }
+
+    let related_information = diagnostic
+        .labels
+        .iter()
+        .filter(|l| Some(*l) != primary_label && !l.message.is_empty())
+        .filter_map(|l| {
+            Some(DiagnosticRelatedInformation {
+                location: location(ctx, code_source_id, uri, l.file_id, l.range.clone())?,
+                message: l.message.clone(),
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let severity = match diagnostic.severity {
+        Severity::Warning => DiagnosticSeverity::WARNING,
+        Severity::Note | Severity::Help => DiagnosticSeverity::INFORMATION,
+        Severity::Bug | Severity::Error => DiagnosticSeverity::ERROR,
+    };
+
+    Diagnostic {
+        range,
+        severity: Some(severity),
+        source: Some("numbat".into()),
+        message,
+        related_information: Some(related_information).filter(|r| !r.is_empty()),
+        ..Default::default()
+    }
+}
+
+pub fn hover(base: &Context, uri: &Url, text: &str, position: Position) -> Option<Hover> {
+    let offset = position_to_offset(text, position);
+
+    let mut ctx = base.clone();
+    let code_source_id = ctx.resolver().next_code_source_id();
+    let statements = ctx.check(text, code_source_for(uri)).unwrap_or_default();
+
+    let mut sections = vec![];
+    let mut range = None;
+
+    if let Some((span, readable_type)) = ctx.type_at(&statements, code_source_id, offset as u32) {
+        let Span { start, end, .. } = span;
+        let expression = &text[start.0 as usize..end.0 as usize];
+        sections.push(format!(
+            "```numbat\n{expression}: {}\n```",
+            plain_text_format(&readable_type, false)
+        ));
+        range = Some(Range::new(
+            offset_to_position(text, start.0 as usize),
+            offset_to_position(text, end.0 as usize),
+        ));
+    }
+
+    if let Some(word) = word_at(text, offset) {
+        if ctx.definition_span(word).is_some() {
+            let info = plain_text_format(&ctx.print_info_for_keyword(word), false);
+            sections.push(format!("```\n{}\n```", info.trim()));
+        }
+    }
+
+    if sections.is_empty() {
+        return None;
+    }
+
+    Some(Hover {
+        contents: HoverContents::Markup(MarkupContent {
+            kind: MarkupKind::Markdown,
+            value: sections.join("\n\n"),
+        }),
+        range,
+    })
+}
+
+pub fn definition(base: &Context, uri: &Url, text: &str, position: Position) -> Option<Location> {
+    let (ctx, code_source_id) = checked_context(base, uri, text);
+
+    let word = word_at(text, position_to_offset(text, position))?;
+    let span = ctx.definition_span(word)?;
+
+    location(
+        &ctx,
+        code_source_id,
+        uri,
+        span.code_source_id,
+        span.start.0 as usize..span.end.0 as usize,
+    )
+}
+
+pub fn completions(
+    base: &Context,
+    uri: &Url,
+    text: &str,
+    position: Position,
+) -> Vec<CompletionItem> {
+    let offset = position_to_offset(text, position);
+    let line_start = text[..offset].rfind('\n').map(|i| i + 1).unwrap_or(0);
+    let line = &text[line_start..offset];
+
+    // Module paths after 'use'
+    if let Some(module_part) = line.trim_start().strip_prefix("use ") {
+        let module_part = module_part.trim_start();
+        return base
+            .list_modules()
+            .filter(|m| m.starts_with(module_part))
+            .map(|m| CompletionItem {
+                label: m.to_string(),
+                kind: Some(CompletionItemKind::MODULE),
+                ..Default::default()
+            })
+            .collect();
+    }
+
+    let word_part = &line[line
+        .char_indices()
+        .rev()
+        .take_while(|(_, c)| is_identifier_char(*c))
+        .last()
+        .map(|(i, _)| i)
+        .unwrap_or(line.len())..];
+    if word_part.is_empty() {
+        return vec![];
+    }
+
+    let (ctx, _) = checked_context(base, uri, text);
+
+    let functions: HashSet<_> = ctx.function_names().collect();
+    let dimensions: HashSet<_> = ctx.dimension_names().iter().cloned().collect();
+    let variables: HashSet<_> = ctx.variable_names().collect();
+
+    ctx.get_completions_for(word_part, false)
+        .map(|word| {
+            let kind = if functions.contains(word.as_str()) {
+                CompletionItemKind::FUNCTION
+            } else if dimensions.contains(word.as_str()) {
+                CompletionItemKind::CLASS
+            } else if variables.contains(word.as_str()) {
+                CompletionItemKind::VARIABLE
+            } else if numbat::keywords::KEYWORDS.contains(&word.as_str()) {
+                CompletionItemKind::KEYWORD
+            } else {
+                CompletionItemKind::UNIT
+            };
+
+            let deprecation = ctx.deprecation_message(&word);
+
+            CompletionItem {
+                label: word,
+                kind: Some(kind),
+                tags: deprecation
+                    .is_some()
+                    .then(|| vec![CompletionItemTag::DEPRECATED]),
+                detail: deprecation.map(|message| format!("deprecated: {message}")),
+                ..Default::default()
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use numbat::module_importer::BuiltinModuleImporter;
+
+    fn context() -> Context {
+        let mut ctx = Context::new(BuiltinModuleImporter::default());
+        let _ = ctx.interpret("use prelude", CodeSource::Internal).unwrap();
+        ctx
+    }
+
+    fn uri() -> Url {
+        Url::parse("untitled:Untitled-1").unwrap()
+    }
+
+    #[test]
+    fn reports_type_errors() {
+        let ctx = context();
+        let text = "let x = 2 m\nlet y = x + 3 s\n";
+
+        let diagnostics = diagnostics(&ctx, &uri(), text);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].range.start.line, 1);
+        assert!(diagnostics[0].message.contains("right hand side: Time"));
+        assert!(diagnostics[0]
+            .related_information
+            .iter()
+            .flatten()
+            .any(|r| r.message == "incompatible dimensions in addition"));
+
+        assert!(super::diagnostics(&ctx, &uri(), "let x = 2 m\n").is_empty());
+    }
+
+    #[test]
+    fn reports_warnings() {
+        let ctx = context();
+        let text = "let x = 2 m\nlet x = 3 m\n";
+
+        let diagnostics = diagnostics(&ctx, &uri(), text);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].severity, Some(DiagnosticSeverity::WARNING));
+        assert_eq!(diagnostics[0].range.start.line, 1);
+        assert!(diagnostics[0]
+            .message
+            .contains("Redefinition of constant 'x'"));
+    }
+
+    #[test]
+    fn hover_shows_type() {
+        let ctx = context();
+        let text = "let distance = 2 km\nlet speed = distance / 3 h\n";
+
+        let hover = hover(&ctx, &uri(), text, Position::new(1, 14)).unwrap();
+        let HoverContents::Markup(content) = hover.contents else {
+            unreachable!()
+        };
+        assert!(content.value.contains("distance: Length"));
+    }
+
+    #[test]
+    fn goto_definition() {
+        let ctx = context();
+        let text = "fn f(x: Length) = 2 x\nf(3 m)\n";
+
+        let location = definition(&ctx, &uri(), text, Position::new(1, 0)).unwrap();
+        assert_eq!(location.uri, uri());
+        assert_eq!(location.range.start, Position::new(0, 3));
+
+        let location = definition(&ctx, &uri(), text, Position::new(0, 8)).unwrap();
+        assert!(location.uri.path().ends_with("dimensions.nbt"));
+    }
+
+    #[test]
+    fn complete_identifiers_and_modules() {
+        let ctx = context();
+
+        let items = completions(&ctx, &uri(), "let x = kilom", Position::new(0, 13));
+        assert!(items.iter().any(|i| i.label == "kilometer"));
+
+        let items = completions(&ctx, &uri(), "use units::cu", Position::new(0, 13));
+        assert!(items.iter().any(|i| i.label == "units::currency"));
+
+        let text = "@deprecated(\"use 'new_f'\")\nfn old_f(x) = x\nold_f(1)";
+        let items = completions(&ctx, &uri(), text, Position::new(2, 4));
+        let item = items.iter().find(|i| i.label == "old_f").unwrap();
+        assert_eq!(item.tags, Some(vec![CompletionItemTag::DEPRECATED]));
+        assert_eq!(item.detail.as_deref(), Some("deprecated: use 'new_f'"));
+    }
+}
@@ -0,0 +1,223 @@
+mod analysis;
+mod position;
+
+use std::collections::HashMap;
+
+use anyhow::Result;
+use lsp_server::{Connection, ExtractError, Message, Notification, Request, RequestId, Response};
+use lsp_types::notification::{
+    DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument, Notification as _,
+    PublishDiagnostics,
+};
+use lsp_types::request::{Completion, GotoDefinition, HoverRequest};
+use lsp_types::{
+    CompletionOptions, GotoDefinitionResponse, HoverProviderCapability, OneOf,
+    PublishDiagnosticsParams, ServerCapabilities, TextDocumentSyncCapability, TextDocumentSyncKind,
+    Url,
+};
+use numbat::module_importer::{BuiltinModuleImporter, ChainedImporter, FileSystemImporter};
+use numbat::resolver::CodeSource;
+use numbat::Context;
+
+struct Server {
+    /// A context with the prelude loaded. Every document is checked in a fresh
+    /// copy of this context.
+    base_context: Context,
+    documents: HashMap<Url, String>,
+}
+
+impl Server {
+    fn new() -> Result<Self> {
+        let mut fs_importer = FileSystemImporter::default();
+        if let Some(modules_path) = std::env::var_os("NUMBAT_MODULES_PATH") {
+            for path in modules_path.to_string_lossy().split(':') {
+                fs_importer.add_path(path);
+            }
+        }
+        let importer = ChainedImporter::new(
+            Box::new(fs_importer),
+            Box::<BuiltinModuleImporter>::default(),
+        );
+
+        let mut base_context = Context::new(importer);
+        let _ = base_context
+            .interpret("use prelude", CodeSource::Internal)
+            .map_err(|e| anyhow::anyhow!("Could not load the prelude: {e}"))?;
+
+        Ok(Self {
+            base_context,
+            documents: HashMap::new(),
+        })
+    }
+
+    fn publish_diagnostics(&self, connection: &Connection, uri: Url) -> Result<()> {
+        let diagnostics = match self.documents.get(&uri) {
+            Some(text) => analysis::diagnostics(&self.base_context, &uri, text),
+            None => vec![],
+        };
+
+        let params = PublishDiagnosticsParams::new(uri, diagnostics, None);
+        connection
+            .sender
+            .send(Message::Notification(Notification::new(
+                PublishDiagnostics::METHOD.into(),
+                params,
+            )))?;
+
+        Ok(())
+    }
+
+    fn handle_request(&self, request: Request) -> Result<Option<Response>> {
+        let request = match cast_request::<HoverRequest>(request) {
+            Ok((id, params)) => {
+                let position = params.text_document_position_params;
+                let uri = position.text_document.uri;
+                let result = self.documents.get(&uri).and_then(|text| {
+                    analysis::hover(&self.base_context, &uri, text, position.position)
+                });
+                return Ok(Some(Response::new_ok(id, result)));
+            }
+            Err(ExtractError::MethodMismatch(request)) => request,
+            Err(ExtractError::JsonError { method, error }) => {
+                anyhow::bail!("Invalid parameters for '{method}': {error}")
+            }
+        };
+
+        let request = match cast_request::<GotoDefinition>(request) {
+            Ok((id, params)) => {
+                let position = params.text_document_position_params;
+                let uri = position.text_document.uri;
+                let result = self
+                    .documents
+                    .get(&uri)
+                    .and_then(|text| {
+                        analysis::definition(&self.base_context, &uri, text, position.position)
+                    })
+                    .map(GotoDefinitionResponse::Scalar);
+                return Ok(Some(Response::new_ok(id, result)));
+            }
+            Err(ExtractError::MethodMismatch(request)) => request,
+            Err(ExtractError::JsonError { method, error }) => {
+                anyhow::bail!("Invalid parameters for '{method}': {error}")
+            }
+        };
+
+        match cast_request::<Completion>(request) {
+            Ok((id, params)) => {
+                let position = params.text_document_position;
+                let uri = position.text_document.uri;
+                let result = self
+                    .documents
+                    .get(&uri)
+                    .map(|text| {
+                        analysis::completions(&self.base_context, &uri, text, position.position)
+                    })
+                    .unwrap_or_default();
+                Ok(Some(Response::new_ok(id, result)))
+            }
+            Err(ExtractError::MethodMismatch(_)) => Ok(None),
+            Err(ExtractError::JsonError { method, error }) => {
+                anyhow::bail!("Invalid parameters for '{method}': {error}")
+            }
+        }
+    }
+
+    fn handle_notification(
+        &mut self,
+        connection: &Connection,
+        notification: Notification,
+    ) -> Result<()> {
+        match notification.method.as_str() {
+            DidOpenTextDocument::METHOD => {
+                let params = cast_notification::<DidOpenTextDocument>(notification)?;
+                let uri = params.text_document.uri;
+                self.documents
+                    .insert(uri.clone(), params.text_document.text);
+                self.publish_diagnostics(connection, uri)?;
+            }
+            DidChangeTextDocument::METHOD => {
+                let params = cast_notification::<DidChangeTextDocument>(notification)?;
+                let uri = params.text_document.uri;
+                // We only support full document synchronization, so the last change
+                // contains the full text.
+                if let Some(change) = params.content_changes.into_iter().last() {
+                    self.documents.insert(uri.clone(), change.text);
+                }
+                self.publish_diagnostics(connection, uri)?;
+            }
+            DidCloseTextDocument::METHOD => {
+                let params = cast_notification::<DidCloseTextDocument>(notification)?;
+                let uri = params.text_document.uri;
+                self.documents.remove(&uri);
+                self.publish_diagnostics(connection, uri)?;
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    fn run(&mut self, connection: &Connection) -> Result<()> {
+        for message in &connection.receiver {
+            match message {
+                Message::Request(request) => {
+                    if connection.handle_shutdown(&request)? {
+                        return Ok(());
+                    }
+                    if let Some(response) = self.handle_request(request)? {
+                        connection.sender.send(Message::Response(response))?;
+                    }
+                }
+                Message::Response(_) => {}
+                Message::Notification(notification) => {
+                    self.handle_notification(connection, notification)?
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn cast_request<R>(request: Request) -> Result<(RequestId, R::Params), ExtractError<Request>>
+where
+    R: lsp_types::request::Request,
+    R::Params: serde::de::DeserializeOwned,
+{
+    request.extract(R::METHOD)
+}
+
+fn cast_notification<N>(notification: Notification) -> Result<N::Params>
+where
+    N: lsp_types::notification::Notification,
+    N::Params: serde::de::DeserializeOwned,
+{
+    Ok(notification.extract(N::METHOD)?)
+}
+
+fn server_capabilities() -> ServerCapabilities {
+    ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+        hover_provider: Some(HoverProviderCapability::Simple(true)),
+        completion_provider: Some(CompletionOptions {
+            trigger_characters: Some(vec![":".into()]),
+            ..Default::default()
+        }),
+        definition_provider: Some(OneOf::Left(true)),
+        ..Default::default()
+    }
+}
+
+fn main() -> Result<()> {
+    let (connection, io_threads) = Connection::stdio();
+
+    connection.initialize(serde_json::to_value(server_capabilities())?)?;
+
+    let mut server = Server::new()?;
+    server.run(&connection)?;
+
+    drop(connection);
+    io_threads.join()?;
+
+    Ok(())
+}
@@ -0,0 +1,68 @@
+//! Conversion between byte offsets (as used in Numbat spans) and LSP positions,
+//! which consist of a zero-based line number and a column that is measured in
+//! UTF-16 code units.
+
+use lsp_types::Position;
+
+pub fn offset_to_position(text: &str, offset: usize) -> Position {
+    let offset = offset.min(text.len());
+    let before = &text[..offset];
+
+    let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+    let line = before.matches('\n').count();
+    let character: usize = before[line_start..].chars().map(char::len_utf16).sum();
+
+    Position::new(line as u32, character as u32)
+}
+
+pub fn position_to_offset(text: &str, position: Position) -> usize {
+    let mut line_start = 0;
+    for _ in 0..position.line {
+        match text[line_start..].find('\n') {
+            Some(i) => line_start += i + 1,
+            None => return text.len(),
+        }
+    }
+
+    let mut remaining = position.character as usize;
+    for (i, c) in text[line_start..].char_indices() {
+        if remaining == 0 || c == '\n' {
+            return line_start + i;
+        }
+        remaining = remaining.saturating_sub(c.len_utf16());
+    }
+
+    text.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let text = "let x = 1 m\nlet π² = 2 µs\n  # comment\n";
+
+        for (offset, (line, character)) in [
+            (0, (0, 0)),
+            (4, (0, 4)),
+            (12, (1, 0)),
+            (16, (1, 4)),
+            // 'π' and '²' are one UTF-16 code unit, but two bytes each
+            (18, (1, 5)),
+            (20, (1, 6)),
+            (text.len(), (3, 0)),
+        ] {
+            let position = Position::new(line, character);
+            assert_eq!(offset_to_position(text, offset), position);
+            assert_eq!(position_to_offset(text, position), offset);
+        }
+    }
+
+    #[test]
+    fn out_of_range() {
+        let text = "1 + 2\n3";
+        assert_eq!(position_to_offset(text, Position::new(0, 100)), 5);
+        assert_eq!(position_to_offset(text, Position::new(10, 0)), text.len());
+    }
+}
@@ -0,0 +1,26 @@
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+use numbat::module_importer::BuiltinModuleImporter;
+use numbat::resolver::CodeSource;
+use numbat::Context;
+
+/// Create a snapshot of the state after loading the prelude, which is embedded
+/// into the library (see `PRELUDE_SNAPSHOT`).
+fn main() {
+    let path = PathBuf::from(env::var_os("OUT_DIR").unwrap()).join("prelude.snapshot");
+
+    let snapshot = if env::var_os("CARGO_FEATURE_PRELUDE_SNAPSHOT").is_some() {
+        let mut ctx = Context::new(BuiltinModuleImporter::default());
+        let _ = ctx
+            .interpret("use prelude", CodeSource::Internal)
+            .expect("The prelude can be loaded");
+        ctx.snapshot()
+    } else {
+        vec![]
+    };
+
+    fs::write(path, snapshot).expect("The snapshot can be written");
+    println!("cargo:rerun-if-changed=build.rs");
+}
@@ -81,6 +81,12 @@ impl std::io::Write for JqueryTerminalWriter {
                 let size = self.buffer.write(buf)?;
                 self.buffer.write_all("]".as_bytes())?;
                 Ok(size)
+            } else if color.fg() == Some(&Color::Yellow) {
+                self.buffer
+                    .write_all("[[;;;hl-diagnostic-yellow]".as_bytes())?;
+                let size = self.buffer.write(buf)?;
+                self.buffer.write_all("]".as_bytes())?;
+                Ok(size)
             } else if color.fg() == Some(&Color::Blue) {
                 self.buffer
                     .write_all("[[;;;hl-diagnostic-blue]".as_bytes())?;
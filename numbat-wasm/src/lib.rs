@@ -12,11 +12,16 @@ use numbat::markup::Formatter;
 use numbat::module_importer::BuiltinModuleImporter;
 use numbat::pretty_print::PrettyPrint;
 use numbat::resolver::CodeSource;
+use numbat::warning::WarningCategory;
 use numbat::{markup as m, NameResolutionError, NumbatError};
 use numbat::{Context, InterpreterSettings};
 
 use jquery_terminal_formatter::{JqueryTerminalFormatter, JqueryTerminalWriter};
 
+/// The state after loading the prelude, created by the build script. This is
+/// empty if the `prelude-snapshot` feature is disabled.
+const PRELUDE_SNAPSHOT: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/prelude.snapshot"));
+
 #[wasm_bindgen]
 pub fn setup_panic_hook() {
     utils::set_panic_hook();
@@ -54,10 +59,11 @@ impl InterpreterOutput {
 #[wasm_bindgen]
 impl Numbat {
     pub fn new(load_prelude: bool, enable_pretty_printing: bool, format_type: FormatType) -> Self {
-        let mut ctx = Context::new(BuiltinModuleImporter::default());
-        if load_prelude {
-            let _ = ctx.interpret("use prelude", CodeSource::Internal).unwrap();
-        }
+        let mut ctx = if load_prelude {
+            Self::context_with_prelude()
+        } else {
+            Context::new(BuiltinModuleImporter::default())
+        };
         ctx.set_terminal_width(Some(84)); // terminal width with current layout
         Numbat {
             ctx,
@@ -66,6 +72,19 @@ impl Numbat {
         }
     }
 
+    /// Restore the prelude from the snapshot that was created at build time. If
+    /// no snapshot is available, prelude modules are loaded on demand instead.
+    fn context_with_prelude() -> Context {
+        if let Ok(ctx) = Context::from_snapshot(PRELUDE_SNAPSHOT, BuiltinModuleImporter::default())
+        {
+            return ctx;
+        }
+
+        let mut ctx = Context::new(BuiltinModuleImporter::default());
+        ctx.load_module_on_demand("prelude").unwrap();
+        ctx
+    }
+
     pub fn set_exchange_rates(&mut self, xml_content: &str) {
         Context::set_exchange_rates(xml_content);
         let _ = self
@@ -91,6 +110,7 @@ impl Numbat {
             print_fn: Box::new(move |s: &m::Markup| {
                 to_be_printed_c.lock().unwrap().push(s.clone());
             }),
+            ..Default::default()
         };
 
         let nl = &self.format(&numbat::markup::nl(), false);
@@ -100,11 +120,17 @@ impl Numbat {
             FormatType::Html => false,
         };
 
-        match self
+        let result = self
             .ctx
             .interpret_with_settings(&mut settings, code, CodeSource::Text)
-            .map_err(|b| *b)
-        {
+            .map_err(|b| *b);
+
+        // Warnings are shown before the results (or errors)
+        for warning in self.ctx.take_warnings() {
+            output.push_str(&self.render_diagnostics(&warning));
+        }
+
+        match result {
             Ok((statements, result)) => {
                 // Pretty print
                 if self.enable_pretty_printing {
@@ -137,34 +163,39 @@ impl Numbat {
                     is_error: false,
                 }
             }
-            Err(NumbatError::ResolverError(e)) => self.print_diagnostic(&e),
+            Err(NumbatError::ResolverError(e)) => self.print_diagnostic(output, &e),
             Err(NumbatError::NameResolutionError(
                 e @ (NameResolutionError::IdentifierClash { .. }
                 | NameResolutionError::ReservedIdentifier(_)),
-            )) => self.print_diagnostic(&e),
-            Err(NumbatError::TypeCheckError(e)) => self.print_diagnostic(&e),
-            Err(NumbatError::RuntimeError(e)) => self.print_diagnostic(&e),
+            )) => self.print_diagnostic(output, &e),
+            Err(NumbatError::TypeCheckError(e)) => self.print_diagnostic(output, &e),
+            Err(NumbatError::RuntimeError(e)) => self.print_diagnostic(output, &e),
         }
     }
 
-    pub fn print_environment(&self) -> JsValue {
-        self.format(&self.ctx.print_environment(), false).into()
+    pub fn print_environment(&mut self) -> JsValue {
+        let output = self.ctx.print_environment();
+        self.format(&output, false).into()
     }
 
-    pub fn print_functions(&self) -> JsValue {
-        self.format(&self.ctx.print_functions(), false).into()
+    pub fn print_functions(&mut self) -> JsValue {
+        let output = self.ctx.print_functions();
+        self.format(&output, false).into()
     }
 
-    pub fn print_dimensions(&self) -> JsValue {
-        self.format(&self.ctx.print_dimensions(), false).into()
+    pub fn print_dimensions(&mut self) -> JsValue {
+        let output = self.ctx.print_dimensions();
+        self.format(&output, false).into()
     }
 
-    pub fn print_variables(&self) -> JsValue {
-        self.format(&self.ctx.print_variables(), false).into()
+    pub fn print_variables(&mut self) -> JsValue {
+        let output = self.ctx.print_variables();
+        self.format(&output, false).into()
     }
 
-    pub fn print_units(&self) -> JsValue {
-        self.format(&self.ctx.print_units(), false).into()
+    pub fn print_units(&mut self) -> JsValue {
+        let output = self.ctx.print_units();
+        self.format(&output, false).into()
     }
 
     pub fn help(&self) -> JsValue {
@@ -183,7 +214,27 @@ impl Numbat {
             .collect()
     }
 
-    fn print_diagnostic(&self, error: &dyn ErrorDiagnostic) -> InterpreterOutput {
+    /// Enable or disable warnings of the given category, e.g. 'precision-loss'.
+    pub fn set_warning_enabled(&mut self, category: &str, enabled: bool) {
+        if let Ok(category) = category.parse::<WarningCategory>() {
+            self.ctx.set_warning_enabled(category, enabled);
+        }
+    }
+
+    fn print_diagnostic(
+        &self,
+        mut output: String,
+        error: &dyn ErrorDiagnostic,
+    ) -> InterpreterOutput {
+        output.push_str(&self.render_diagnostics(error));
+
+        InterpreterOutput {
+            output,
+            is_error: true,
+        }
+    }
+
+    fn render_diagnostics(&self, error: &dyn ErrorDiagnostic) -> String {
         use codespan_reporting::term::{self, Config};
 
         let mut writer: Box<dyn BufferedWriter> = match self.format_type {
@@ -198,9 +249,6 @@ impl Numbat {
             term::emit(&mut writer, &config, &resolver.files, &diagnostic).unwrap();
         }
 
-        InterpreterOutput {
-            output: writer.to_string(),
-            is_error: true,
-        }
+        writer.to_string()
     }
 }
@@ -14,5 +14,14 @@ fn import_prelude(c: &mut Criterion) {
     });
 }
 
-criterion_group!(benches, import_prelude);
+fn restore_prelude_snapshot(c: &mut Criterion) {
+    let mut context = Context::new(BuiltinModuleImporter::default());
+    let _ = context.interpret("use prelude", CodeSource::Text).unwrap();
+    let snapshot = context.snapshot();
+    c.bench_function("Restore prelude snapshot", |b| {
+        b.iter(|| Context::from_snapshot(&snapshot, BuiltinModuleImporter::default()))
+    });
+}
+
+criterion_group!(benches, import_prelude, restore_prelude_snapshot);
 criterion_main!(benches);
@@ -3,7 +3,7 @@ use itertools::Itertools;
 use numbat::markup::plain_text_format;
 use numbat::module_importer::FileSystemImporter;
 use numbat::resolver::CodeSource;
-use numbat::Context;
+use numbat::{Context, InterpreterSettings};
 use std::path::Path;
 use std::process::exit;
 
@@ -100,9 +100,17 @@ fn inspect_functions_in_module(ctx: &Context, prelude_ctx: &Context, module: Str
                     .interpret(&extra_import, CodeSource::Internal)
                     .unwrap();
 
-                if let Ok((statements, results)) =
-                    example_ctx.interpret(&example_code, CodeSource::Internal)
-                {
+                // Output of 'print' or 'trace' would end up in the middle of the document
+                let mut settings = InterpreterSettings {
+                    print_fn: Box::new(|_| {}),
+                    ..Default::default()
+                };
+
+                if let Ok((statements, results)) = example_ctx.interpret_with_settings(
+                    &mut settings,
+                    &example_code,
+                    CodeSource::Internal,
+                ) {
                     let example_input = extra_import + &example_code;
 
                     //Encode the example url
@@ -8,6 +8,7 @@ use crate::{
 use compact_str::{format_compact, CompactString, ToCompactString};
 use itertools::Itertools;
 use num_traits::Signed;
+use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum UnaryOperator {
@@ -268,7 +269,7 @@ pub(crate) use scalar;
 #[cfg(test)]
 pub(crate) use struct_;
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum TypeAnnotation {
     TypeExpression(TypeExpression),
     Bool(Span),
@@ -323,8 +324,7 @@ impl PrettyPrint for TypeAnnotation {
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
-
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum TypeExpression {
     Unity(Span),
     TypeIdentifier(Span, CompactString),
@@ -402,7 +402,7 @@ pub enum ProcedureKind {
     Type,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum TypeParameterBound {
     Dim,
 }
@@ -451,6 +451,14 @@ pub enum Statement<'a> {
         struct_name: &'a str,
         fields: Vec<(Span, &'a str, TypeAnnotation)>,
     },
+    /// A loop over the elements of a list: `for x in list { … }`
+    ForLoop {
+        loop_variable_span: Span,
+        loop_variable: &'a str,
+        list: Expression<'a>,
+        /// Definitions, procedure calls and nested loops
+        body: Vec<Statement<'a>>,
+    },
 }
 
 #[cfg(test)]
@@ -693,6 +701,17 @@ impl ReplaceSpans for Statement<'_> {
                     .map(|(_span, name, type_)| (Span::dummy(), *name, type_.replace_spans()))
                     .collect(),
             },
+            Statement::ForLoop {
+                loop_variable,
+                list,
+                body,
+                ..
+            } => Statement::ForLoop {
+                loop_variable_span: Span::dummy(),
+                loop_variable,
+                list: list.replace_spans(),
+                body: body.replace_spans(),
+            },
         }
     }
 }
@@ -1,18 +1,26 @@
 use std::collections::HashMap;
 
+use codespan_reporting::files::SimpleFiles;
 use compact_str::{CompactString, ToCompactString};
 use itertools::Itertools;
+use serde::{Deserialize, Serialize};
 
 use crate::ast::ProcedureKind;
 use crate::decorator::Decorator;
 use crate::dimension::DimensionRegistry;
+use crate::ffi::{Callable, RegisteredFunction};
 use crate::interpreter::{
     Interpreter, InterpreterResult, InterpreterSettings, Result, RuntimeError,
 };
+use crate::module_importer::ModuleImporter;
 use crate::name_resolution::LAST_RESULT_IDENTIFIERS;
 use crate::prefix::Prefix;
 use crate::prefix_parser::AcceptsPrefix;
 use crate::pretty_print::PrettyPrint;
+use crate::profiler::Profiler;
+use crate::span::Span;
+use crate::typechecker::qualified_type::QualifiedType;
+use crate::typechecker::type_scheme::TypeScheme;
 use crate::typed_ast::{
     BinaryOperator, DefineVariable, Expression, Statement, StringPart, UnaryOperator,
 };
@@ -22,22 +30,23 @@ use crate::value::{FunctionReference, Value};
 use crate::vm::{Constant, ExecutionContext, Op, Vm};
 use crate::{decorator, ffi, Type};
 
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct LocalMetadata {
     pub name: Option<CompactString>,
     pub url: Option<CompactString>,
     pub description: Option<CompactString>,
+    pub deprecated: Option<CompactString>,
     pub aliases: Vec<CompactString>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Local {
     identifier: CompactString,
     depth: usize,
     pub metadata: LocalMetadata,
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct BytecodeInterpreter {
     vm: Vm,
     /// List of local variables currently in scope, one vector for each scope (for now: 0: 'global' scope, 1: function scope)
@@ -164,8 +173,20 @@ impl BytecodeInterpreter {
                 }
 
                 if let Some(idx) = self.vm.get_ffi_callable_idx(name) {
-                    // TODO: check overflow:
-                    self.vm.add_op2(Op::FFICallFunction, idx, args.len() as u16);
+                    if let Callable::FunctionWithSpans(..) = self.vm.ffi_callable(idx).callable {
+                        let arg_spans = args.iter().map(|a| a.full_span()).collect();
+                        let spans_idx = self.vm.add_procedure_arg_span(arg_spans);
+
+                        self.vm.add_op3(
+                            Op::FFICallFunctionWithSpans,
+                            idx,
+                            args.len() as u16,
+                            spans_idx,
+                        );
+                    } else {
+                        // TODO: check overflow:
+                        self.vm.add_op2(Op::FFICallFunction, idx, args.len() as u16);
+                    }
                 } else {
                     let idx = self.vm.get_function_idx(name);
 
@@ -246,21 +267,27 @@ impl BytecodeInterpreter {
                 let if_jump_offset = self.vm.current_offset() + 1; // +1 for the opcode
                 self.vm.add_op1(Op::JumpIfFalse, 0xffff);
 
+                self.add_source_location(then_expr.full_span());
                 self.compile_expression(then_expr)?;
 
                 let else_jump_offset = self.vm.current_offset() + 1;
                 self.vm.add_op1(Op::Jump, 0xffff);
 
                 let else_block_offset = self.vm.current_offset();
-                self.vm
-                    .patch_u16_value_at(if_jump_offset, else_block_offset - (if_jump_offset + 2));
+                self.vm.patch_u16_value_at(
+                    if_jump_offset,
+                    (else_block_offset - (if_jump_offset + 2)) as u16,
+                );
 
+                self.add_source_location(else_expr.full_span());
                 self.compile_expression(else_expr)?;
 
                 let end_offset = self.vm.current_offset();
 
-                self.vm
-                    .patch_u16_value_at(else_jump_offset, end_offset - (else_jump_offset + 2));
+                self.vm.patch_u16_value_at(
+                    else_jump_offset,
+                    (end_offset - (else_jump_offset + 2)) as u16,
+                );
             }
             Expression::List(_, elements, _) => {
                 for element in elements {
@@ -290,12 +317,16 @@ impl BytecodeInterpreter {
             name: crate::decorator::name(decorators).map(CompactString::from),
             url: crate::decorator::url(decorators).map(CompactString::from),
             description: crate::decorator::description(decorators),
+            deprecated: crate::decorator::deprecated(decorators).map(CompactString::from),
             aliases: aliases.clone(),
         };
 
+        self.add_source_location(expr.full_span());
+
         for alias_name in aliases {
             self.compile_expression(expr)?;
 
+            self.vm.add_local_name(&alias_name);
             self.locals[current_depth].push(Local {
                 identifier: alias_name.clone(),
                 depth: current_depth,
@@ -312,6 +343,7 @@ impl BytecodeInterpreter {
     ) -> Result<()> {
         match stmt {
             Statement::Expression(expr) => {
+                self.add_source_location(expr.full_span());
                 self.compile_expression(expr)?;
                 self.vm.add_op(Op::Return);
             }
@@ -335,16 +367,13 @@ impl BytecodeInterpreter {
 
                 let current_depth = self.current_depth();
                 for parameter in parameters {
-                    self.locals[current_depth].push(Local {
-                        identifier: parameter.1.to_compact_string(),
-                        depth: current_depth,
-                        metadata: LocalMetadata::default(),
-                    });
+                    self.add_local(parameter.1, current_depth);
                 }
                 for local_variables in local_variables {
                     self.compile_define_variable(local_variables)?;
                 }
 
+                self.add_source_location(expr.full_span());
                 self.compile_expression(expr)?;
 
                 self.vm.add_op(Op::Return);
@@ -362,15 +391,30 @@ impl BytecodeInterpreter {
                 parameters,
                 None,
                 _local_variables,
-                _return_type,
+                function_type,
                 _return_type_annotation,
-                _readable_return_type,
+                readable_return_type,
             ) => {
                 // Declaring a foreign function does not generate any bytecode. But we register
                 // its name and arity here to be able to distinguish it from normal functions.
 
-                self.vm
-                    .add_foreign_function(name, parameters.len()..=parameters.len());
+                let (TypeScheme::Concrete(Type::Fn(_, return_type))
+                | TypeScheme::Quantified(
+                    _,
+                    QualifiedType {
+                        inner: Type::Fn(_, return_type),
+                        ..
+                    },
+                )) = function_type
+                else {
+                    unreachable!("Functions have a function type")
+                };
+                self.vm.add_foreign_function(
+                    name,
+                    parameters.len()..=parameters.len(),
+                    return_type,
+                    readable_return_type,
+                );
 
                 self.functions.insert(name.to_compact_string(), true);
             }
@@ -400,6 +444,7 @@ impl BytecodeInterpreter {
                             ),
                             url: decorator::url(decorators).map(CompactString::from),
                             description: decorator::description(decorators),
+                            deprecated: decorator::deprecated(decorators).map(CompactString::from),
                             binary_prefixes: decorators.contains(&Decorator::BinaryPrefixes),
                             metric_prefixes: decorators.contains(&Decorator::MetricPrefixes),
                         },
@@ -450,11 +495,13 @@ impl BytecodeInterpreter {
                         canonical_name: decorator::get_canonical_unit_name(unit_name, decorators),
                         url: decorator::url(decorators).map(CompactString::from),
                         description: decorator::description(decorators),
+                        deprecated: decorator::deprecated(decorators).map(CompactString::from),
                         binary_prefixes: decorators.contains(&Decorator::BinaryPrefixes),
                         metric_prefixes: decorators.contains(&Decorator::MetricPrefixes),
                     },
                 ); // TODO: there is some asymmetry here because we do not introduce identifiers for base units
 
+                self.add_source_location(expr.full_span());
                 self.compile_expression(expr)?;
                 self.vm
                     .add_op2(Op::SetUnitConstant, unit_information_idx, constant_idx);
@@ -476,6 +523,14 @@ impl BytecodeInterpreter {
                 self.vm.add_op1(Op::PrintString, idx);
             }
             Statement::ProcedureCall(kind, args) => {
+                if let Some(span) = args
+                    .iter()
+                    .map(|a| a.full_span())
+                    .reduce(|a, b| a.extend(&b))
+                {
+                    self.add_source_location(span);
+                }
+
                 // Put all arguments on top of the stack
                 for arg in args {
                     self.compile_expression(arg)?;
@@ -499,14 +554,70 @@ impl BytecodeInterpreter {
             Statement::DefineStruct(struct_info) => {
                 self.vm.add_struct_info(struct_info);
             }
+            Statement::ForLoop(loop_variable, list, body) => {
+                let current_depth = self.current_depth();
+                let num_locals_before = self.locals[current_depth].len();
+
+                self.add_source_location(list.full_span());
+                self.compile_expression(list)?;
+
+                // The elements that have not been visited yet are kept in a local
+                // variable that can not be referred to by name.
+                self.add_local("<list>", current_depth);
+
+                let loop_start_offset = self.vm.current_offset();
+                let exit_jump_offset = self.vm.current_offset() + 1; // +1 for the opcode
+                self.vm.add_op1(Op::IterateList, 0xffff);
+
+                self.add_local(loop_variable, current_depth);
+
+                for statement in body {
+                    self.compile_statement(statement, dimension_registry)?;
+                }
+
+                // Remove the loop variable and the definitions in the loop body
+                let num_body_locals = self.locals[current_depth].len() - (num_locals_before + 1);
+                self.vm.add_op1(Op::Pop, loop_operand(num_body_locals)?);
+                self.locals[current_depth].truncate(num_locals_before + 1);
+                self.vm.truncate_local_names(num_locals_before + 1);
+
+                let loop_offset = self.vm.current_offset() + 3; // opcode and operand
+                self.vm
+                    .add_op1(Op::Loop, loop_operand(loop_offset - loop_start_offset)?);
+
+                // The list has been popped off by `IterateList` when the loop exits
+                let end_offset = self.vm.current_offset();
+                self.vm.patch_u16_value_at(
+                    exit_jump_offset,
+                    loop_operand(end_offset - (exit_jump_offset + 2))?,
+                );
+                self.locals[current_depth].truncate(num_locals_before);
+                self.vm.truncate_local_names(num_locals_before);
+            }
         }
 
         Ok(())
     }
 
-    fn run(&mut self, settings: &mut InterpreterSettings) -> Result<InterpreterResult> {
+    fn run(
+        &mut self,
+        settings: &mut InterpreterSettings,
+        files: &SimpleFiles<String, String>,
+        importer: &dyn ModuleImporter,
+    ) -> Result<InterpreterResult> {
         let mut ctx = ExecutionContext {
             print_fn: &mut settings.print_fn,
+            files,
+            importer,
+            debugger: settings.debugger.as_mut(),
+            limits: &settings.limits,
+            allow_side_effects: settings.allow_side_effects,
+            interrupt: settings.interrupt.as_deref(),
+            result_fn: settings.result_fn.as_deref_mut(),
+            plot_fn: settings.plot_fn.as_deref_mut(),
+            arguments: &settings.arguments,
+            read_line_fn: &mut settings.read_line_fn,
+            allocated_list_elements: 0,
         };
 
         self.vm.disassemble();
@@ -529,10 +640,44 @@ impl BytecodeInterpreter {
         self.vm.set_debug(activate);
     }
 
+    pub(crate) fn set_profiling(&mut self, activate: bool) {
+        self.vm.set_profiling(activate);
+    }
+
+    pub(crate) fn profiler(&self) -> Option<&Profiler> {
+        self.vm.profiler()
+    }
+
+    pub(crate) fn register_native_function(
+        &mut self,
+        name: &str,
+        function: RegisteredFunction,
+    ) -> Option<RegisteredFunction> {
+        self.vm.register_native_function(name, function)
+    }
+
+    pub(crate) fn unregister_native_function(&mut self, name: &str) {
+        self.vm.unregister_native_function(name);
+    }
+
     fn current_depth(&self) -> usize {
         self.locals.len() - 1
     }
 
+    fn add_local(&mut self, identifier: &str, depth: usize) {
+        self.vm.add_local_name(identifier);
+        self.locals[depth].push(Local {
+            identifier: identifier.to_compact_string(),
+            depth,
+            metadata: LocalMetadata::default(),
+        });
+    }
+
+    fn add_source_location(&mut self, span: Span) {
+        let num_locals = self.locals[self.current_depth()].len();
+        self.vm.add_source_location(span, num_locals);
+    }
+
     pub fn get_defining_unit(&self, unit_name: &str) -> Option<&Unit> {
         self.unit_name_to_constant_index
             .get(unit_name)
@@ -548,6 +693,11 @@ impl BytecodeInterpreter {
     }
 }
 
+/// Convert a stack size or jump distance inside a for loop to a 16-bit operand.
+fn loop_operand(value: usize) -> Result<u16> {
+    u16::try_from(value).map_err(|_| Box::new(RuntimeError::LoopTooLarge))
+}
+
 impl Interpreter for BytecodeInterpreter {
     fn new() -> Self {
         Self {
@@ -563,12 +713,14 @@ impl Interpreter for BytecodeInterpreter {
         settings: &mut InterpreterSettings,
         statements: &[Statement],
         dimension_registry: &DimensionRegistry,
+        files: &SimpleFiles<String, String>,
+        importer: &dyn ModuleImporter,
     ) -> Result<InterpreterResult> {
         for statement in statements {
             self.compile_statement(statement, dimension_registry)?;
         }
 
-        self.run(settings)
+        self.run(settings, files, importer)
     }
 
     fn get_unit_registry(&self) -> &UnitRegistry {
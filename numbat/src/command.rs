@@ -19,6 +19,7 @@ pub enum ListItems {
     Dimensions,
     Variables,
     Units,
+    Modules,
 }
 
 enum QuitAlias {
@@ -64,6 +65,7 @@ pub enum CommandControlFlow {
 }
 
 #[derive(Debug)]
+#[allow(clippy::large_enum_variant)]
 pub enum CommandError {
     Parse(ParseError),
     Runtime(RuntimeError),
@@ -275,13 +277,14 @@ impl<Editor> CommandRunner<Editor> {
                     Some("dimensions") => Some(ListItems::Dimensions),
                     Some("variables") => Some(ListItems::Variables),
                     Some("units") => Some(ListItems::Units),
+                    Some("modules") => Some(ListItems::Modules),
                     _ => {
                         return Err(Box::new(
                             parser
                                 .err_at_idx(
                                     1,
                                     "if provided, the argument to `list` must be \
-                                             one of: functions, dimensions, variables, units",
+                                             one of: functions, dimensions, variables, units, modules",
                                 )
                                 .into(),
                         ));
@@ -346,6 +349,17 @@ impl<Editor> CommandRunner<Editor> {
                     return Ok(None);
                 };
 
+                // `exit (…)` is a call to the `exit` function, not the command
+                if matches!(quit_alias, QuitAlias::Exit)
+                    && parser
+                        .args
+                        .clone()
+                        .next()
+                        .is_some_and(|arg| arg.starts_with('('))
+                {
+                    return Ok(None);
+                }
+
                 parser
                     .ensure_zero_args(
                         match quit_alias {
@@ -397,6 +411,7 @@ impl<Editor> CommandRunner<Editor> {
                     Some(ListItems::Dimensions) => ctx.print_dimensions(),
                     Some(ListItems::Variables) => ctx.print_variables(),
                     Some(ListItems::Units) => ctx.print_units(),
+                    Some(ListItems::Modules) => ctx.print_modules(),
                 };
                 print_fn(&markup);
                 CommandControlFlow::Continue
@@ -805,6 +820,14 @@ mod test {
                 items: Some(ListItems::Units),
             },
         );
+        expect_ok(
+            &runner,
+            &mut ctx,
+            "list modules",
+            BareCommand::List {
+                items: Some(ListItems::Modules),
+            },
+        );
 
         expect_ok(&runner, &mut ctx, "reset", BareCommand::Reset);
         expect_fail(&runner, &mut ctx, "reset arg");
@@ -817,6 +840,7 @@ mod test {
         expect_ok(&runner, &mut ctx, "exit", BareCommand::Quit);
         expect_fail(&runner, &mut ctx, "exit arg");
         expect_fail(&runner, &mut ctx, "exit arg1 arg2");
+        assert!(runner.get_command("exit (1)", &mut ctx).unwrap().is_none());
 
         expect_ok(
             &runner,
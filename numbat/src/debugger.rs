@@ -0,0 +1,346 @@
+//! Interactive debugging of Numbat programs.
+//!
+//! A [`Debugger`] can be attached to a run of the interpreter via
+//! [`InterpreterSettings`](crate::InterpreterSettings). The compiler records a
+//! table of *source locations* (statements, local variable definitions, function
+//! bodies and branches of conditionals) that maps bytecode offsets back to spans
+//! in the source code. Whenever the VM is about to execute the first instruction
+//! of such a location, the debugger decides whether execution should pause. If so,
+//! the [`DebugHandler`] is called with a [`PausedState`] that can be used to inspect
+//! the call stack and the values of local variables.
+
+use codespan_reporting::files::{Files, SimpleFiles};
+use compact_str::CompactString;
+
+use crate::span::Span;
+use crate::value::Value;
+
+/// What to do after the debugger paused.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DebugCommand {
+    /// Run until the next breakpoint is hit.
+    Continue,
+    /// Pause at the next source location, possibly inside a called function.
+    StepIn,
+    /// Pause at the next source location in the current function (or a caller).
+    StepOver,
+    /// Pause at the next source location after the current function returned.
+    StepOut,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Run,
+    StepIn,
+    StepOver { depth: usize },
+    StepOut { depth: usize },
+}
+
+/// A single entry in the call stack of a paused program.
+#[derive(Debug, Clone)]
+pub struct StackFrame {
+    /// Name of the function, `<main>` for the global scope.
+    pub function_name: CompactString,
+    /// The source location that is currently executed in this frame.
+    pub span: Option<Span>,
+    /// Local variables (function parameters and `where` definitions) that have
+    /// already been evaluated. For the global scope, these are the variables that
+    /// have been defined by the code that is being debugged.
+    pub locals: Vec<(CompactString, Value)>,
+}
+
+/// A position in a source file, with one-based line and column numbers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceLocation {
+    pub file_name: String,
+    pub line: usize,
+    pub column: usize,
+    pub line_text: String,
+}
+
+impl SourceLocation {
+    pub(crate) fn of(files: &SimpleFiles<String, String>, span: Span) -> Option<Self> {
+        let file_id = span.code_source_id;
+        let start = span.start.as_usize();
+
+        let line_index = files.line_index(file_id, start).ok()?;
+        let line_range = files.line_range(file_id, line_index).ok()?;
+        let source = files.get(file_id).ok()?.source();
+
+        Some(SourceLocation {
+            file_name: files.name(file_id).ok()?,
+            line: line_index + 1,
+            column: source[line_range.start..start].chars().count() + 1,
+            line_text: source[line_range].trim_end().to_string(),
+        })
+    }
+}
+
+/// The state of the program at the point where the debugger paused.
+pub struct PausedState<'a> {
+    pub(crate) span: Span,
+    pub(crate) call_stack: Vec<StackFrame>,
+    pub(crate) globals: Vec<(&'a str, &'a Value)>,
+    pub(crate) files: &'a SimpleFiles<String, String>,
+}
+
+impl PausedState<'_> {
+    /// The span of the source location that is about to be executed.
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
+    /// The call stack, with the innermost frame last.
+    pub fn call_stack(&self) -> &[StackFrame] {
+        &self.call_stack
+    }
+
+    pub fn current_frame(&self) -> &StackFrame {
+        self.call_stack.last().expect("Call stack is not empty")
+    }
+
+    /// Look up the value of a variable, first in the current frame, then in
+    /// the global scope.
+    pub fn lookup(&self, name: &str) -> Option<&Value> {
+        self.current_frame()
+            .locals
+            .iter()
+            .rev()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v)
+            .or_else(|| {
+                self.globals
+                    .iter()
+                    .rev()
+                    .find(|(n, _)| *n == name)
+                    .map(|(_, v)| *v)
+            })
+    }
+
+    /// Convert a span into a file name, line and column.
+    pub fn source_location(&self, span: Span) -> Option<SourceLocation> {
+        SourceLocation::of(self.files, span)
+    }
+}
+
+pub trait DebugHandler: Send {
+    /// Called whenever execution is paused. Breakpoints can be added or removed
+    /// before execution resumes.
+    fn on_pause(&mut self, state: &PausedState, breakpoints: &mut Vec<Span>) -> DebugCommand;
+}
+
+pub struct Debugger {
+    handler: Box<dyn DebugHandler>,
+    breakpoints: Vec<Span>,
+    mode: Mode,
+    files: SimpleFiles<String, String>,
+}
+
+impl Debugger {
+    /// Create a new debugger that pauses at the very first source location.
+    pub fn new(handler: impl DebugHandler + 'static) -> Self {
+        Self {
+            handler: Box::new(handler),
+            breakpoints: vec![],
+            mode: Mode::StepIn,
+            files: SimpleFiles::new(),
+        }
+    }
+
+    /// Do not pause at the first source location, but only when a breakpoint is hit.
+    pub fn run_to_breakpoint(mut self) -> Self {
+        self.mode = Mode::Run;
+        self
+    }
+
+    /// Pause whenever a source location that starts within `span` is reached.
+    pub fn add_breakpoint(&mut self, span: Span) {
+        self.breakpoints.push(span);
+    }
+
+    pub fn breakpoints(&self) -> &[Span] {
+        &self.breakpoints
+    }
+
+    pub(crate) fn set_files(&mut self, files: SimpleFiles<String, String>) {
+        self.files = files;
+    }
+
+    fn is_breakpoint(&self, span: &Span) -> bool {
+        self.breakpoints.iter().any(|b| {
+            b.code_source_id == span.code_source_id && b.start <= span.start && span.start <= b.end
+        })
+    }
+
+    /// Decide whether to pause at a source location with the given span, `depth`
+    /// being the current size of the call stack.
+    pub(crate) fn should_pause(&self, span: &Span, depth: usize) -> bool {
+        let step = match self.mode {
+            Mode::Run => false,
+            Mode::StepIn => true,
+            Mode::StepOver { depth: d } => depth <= d,
+            Mode::StepOut { depth: d } => depth < d,
+        };
+
+        step || self.is_breakpoint(span)
+    }
+
+    pub(crate) fn pause<'a>(
+        &'a mut self,
+        depth: usize,
+        make_state: impl FnOnce(&'a SimpleFiles<String, String>) -> PausedState<'a>,
+    ) {
+        let state = make_state(&self.files);
+        let command = self.handler.on_pause(&state, &mut self.breakpoints);
+
+        self.mode = match command {
+            DebugCommand::Continue => Mode::Run,
+            DebugCommand::StepIn => Mode::StepIn,
+            DebugCommand::StepOver => Mode::StepOver { depth },
+            DebugCommand::StepOut => Mode::StepOut { depth },
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+    use crate::resolver::CodeSource;
+    use crate::{Context, InterpreterSettings};
+
+    static CODE: &str = "fn f(x) = y + 1
+  where y = 2 x
+let a = 3
+let b = f(a)
+b
+";
+
+    type Log = Arc<Mutex<Vec<(CompactString, usize, Vec<CompactString>)>>>;
+
+    struct ScriptedHandler {
+        commands: Vec<DebugCommand>,
+        log: Log,
+    }
+
+    impl DebugHandler for ScriptedHandler {
+        fn on_pause(&mut self, state: &PausedState, _: &mut Vec<Span>) -> DebugCommand {
+            let frame = state.current_frame();
+            let line = state.source_location(state.span()).unwrap().line;
+            let locals = frame
+                .locals
+                .iter()
+                .map(|(name, value)| format!("{name}={value}").into())
+                .collect();
+            self.log
+                .lock()
+                .unwrap()
+                .push((frame.function_name.clone(), line, locals));
+
+            if self.commands.is_empty() {
+                DebugCommand::Continue
+            } else {
+                self.commands.remove(0)
+            }
+        }
+    }
+
+    fn debug(
+        commands: &[DebugCommand],
+        breakpoint_line: Option<usize>,
+    ) -> Vec<(CompactString, usize, Vec<CompactString>)> {
+        let mut ctx = Context::new_without_importer();
+        let code_source_id = ctx.resolver().next_code_source_id();
+
+        let log = Log::default();
+        let mut debugger = Debugger::new(ScriptedHandler {
+            commands: commands.to_vec(),
+            log: log.clone(),
+        });
+        if let Some(line) = breakpoint_line {
+            debugger = debugger.run_to_breakpoint();
+            let start = CODE
+                .lines()
+                .take(line - 1)
+                .map(|l| l.len() + 1)
+                .sum::<usize>();
+            let end = start + CODE.lines().nth(line - 1).unwrap().len();
+            debugger.add_breakpoint(Span {
+                start: (start as u32).into(),
+                end: (end as u32).into(),
+                code_source_id,
+            });
+        }
+
+        let mut settings = InterpreterSettings {
+            print_fn: Box::new(|_| {}),
+            debugger: Some(debugger),
+            ..Default::default()
+        };
+        let _ = ctx
+            .interpret_with_settings(&mut settings, CODE, CodeSource::Internal)
+            .unwrap();
+
+        let log = log.lock().unwrap().clone();
+        log
+    }
+
+    fn pause(
+        function_name: &str,
+        line: usize,
+        locals: &[&str],
+    ) -> (CompactString, usize, Vec<CompactString>) {
+        (
+            function_name.into(),
+            line,
+            locals.iter().map(|l| CompactString::from(*l)).collect(),
+        )
+    }
+
+    #[test]
+    fn step_in() {
+        use DebugCommand::StepIn;
+        assert_eq!(
+            debug(&[StepIn, StepIn, StepIn, StepIn], None),
+            vec![
+                pause("<main>", 3, &[]),
+                pause("<main>", 4, &["a=3"]),
+                pause("f", 2, &["x=3"]),
+                pause("f", 1, &["x=3", "y=6"]),
+                pause("<main>", 5, &["a=3", "b=7"]),
+            ]
+        );
+    }
+
+    #[test]
+    fn step_over_and_out() {
+        use DebugCommand::*;
+        assert_eq!(
+            debug(&[StepIn, StepOver], None),
+            vec![
+                pause("<main>", 3, &[]),
+                pause("<main>", 4, &["a=3"]),
+                pause("<main>", 5, &["a=3", "b=7"]),
+            ]
+        );
+        assert_eq!(
+            debug(&[StepIn, StepIn, StepOut], None),
+            vec![
+                pause("<main>", 3, &[]),
+                pause("<main>", 4, &["a=3"]),
+                pause("f", 2, &["x=3"]),
+                pause("<main>", 5, &["a=3", "b=7"]),
+            ]
+        );
+    }
+
+    #[test]
+    fn breakpoints() {
+        assert_eq!(debug(&[], Some(2)), vec![pause("f", 2, &["x=3"])]);
+        assert_eq!(
+            debug(&[], Some(5)),
+            vec![pause("<main>", 5, &["a=3", "b=7"])]
+        );
+    }
+}
@@ -11,6 +11,8 @@ pub enum Decorator<'a> {
     Name(CompactString),
     Description(CompactString),
     Example(CompactString, Option<CompactString>),
+    Test,
+    Deprecated(CompactString),
 }
 
 /// Get an iterator of data computed from a name and/or its alias's `AcceptsPrefix` and
@@ -105,6 +107,15 @@ pub fn url<'a>(decorators: &'a [Decorator<'a>]) -> Option<&'a str> {
     None
 }
 
+pub fn deprecated<'a>(decorators: &'a [Decorator<'a>]) -> Option<&'a str> {
+    for decorator in decorators {
+        if let Decorator::Deprecated(message) = decorator {
+            return Some(message);
+        }
+    }
+    None
+}
+
 pub fn description(decorators: &[Decorator]) -> Option<CompactString> {
     let mut description = CompactString::with_capacity(decorators.len());
     for decorator in decorators {
@@ -152,6 +163,10 @@ pub fn contains_aliases(decorators: &[Decorator]) -> bool {
     false
 }
 
+pub fn contains_test(decorators: &[Decorator]) -> bool {
+    decorators.contains(&Decorator::Test)
+}
+
 pub fn contains_examples(decorators: &[Decorator]) -> bool {
     for decorator in decorators {
         if let Decorator::Example(_, _) = decorator {
@@ -6,7 +6,8 @@ use crate::{
     pretty_print::PrettyPrint,
     resolver::ResolverError,
     typechecker::{IncompatibleDimensionsError, TypeCheckError},
-    NameResolutionError,
+    warning::Warning,
+    NameResolutionError, NumbatError,
 };
 
 pub type Diagnostic = codespan_reporting::diagnostic::Diagnostic<usize>;
@@ -15,6 +16,17 @@ pub trait ErrorDiagnostic {
     fn diagnostics(&self) -> Vec<Diagnostic>;
 }
 
+impl ErrorDiagnostic for NumbatError {
+    fn diagnostics(&self) -> Vec<Diagnostic> {
+        match self {
+            NumbatError::ResolverError(e) => e.diagnostics(),
+            NumbatError::NameResolutionError(e) => e.diagnostics(),
+            NumbatError::TypeCheckError(e) => e.diagnostics(),
+            NumbatError::RuntimeError(e) => e.diagnostics(),
+        }
+    }
+}
+
 impl ErrorDiagnostic for ParseError {
     fn diagnostics(&self) -> Vec<Diagnostic> {
         vec![Diagnostic::error()
@@ -78,6 +90,9 @@ impl ErrorDiagnostic for TypeCheckError {
         let inner_error = format!("{self}");
 
         let d = match self {
+            TypeCheckError::MultipleErrors(errors) => {
+                return errors.iter().flat_map(|e| e.diagnostics()).collect();
+            }
             TypeCheckError::UnknownIdentifier(span, _, suggestion) => {
                 let notes = if let Some(suggestion) = suggestion {
                     vec![format!("Did you mean '{suggestion}'?")]
@@ -188,6 +203,8 @@ impl ErrorDiagnostic for TypeCheckError {
                         },
                         range = if arity.start() == arity.end() {
                             format!("{}", arity.start())
+                        } else if *arity.end() == usize::MAX {
+                            format!("at least {}", arity.start())
                         } else {
                             format!("{} to {}", arity.start(), arity.end())
                         }
@@ -335,6 +352,7 @@ impl ErrorDiagnostic for TypeCheckError {
             | TypeCheckError::OverflowInConstExpr(span)
             | TypeCheckError::ExpectedDimensionType(span, _)
             | TypeCheckError::ExpectedBool(span)
+            | TypeCheckError::ExpectedListInForLoop(span, _)
             | TypeCheckError::NoFunctionReferenceToGenericFunction(span)
             | TypeCheckError::OnlyFunctionsAndReferencesCanBeCalled(span)
             | TypeCheckError::DerivedUnitDefinitionMustNotBeGeneric(span)
@@ -538,3 +556,54 @@ impl ErrorDiagnostic for RuntimeError {
         }
     }
 }
+
+impl ErrorDiagnostic for Warning {
+    fn diagnostics(&self) -> Vec<Diagnostic> {
+        let d = Diagnostic::warning().with_message(self.to_string());
+
+        let d = match self {
+            Warning::PrecisionLoss(span) => d.with_labels(vec![span
+                .diagnostic_label(LabelStyle::Primary)
+                .with_message("will be rounded to the nearest floating point number")]),
+            Warning::Redefinition {
+                span,
+                original_span,
+                ..
+            } => d.with_labels(vec![
+                original_span
+                    .diagnostic_label(LabelStyle::Secondary)
+                    .with_message("Previously defined here"),
+                span.diagnostic_label(LabelStyle::Primary)
+                    .with_message("shadows the previous definition"),
+            ]),
+            Warning::ImplicitScalarExponent(span) => d.with_labels(vec![span
+                .diagnostic_label(LabelStyle::Primary)
+                .with_message("add a type annotation if this is intended")]),
+            Warning::AmbiguousUnit {
+                span, other_span, ..
+            } => d.with_labels(vec![
+                other_span
+                    .diagnostic_label(LabelStyle::Secondary)
+                    .with_message("other unit defined here"),
+                span.diagnostic_label(LabelStyle::Primary)
+                    .with_message("introduces the ambiguity"),
+            ]),
+            Warning::Deprecated {
+                span,
+                definition_span,
+                ..
+            } => d.with_labels(vec![
+                definition_span
+                    .diagnostic_label(LabelStyle::Secondary)
+                    .with_message("marked as deprecated here"),
+                span.diagnostic_label(LabelStyle::Primary)
+                    .with_message("used here"),
+            ]),
+        };
+
+        vec![d.with_notes(vec![format!(
+            "suppress this warning with category '{}'",
+            self.category()
+        )])]
+    }
+}
@@ -1,4 +1,5 @@
 use compact_str::{CompactString, ToCompactString};
+use serde::{Deserialize, Serialize};
 
 use crate::arithmetic::{Exponent, Power};
 use crate::ast::{TypeExpression, TypeParameterBound};
@@ -6,7 +7,7 @@ use crate::registry::{BaseRepresentation, Registry, Result};
 use crate::span::Span;
 use crate::BaseRepresentationFactor;
 
-#[derive(Default, Clone)]
+#[derive(Default, Clone, Serialize, Deserialize)]
 pub struct DimensionRegistry {
     registry: Registry<()>,
     pub introduced_type_parameters: Vec<(Span, CompactString, Option<TypeParameterBound>)>,
@@ -0,0 +1,236 @@
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use compact_str::{format_compact, CompactString};
+use serde::ser::{Serialize, SerializeMap, Serializer};
+
+use super::macros::*;
+use super::Args;
+use super::Result;
+use crate::quantity::Quantity;
+use crate::value::Value;
+use crate::vm::ExecutionContext;
+use crate::RuntimeError;
+
+/// Paths that exist relative to the current working directory (and absolute
+/// paths) are used as they are. Other relative paths are looked up in the
+/// search paths of the module importer.
+fn resolve_path(ctx: &ExecutionContext, path: &str) -> PathBuf {
+    let path = Path::new(path);
+    if path.is_relative() && !path.exists() {
+        if let Some(found) = ctx.importer.find_file(path) {
+            return found;
+        }
+    }
+    path.to_owned()
+}
+
+/// A column of a CSV file
+struct CsvColumn {
+    /// The resolved path of the file
+    path: PathBuf,
+    /// The (1-based) number of the column
+    number: usize,
+    /// The cells of the column, along with their (1-based) line numbers
+    cells: Vec<(u64, CompactString)>,
+}
+
+/// Read the cells of the column with the given header from a CSV file.
+fn read_csv_column(ctx: &ExecutionContext, path: &str, column: &str) -> Result<CsvColumn> {
+    let path = resolve_path(ctx, path);
+
+    let file = File::open(&path).map_err(|_| RuntimeError::FileRead(path.clone()))?;
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_reader(file);
+
+    let invalid_data = |e: csv::Error| RuntimeError::InvalidDataFile(path.clone(), e.to_string());
+
+    let index = reader
+        .headers()
+        .map_err(invalid_data)?
+        .iter()
+        .position(|header| header == column)
+        .ok_or_else(|| RuntimeError::ColumnNotFound(path.clone(), column.to_string()))?;
+
+    let mut cells = vec![];
+    for record in reader.records() {
+        let record = record.map_err(invalid_data)?;
+        let line = record.position().map(|p| p.line()).unwrap_or_default();
+        cells.push((line, record[index].into()));
+    }
+
+    Ok(CsvColumn {
+        path,
+        number: index + 1,
+        cells,
+    })
+}
+
+pub fn read_csv(ctx: &mut ExecutionContext, mut args: Args) -> Result<Value> {
+    let path = string_arg!(args);
+    let column = string_arg!(args);
+    let unit = quantity_arg!(args);
+
+    let column = read_csv_column(ctx, &path, &column)?;
+
+    ctx.allocate_list(column.cells.len(), column.cells.len())?;
+
+    let factor = unit.unsafe_value().to_f64();
+    let mut values = VecDeque::with_capacity(column.cells.len());
+    for (line, cell) in column.cells {
+        let Ok(value) = cell.parse::<f64>() else {
+            return Err(Box::new(RuntimeError::InvalidNumberInDataFile(
+                column.path,
+                line,
+                column.number,
+                cell.to_string(),
+            )));
+        };
+        values.push_back(Value::Quantity(Quantity::new_f64(
+            value * factor,
+            unit.unit().clone(),
+        )));
+    }
+
+    Ok(values.into())
+}
+
+pub fn read_csv_strings(ctx: &mut ExecutionContext, mut args: Args) -> Result<Value> {
+    let path = string_arg!(args);
+    let column = string_arg!(args);
+
+    let column = read_csv_column(ctx, &path, &column)?;
+    ctx.allocate_list(column.cells.len(), column.cells.len())?;
+
+    let strings: VecDeque<_> = column
+        .cells
+        .into_iter()
+        .map(|(_, cell)| Value::String(cell))
+        .collect();
+
+    Ok(strings.into())
+}
+
+pub fn csv_column(ctx: &mut ExecutionContext, mut args: Args) -> Result<Value> {
+    let values = list_arg!(args);
+    let unit = quantity_arg!(args);
+
+    ctx.allocate_list(values.len(), values.len())?;
+
+    let factor = unit.unsafe_value().to_f64();
+    let cells = values
+        .iter()
+        .map(|value| {
+            let converted = value
+                .clone()
+                .unsafe_as_quantity()
+                .convert_to(unit.unit())
+                .map_err(RuntimeError::QuantityError)?;
+            let cell = converted.unsafe_value().to_f64() / factor;
+            Ok(Value::String(format_compact!("{cell}")))
+        })
+        .collect::<Result<VecDeque<_>>>()?;
+
+    Ok(cells.into())
+}
+
+pub fn write_csv(mut args: Args) -> Result<Value> {
+    let path = string_arg!(args);
+    let headers: Vec<_> = list_arg!(args)
+        .iter()
+        .map(|header| header.clone().unsafe_as_string())
+        .collect();
+    let columns: Vec<Vec<_>> = list_arg!(args)
+        .iter()
+        .map(|column| {
+            column
+                .clone()
+                .unsafe_as_list()
+                .iter()
+                .map(|cell| cell.clone().unsafe_as_string())
+                .collect()
+        })
+        .collect();
+
+    if headers.len() != columns.len() {
+        return Err(Box::new(RuntimeError::CsvHeaderCountMismatch(
+            headers.len(),
+            columns.len(),
+        )));
+    }
+    let num_rows = columns.first().map(|column| column.len()).unwrap_or(0);
+    if let Some(column) = columns.iter().find(|c| c.len() != num_rows) {
+        return Err(Box::new(RuntimeError::CsvColumnLengthMismatch(
+            num_rows,
+            column.len(),
+        )));
+    }
+
+    let file_path = PathBuf::from(path.as_str());
+    let file_write_error = |_| RuntimeError::FileWrite(file_path.clone());
+    let mut writer = csv::Writer::from_path(&file_path).map_err(file_write_error)?;
+    writer
+        .write_record(headers.iter().map(|h| h.as_str()))
+        .map_err(file_write_error)?;
+    for row in 0..num_rows {
+        writer
+            .write_record(columns.iter().map(|column| column[row].as_str()))
+            .map_err(file_write_error)?;
+    }
+    writer
+        .flush()
+        .map_err(|_| RuntimeError::FileWrite(file_path.clone()))?;
+
+    return_string!(owned = path)
+}
+
+/// A plain JSON representation of a value: quantities are written as objects
+/// with a value and a unit, structs as objects with their fields.
+struct JsonData<'a>(&'a Value);
+
+impl Serialize for JsonData<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        match self.0 {
+            Value::Quantity(q) => {
+                // Zero can be converted to any unit, so simplifying it would drop the unit
+                let q = if q.is_zero() {
+                    q.clone()
+                } else {
+                    q.full_simplify()
+                };
+                let mut map = serializer.serialize_map(Some(2))?;
+                map.serialize_entry("value", &q.unsafe_value().to_f64())?;
+                map.serialize_entry("unit", &q.unit().to_string())?;
+                map.end()
+            }
+            Value::Boolean(b) => serializer.serialize_bool(*b),
+            Value::String(s) => serializer.serialize_str(s),
+            Value::DateTime(dt) => serializer.collect_str(dt),
+            Value::FunctionReference(reference) => serializer.collect_str(reference),
+            Value::FormatSpecifiers(specifiers) => specifiers.serialize(serializer),
+            Value::StructInstance(info, values) => {
+                serializer.collect_map(info.fields.keys().zip(values.iter().map(JsonData)))
+            }
+            Value::List(elements) => serializer.collect_seq(elements.iter().map(JsonData)),
+        }
+    }
+}
+
+pub fn write_json(mut args: Args) -> Result<Value> {
+    let path = string_arg!(args);
+    let value = arg!(args);
+
+    let file_path = PathBuf::from(path.as_str());
+    let write = || -> std::io::Result<()> {
+        let mut writer = BufWriter::new(File::create(&file_path)?);
+        serde_json::to_writer_pretty(&mut writer, &JsonData(&value))?;
+        writeln!(writer)?;
+        writer.flush()
+    };
+    write().map_err(|_| Box::new(RuntimeError::FileWrite(file_path.clone())))?;
+
+    return_string!(owned = path)
+}
@@ -0,0 +1,48 @@
+use std::collections::VecDeque;
+
+use compact_str::CompactString;
+
+use super::macros::*;
+use super::Args;
+use super::Result;
+use crate::value::Value;
+use crate::vm::ExecutionContext;
+use crate::RuntimeError;
+
+pub fn args(ctx: &mut ExecutionContext, _args: Args) -> Result<Value> {
+    ctx.allocate_list(ctx.arguments.len(), ctx.arguments.len())?;
+
+    let arguments: VecDeque<_> = ctx.arguments.iter().cloned().map(Value::String).collect();
+
+    Ok(arguments.into())
+}
+
+pub fn env(mut args: Args) -> Result<Value> {
+    let name = string_arg!(args);
+
+    match std::env::var(name.as_str()) {
+        Ok(value) => return_string!(owned = value.into()),
+        Err(_) => Err(Box::new(RuntimeError::EnvironmentVariableNotSet(
+            name.to_string(),
+        ))),
+    }
+}
+
+pub fn read_line(ctx: &mut ExecutionContext, mut args: Args) -> Result<Value> {
+    let prompt = string_arg!(args);
+
+    let line = (ctx.read_line_fn)(&prompt).ok_or(RuntimeError::EndOfInput)?;
+    let line = line.trim_end_matches(['\n', '\r']);
+
+    return_string!(owned = CompactString::from(line))
+}
+
+pub fn exit(mut args: Args) -> Result<Value> {
+    let code = scalar_arg!(args).to_f64();
+
+    if code.fract() == 0.0 && (0.0..=255.0).contains(&code) {
+        Err(Box::new(RuntimeError::Exit(code as i32)))
+    } else {
+        Err(Box::new(RuntimeError::InvalidExitCode(code)))
+    }
+}
@@ -1,7 +1,14 @@
 use std::collections::HashMap;
 use std::sync::OnceLock;
 
+use compact_str::{format_compact, CompactString, ToCompactString};
+
 use super::{macros::*, Args};
+use crate::debugger::SourceLocation;
+use crate::markup as m;
+use crate::pretty_print::PrettyPrint;
+use crate::span::Span;
+use crate::vm::ExecutionContext;
 use crate::{quantity::Quantity, value::Value, RuntimeError};
 
 use super::{Callable, ForeignFunction, Result};
@@ -10,7 +17,9 @@ static FFI_FUNCTIONS: OnceLock<HashMap<String, ForeignFunction>> = OnceLock::new
 
 pub(crate) fn functions() -> &'static HashMap<String, ForeignFunction> {
     use super::currency::*;
+    use super::data::*;
     use super::datetime::*;
+    use super::environment::*;
     use super::lists::*;
     use super::lookup::*;
     use super::math::*;
@@ -21,16 +30,20 @@ pub(crate) fn functions() -> &'static HashMap<String, ForeignFunction> {
         let mut m = HashMap::new();
 
         macro_rules! insert_function {
-            ($fn_name:expr, $callable:expr, $arity:expr) => {
+            ($fn_name:expr, $callable:expr, $arity:expr, side_effects: $side_effects:expr) => {
                 m.insert(
                     $fn_name.to_string(),
                     ForeignFunction {
-                        name: $fn_name,
+                        name: CompactString::const_new($fn_name),
                         arity: $arity,
                         callable: Callable::Function($callable),
+                        side_effects: $side_effects,
                     },
                 );
             };
+            ($fn_name:expr, $callable:expr, $arity:expr) => {
+                insert_function!($fn_name, $callable, $arity, side_effects: false);
+            };
             ($callable:expr, $arity:expr) => {
                 insert_function!(stringify!($callable), $callable, $arity);
             };
@@ -82,8 +95,24 @@ pub(crate) fn functions() -> &'static HashMap<String, ForeignFunction> {
         insert_function!(len, 1..=1);
         insert_function!(head, 1..=1);
         insert_function!(tail, 1..=1);
-        insert_function!(cons, 2..=2);
-        insert_function!(cons_end, 2..=2);
+        m.insert(
+            "cons".to_string(),
+            ForeignFunction {
+                name: CompactString::const_new("cons"),
+                arity: 2..=2,
+                callable: Callable::FunctionWithContext(cons),
+                side_effects: false,
+            },
+        );
+        m.insert(
+            "cons_end".to_string(),
+            ForeignFunction {
+                name: CompactString::const_new("cons_end"),
+                arity: 2..=2,
+                callable: Callable::FunctionWithContext(cons_end),
+                side_effects: false,
+            },
+        );
 
         // Strings
         insert_function!(str_length, 1..=1);
@@ -112,8 +141,80 @@ pub(crate) fn functions() -> &'static HashMap<String, ForeignFunction> {
         // Database lookup
         insert_function!(_get_chemical_element_data_raw, 1..=1);
 
+        // Debugging
+        m.insert(
+            "trace".to_string(),
+            ForeignFunction {
+                name: CompactString::const_new("trace"),
+                arity: 2..=2,
+                callable: Callable::FunctionWithSpans(trace),
+                side_effects: false,
+            },
+        );
+
+        // Environment
+        m.insert(
+            "args".to_string(),
+            ForeignFunction {
+                name: CompactString::const_new("args"),
+                arity: 0..=0,
+                callable: Callable::FunctionWithContext(args),
+                side_effects: true,
+            },
+        );
+        insert_function!("env", env, 1..=1, side_effects: true);
+        insert_function!("exit", exit, 1..=1, side_effects: true);
+        m.insert(
+            "read_line".to_string(),
+            ForeignFunction {
+                name: CompactString::const_new("read_line"),
+                arity: 1..=1,
+                callable: Callable::FunctionWithContext(read_line),
+                side_effects: true,
+            },
+        );
+
+        // Data files
+        m.insert(
+            "read_csv".to_string(),
+            ForeignFunction {
+                name: CompactString::const_new("read_csv"),
+                arity: 3..=3,
+                callable: Callable::FunctionWithContext(read_csv),
+                side_effects: true,
+            },
+        );
+        m.insert(
+            "read_csv_strings".to_string(),
+            ForeignFunction {
+                name: CompactString::const_new("read_csv_strings"),
+                arity: 2..=2,
+                callable: Callable::FunctionWithContext(read_csv_strings),
+                side_effects: true,
+            },
+        );
+        m.insert(
+            "csv_column".to_string(),
+            ForeignFunction {
+                name: CompactString::const_new("csv_column"),
+                arity: 2..=2,
+                callable: Callable::FunctionWithContext(csv_column),
+                side_effects: false,
+            },
+        );
+        insert_function!("write_csv", write_csv, 3..=3, side_effects: true);
+        insert_function!("write_json", write_json, 2..=2, side_effects: true);
+
         // Plotting
-        insert_function!(show, 1..=1);
+        m.insert(
+            "show".to_string(),
+            ForeignFunction {
+                name: CompactString::const_new("show"),
+                arity: 1..=1,
+                callable: Callable::FunctionWithContext(show),
+                side_effects: true,
+            },
+        );
 
         m
     })
@@ -125,6 +226,32 @@ fn error(mut args: Args) -> Result<Value> {
     )))
 }
 
+/// Print a labeled value, together with its location in the source code, and
+/// return it unchanged.
+fn trace(ctx: &mut ExecutionContext, mut args: Args, arg_spans: Vec<Span>) -> Result<Value> {
+    let label = arg!(args).unsafe_as_string().to_compact_string();
+    let value = arg!(args);
+
+    let location = arg_spans
+        .get(1)
+        .and_then(|span| SourceLocation::of(ctx.files, *span))
+        .map(|l| {
+            m::dimmed(format_compact!("[{}:{}:{}]", l.file_name, l.line, l.column)) + m::space()
+        })
+        .unwrap_or_default();
+
+    (ctx.print_fn)(
+        &(location
+            + m::text(label)
+            + m::space()
+            + m::operator("=")
+            + m::space()
+            + value.pretty_print()),
+    );
+
+    Ok(value)
+}
+
 fn value_of(mut args: Args) -> Result<Value> {
     let quantity = quantity_arg!(args);
 
@@ -2,6 +2,7 @@ use super::macros::*;
 use super::{Args, Result};
 use crate::quantity::Quantity;
 use crate::value::Value;
+use crate::vm::ExecutionContext;
 use crate::RuntimeError;
 
 pub fn len(mut args: Args) -> Result<Value> {
@@ -27,17 +28,19 @@ pub fn tail(mut args: Args) -> Result<Value> {
     Ok(list.into())
 }
 
-pub fn cons(mut args: Args) -> Result<Value> {
+pub fn cons(ctx: &mut ExecutionContext, mut args: Args) -> Result<Value> {
     let element = arg!(args);
     let mut list = list_arg!(args);
+    ctx.allocate_list(list.len() + 1, 1)?;
     list.push_front(element);
 
     return_list!(list)
 }
 
-pub fn cons_end(mut args: Args) -> Result<Value> {
+pub fn cons_end(ctx: &mut ExecutionContext, mut args: Args) -> Result<Value> {
     let element = arg!(args);
     let mut list = list_arg!(args);
+    ctx.allocate_list(list.len() + 1, 1)?;
     list.push_back(element);
 
     return_list!(list)
@@ -1,5 +1,7 @@
 mod currency;
+mod data;
 mod datetime;
+mod environment;
 mod functions;
 mod lists;
 mod lookup;
@@ -10,9 +12,14 @@ mod procedures;
 mod strings;
 
 use std::collections::VecDeque;
+use std::sync::Arc;
+
+use compact_str::CompactString;
 
 use crate::interpreter::RuntimeError;
 use crate::span::Span;
+use crate::typed_ast::Type;
+use crate::unit_registry::UnitRegistry;
 use crate::value::Value;
 use crate::vm::ExecutionContext;
 
@@ -24,16 +31,111 @@ type Result<T> = std::result::Result<T, Box<RuntimeError>>;
 
 pub(crate) type Args = VecDeque<Value>;
 
+/// A function that has been registered by the embedding application, see
+/// [`Context::register_function`](crate::Context::register_function).
+pub(crate) type NativeFunction = dyn Fn(Vec<Value>) -> Result<Value> + Send + Sync;
+
+/// A native function, along with whether calling it has effects outside of the interpreter
+#[derive(Clone)]
+pub(crate) struct RegisteredFunction {
+    pub(crate) function: Arc<NativeFunction>,
+    pub(crate) side_effects: bool,
+}
+
+#[derive(Clone)]
 pub(crate) enum Callable {
     Function(fn(Args) -> Result<Value>),
+    /// A function that needs access to the settings of the embedding application
+    FunctionWithContext(fn(&mut ExecutionContext, Args) -> Result<Value>),
+    /// A function that needs the source spans of its arguments
+    FunctionWithSpans(fn(&mut ExecutionContext, Args, Vec<Span>) -> Result<Value>),
     Procedure(fn(&mut ExecutionContext, Args, Vec<Span>) -> ControlFlow),
+    /// A native function, which is only trusted to return a value of its declared type
+    /// after checking it.
+    Native {
+        function: Arc<NativeFunction>,
+        return_type: Type,
+        readable_return_type: CompactString,
+    },
 }
 
+#[derive(Clone)]
 pub(crate) struct ForeignFunction {
-    pub(crate) name: &'static str,
+    pub(crate) name: CompactString,
     pub(crate) arity: ArityRange,
     pub(crate) callable: Callable,
+    /// Whether calling this function has effects outside of the interpreter
+    pub(crate) side_effects: bool,
+}
+
+impl ForeignFunction {
+    /// Call a foreign function. Procedures need to be called with their argument spans instead.
+    pub(crate) fn call(
+        &self,
+        ctx: &mut ExecutionContext,
+        args: Args,
+        unit_registry: &UnitRegistry,
+    ) -> Result<Value> {
+        match &self.callable {
+            Callable::Function(function) => function(args),
+            Callable::FunctionWithContext(function) => function(ctx, args),
+            // Called through a function reference, where the spans are not known
+            Callable::FunctionWithSpans(function) => function(ctx, args, vec![]),
+            Callable::Native {
+                function,
+                return_type,
+                readable_return_type,
+            } => {
+                let value = function(args.into())?;
+                if value.is_of_type(return_type, unit_registry) {
+                    Ok(value)
+                } else {
+                    Err(Box::new(RuntimeError::InvalidReturnValue(
+                        self.name.to_string(),
+                        readable_return_type.to_string(),
+                    )))
+                }
+            }
+            Callable::Procedure(..) => unreachable!("Procedures can not be called like functions"),
+        }
+    }
 }
 
 pub(crate) use functions::functions;
 pub(crate) use procedures::procedures;
+
+/// Look up a foreign function or procedure by name.
+pub(crate) fn lookup(name: &str) -> Option<&'static ForeignFunction> {
+    functions()
+        .get(name)
+        .or_else(|| procedures().values().find(|ff| ff.name == name))
+}
+
+/// (De)serialization of references to foreign functions, which are stored by name.
+pub(crate) mod serde_by_name {
+    use std::sync::Arc;
+
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    use super::ForeignFunction;
+
+    pub(crate) fn serialize<S: Serializer>(
+        callables: &[Arc<ForeignFunction>],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(callables.iter().map(|ff| ff.name.as_str()))
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<Arc<ForeignFunction>>, D::Error> {
+        Vec::<String>::deserialize(deserializer)?
+            .iter()
+            .map(|name| {
+                super::lookup(name)
+                    .map(|ff| Arc::new(ff.clone()))
+                    .ok_or_else(|| D::Error::custom(format!("unknown foreign function '{name}'")))
+            })
+            .collect()
+    }
+}
@@ -4,6 +4,7 @@ use super::macros::*;
 use super::Args;
 use super::Result;
 use crate::value::Value;
+use crate::vm::ExecutionContext;
 use crate::RuntimeError;
 use compact_str::CompactString;
 
@@ -96,7 +97,7 @@ fn show_plot(_plot: Plot) -> CompactString {
     CompactString::const_new("Plotting is currently not supported on this platform.")
 }
 
-pub fn show(args: Args) -> Result<Value> {
+pub fn show(ctx: &mut ExecutionContext, args: Args) -> Result<Value> {
     // Dynamic dispatch hack since we don't have bounded polymorphism.
     // And no real support for generics in the FFI.
     let Value::StructInstance(info, _) = args.front().unwrap() else {
@@ -116,5 +117,10 @@ pub fn show(args: Args) -> Result<Value> {
         ))));
     };
 
+    if let Some(plot_fn) = ctx.plot_fn.as_deref_mut() {
+        plot_fn(&plot.to_html());
+        return return_string!(borrowed = "");
+    }
+
     return_string!(owned = show_plot(plot))
 }
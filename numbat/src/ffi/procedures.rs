@@ -2,6 +2,8 @@ use std::collections::HashMap;
 
 use std::sync::OnceLock;
 
+use compact_str::CompactString;
+
 use super::macros::*;
 use crate::{
     ast::ProcedureKind,
@@ -25,25 +27,28 @@ pub(crate) fn procedures() -> &'static HashMap<ProcedureKind, ForeignFunction> {
         m.insert(
             ProcedureKind::Print,
             ForeignFunction {
-                name: "print",
+                name: CompactString::const_new("print"),
                 arity: 0..=1,
                 callable: Callable::Procedure(print),
+                side_effects: false,
             },
         );
         m.insert(
             ProcedureKind::Assert,
             ForeignFunction {
-                name: "assert",
+                name: CompactString::const_new("assert"),
                 arity: 1..=1,
                 callable: Callable::Procedure(assert),
+                side_effects: false,
             },
         );
         m.insert(
             ProcedureKind::AssertEq,
             ForeignFunction {
-                name: "assert_eq",
+                name: CompactString::const_new("assert_eq"),
                 arity: 2..=3,
                 callable: Callable::Procedure(assert_eq),
+                side_effects: false,
             },
         );
         // Note: The 'type' procedure is missing here because it has special handling code in the compiler
@@ -0,0 +1,560 @@
+//! A canonical source formatter for Numbat code.
+//!
+//! The formatter works on the token stream. It keeps the line structure
+//! and the comments of the original program, but normalises the horizontal
+//! spacing between tokens, the layout of `where`/`and` clauses and the
+//! placement of decorators. Formatting never changes the meaning of a
+//! program.
+
+use std::borrow::Cow;
+
+use crate::parser::{parse, ParseErrorKind};
+use crate::resolver::ResolverError;
+use crate::tokenizer::{tokenize, Token, TokenKind};
+use crate::ParseError;
+
+/// Which symbol to use for explicit multiplications.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MultiplicationOperator {
+    /// Keep the operator as written in the source.
+    #[default]
+    Preserve,
+    /// `*`
+    Asterisk,
+    /// `×`
+    Times,
+    /// `·`
+    Dot,
+}
+
+impl MultiplicationOperator {
+    fn symbol(self) -> Option<&'static str> {
+        match self {
+            MultiplicationOperator::Preserve => None,
+            MultiplicationOperator::Asterisk => Some("*"),
+            MultiplicationOperator::Times => Some("×"),
+            MultiplicationOperator::Dot => Some("·"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct FormatOptions {
+    pub multiplication_operator: MultiplicationOperator,
+}
+
+/// Indentation of `where` and `and` clauses, relative to the enclosing function definition
+const WHERE_INDENT: &str = "  ";
+const AND_INDENT: &str = "    ";
+
+/// The syntactic role of a token, where it can not be determined from
+/// the token kind alone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Role {
+    Normal,
+    /// Prefix `-`, `+` or `!`
+    Unary,
+    /// Postfix `!`
+    Factorial,
+    /// `<` and `>` around type parameters, e.g. in `List<D>`
+    GenericOpen,
+    GenericClose,
+}
+
+struct Line<'a> {
+    indent: Cow<'a, str>,
+    tokens: Vec<usize>,
+    comment: Option<&'a str>,
+}
+
+impl Line<'_> {
+    fn is_blank(&self) -> bool {
+        self.tokens.is_empty() && self.comment.is_none()
+    }
+}
+
+fn token_end(token: &Token) -> usize {
+    token.span.start.0 as usize + token.lexeme.len()
+}
+
+fn ends_value(kind: TokenKind, role: Role) -> bool {
+    use TokenKind::*;
+
+    match kind {
+        Number
+        | IntegerWithBase(_)
+        | Identifier
+        | RightParen
+        | RightBracket
+        | RightCurly
+        | StringFixed
+        | StringInterpolationEnd
+        | True
+        | False
+        | NaN
+        | Inf
+        | UnicodeExponent
+        | QuestionMark
+        | Bool
+        | String
+        | DateTime
+        | List => true,
+        ExclamationMark => role == Role::Factorial,
+        GreaterThan => role == Role::GenericClose,
+        _ => false,
+    }
+}
+
+fn assign_roles(tokens: &[Token]) -> Vec<Role> {
+    use TokenKind::*;
+
+    let mut roles = Vec::with_capacity(tokens.len());
+    let mut generic_depth = 0usize;
+    let mut bracket_depth = 0usize;
+
+    // The last two tokens before the current one, ignoring newlines
+    let mut last: [Option<(TokenKind, Role)>; 2] = [Option::None; 2];
+
+    for (i, token) in tokens.iter().enumerate() {
+        if token.kind == Newline {
+            roles.push(Role::Normal);
+            continue;
+        }
+
+        let after_newline = i > 0 && tokens[i - 1].kind == Newline;
+        let follows_value = !(after_newline && bracket_depth == 0)
+            && last[0].is_some_and(|(kind, role)| ends_value(kind, role));
+
+        let role = match token.kind {
+            Minus | Plus if !follows_value => Role::Unary,
+            ExclamationMark if follows_value => Role::Factorial,
+            ExclamationMark => Role::Unary,
+            LessThan
+                if matches!(last[0], Some((List | DoubleColon, _)))
+                    || (matches!(last[0], Some((Identifier, _)))
+                        && matches!(last[1], Some((Fn | Struct, _)))) =>
+            {
+                generic_depth += 1;
+                Role::GenericOpen
+            }
+            GreaterThan if generic_depth > 0 => {
+                generic_depth -= 1;
+                Role::GenericClose
+            }
+            _ => Role::Normal,
+        };
+
+        match token.kind {
+            LeftParen | LeftBracket | LeftCurly => bracket_depth += 1,
+            RightParen | RightBracket | RightCurly => {
+                bracket_depth = bracket_depth.saturating_sub(1)
+            }
+            _ => {}
+        }
+
+        last = [Some((token.kind, role)), last[0]];
+        roles.push(role);
+    }
+
+    roles
+}
+
+/// Decide whether there should be a single space between two adjacent
+/// tokens on the same line.
+fn space_between(prev: (&Token, Role), next: (&Token, Role)) -> bool {
+    use TokenKind::*;
+
+    let (prev, prev_role) = prev;
+    let (next, next_role) = next;
+    let had_space = token_end(prev) < next.span.start.0 as usize;
+
+    match (prev.kind, next.kind) {
+        (StringInterpolationStart | StringInterpolationMiddle, _) => false,
+        (_, StringInterpolationMiddle | StringInterpolationEnd | StringInterpolationSpecifiers) => {
+            false
+        }
+        (LeftParen | LeftBracket, _) | (_, RightParen | RightBracket) => false,
+        (LeftCurly, RightCurly) => false,
+        (LeftCurly, _) | (_, RightCurly) => true,
+        (_, Comma | Colon) => false,
+        (Comma | Colon, _) => true,
+        (DoubleColon | Period | At, _) | (_, DoubleColon | Period | Ellipsis) => false,
+        (Power, _) | (_, Power) | (_, UnicodeExponent) => false,
+        (_, _) if prev_role == Role::Unary => false,
+        (_, ExclamationMark) if next_role == Role::Factorial => false,
+        (_, LessThan) if next_role == Role::GenericOpen => false,
+        (LessThan, _) if prev_role == Role::GenericOpen => false,
+        (_, GreaterThan) if next_role == Role::GenericClose => false,
+        (
+            Identifier | RightParen | RightBracket | ProcedurePrint | ProcedureAssert
+            | ProcedureAssertEq | ProcedureType,
+            LeftParen,
+        ) => false,
+        (GreaterThan, LeftParen) if prev_role == Role::GenericClose => false,
+        (CapitalFn, LeftBracket) => false,
+        // `km/h` and `g/cm³` are commonly written without spaces, so we only
+        // insert spaces around a division if there was some whitespace before.
+        // The same holds for implicit multiplications like `2π` or `360°`.
+        (_, Divide) | (Divide, _) | (Number, Identifier) => had_space,
+        _ => true,
+    }
+}
+
+/// Split the token stream into lines and recover the comments that the
+/// tokenizer skipped over.
+fn split_into_lines<'a>(input: &'a str, tokens: &[Token<'a>]) -> Vec<Line<'a>> {
+    let mut lines = vec![];
+    let mut current = Line {
+        indent: Cow::Borrowed(""),
+        tokens: vec![],
+        comment: None,
+    };
+    let mut line_start = 0;
+    let mut position = 0;
+
+    for (i, token) in tokens.iter().enumerate() {
+        let start = token.span.start.0 as usize;
+        // The lexeme of a newline token includes a preceding comment
+        let gap = if token.kind == TokenKind::Newline {
+            &input[position..token_end(token)]
+        } else {
+            &input[position..start]
+        };
+        if let Some(offset) = gap.find('#') {
+            if current.tokens.is_empty() {
+                current.indent = Cow::Borrowed(&input[line_start..position + offset]);
+            }
+            current.comment = Some(gap[offset..].trim_end());
+        }
+
+        match token.kind {
+            TokenKind::Newline => {
+                lines.push(std::mem::replace(
+                    &mut current,
+                    Line {
+                        indent: Cow::Borrowed(""),
+                        tokens: vec![],
+                        comment: None,
+                    },
+                ));
+                line_start = token_end(token);
+            }
+            TokenKind::Eof => {}
+            _ => {
+                if current.tokens.is_empty() && current.comment.is_none() {
+                    current.indent = Cow::Borrowed(&input[line_start..start]);
+                }
+                current.tokens.push(i);
+            }
+        }
+
+        position = token_end(token).max(position);
+    }
+
+    let rest = &input[position..];
+    if let Some(offset) = rest.find('#') {
+        if current.tokens.is_empty() {
+            current.indent = Cow::Borrowed(&input[line_start..position + offset]);
+        }
+        current.comment = Some(rest[offset..].trim_end());
+    }
+    lines.push(current);
+
+    lines
+}
+
+/// Returns the number of tokens in a decorator like `@aliases(m: short)` at
+/// the start of the given line, or `None` if the line does not start with a
+/// (complete) decorator.
+fn decorator_length(tokens: &[Token], line: &[usize]) -> Option<usize> {
+    let kind = |i: usize| line.get(i).map(|&idx| tokens[idx].kind);
+
+    if kind(0) != Some(TokenKind::At) || kind(1) != Some(TokenKind::Identifier) {
+        return None;
+    }
+    if kind(2) != Some(TokenKind::LeftParen) {
+        return Some(2);
+    }
+
+    let mut depth = 0;
+    for i in 2..line.len() {
+        match kind(i) {
+            Some(TokenKind::LeftParen) => depth += 1,
+            Some(TokenKind::RightParen) => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i + 1);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    None
+}
+
+/// Put every decorator and every `where`/`and` clause on its own line.
+fn normalize_layout<'a>(tokens: &[Token<'a>], lines: Vec<Line<'a>>) -> Vec<Line<'a>> {
+    let mut result: Vec<Line<'a>> = vec![];
+    // The indentation of the last function definition, which `where`/`and` clauses belong to
+    let mut definition_indent: Cow<'a, str> = Cow::Borrowed("");
+
+    for line in lines {
+        let mut rest = line.tokens.as_slice();
+        let mut pieces = vec![];
+
+        while let Some(length) = decorator_length(tokens, rest) {
+            if length == rest.len() {
+                break;
+            }
+            pieces.push(&rest[..length]);
+            rest = &rest[length..];
+        }
+
+        let mut start = 0;
+        for i in 1..rest.len() {
+            if matches!(tokens[rest[i]].kind, TokenKind::Where | TokenKind::And) {
+                pieces.push(&rest[start..i]);
+                start = i;
+            }
+        }
+        pieces.push(&rest[start..]);
+
+        let number_of_pieces = pieces.len();
+        for (i, piece) in pieces.into_iter().enumerate() {
+            let indent = match piece.first().map(|&idx| tokens[idx].kind) {
+                Some(TokenKind::Where) => Cow::Owned(format!("{definition_indent}{WHERE_INDENT}")),
+                Some(TokenKind::And) => Cow::Owned(format!("{definition_indent}{AND_INDENT}")),
+                Some(TokenKind::Fn) => {
+                    definition_indent = line.indent.clone();
+                    line.indent.clone()
+                }
+                _ => line.indent.clone(),
+            };
+            let comment = if i + 1 == number_of_pieces {
+                line.comment
+            } else {
+                None
+            };
+
+            // Join a lone `where` with the definition on the next line
+            if let Some(previous) = result.last_mut() {
+                let previous_is_lone_where = previous.comment.is_none()
+                    && previous.tokens.len() == 1
+                    && tokens[previous.tokens[0]].kind == TokenKind::Where;
+                if previous_is_lone_where && !piece.is_empty() {
+                    previous.tokens.extend_from_slice(piece);
+                    previous.comment = comment;
+                    continue;
+                }
+            }
+
+            result.push(Line {
+                indent,
+                tokens: piece.to_vec(),
+                comment,
+            });
+        }
+    }
+
+    result
+}
+
+fn render_line(
+    output: &mut String,
+    tokens: &[Token],
+    roles: &[Role],
+    line: &Line,
+    options: &FormatOptions,
+) {
+    output.push_str(&line.indent);
+
+    for (i, &idx) in line.tokens.iter().enumerate() {
+        if i > 0 {
+            let prev = line.tokens[i - 1];
+            if space_between((&tokens[prev], roles[prev]), (&tokens[idx], roles[idx])) {
+                output.push(' ');
+            }
+        }
+
+        let token = &tokens[idx];
+        match (token.kind, options.multiplication_operator.symbol()) {
+            (TokenKind::Multiply, Some(symbol)) => output.push_str(symbol),
+            _ => output.push_str(token.lexeme),
+        }
+
+        // Keep the original spacing after a `-` or `+` at the start of a
+        // continuation line, where we can not tell unary and binary apart.
+        if i == 0
+            && roles[idx] == Role::Unary
+            && matches!(token.kind, TokenKind::Minus | TokenKind::Plus)
+        {
+            if let Some(&next) = line.tokens.get(1) {
+                if token_end(token) < tokens[next].span.start.0 as usize {
+                    output.push(' ');
+                }
+            }
+        }
+    }
+
+    if let Some(comment) = line.comment {
+        if !line.tokens.is_empty() {
+            output.push_str("  ");
+        }
+        output.push_str(comment);
+    }
+
+    while output.ends_with([' ', '\t']) {
+        output.pop();
+    }
+    output.push('\n');
+}
+
+/// Format the given Numbat program. Returns the parse errors if the input
+/// is not a valid program.
+pub fn format(input: &str, options: &FormatOptions) -> Result<String, ResolverError> {
+    const CODE_SOURCE_ID: usize = 0;
+
+    parse(input, CODE_SOURCE_ID).map_err(|(_, errors)| ResolverError::ParseErrors(errors))?;
+
+    let tokens = tokenize(input, CODE_SOURCE_ID).map_err(|e| {
+        ResolverError::ParseErrors(vec![ParseError {
+            kind: ParseErrorKind::TokenizerError(e.kind),
+            span: e.span,
+        }])
+    })?;
+    let roles = assign_roles(&tokens);
+    let lines = normalize_layout(&tokens, split_into_lines(input, &tokens));
+
+    let mut output = String::new();
+    let mut previous: Option<&Line> = None;
+    for line in &lines {
+        if line.is_blank() {
+            let skip = match previous {
+                None => true,
+                Some(previous) => {
+                    previous.is_blank() || decorator_length(&tokens, &previous.tokens).is_some()
+                }
+            };
+            if skip {
+                continue;
+            }
+        }
+
+        render_line(&mut output, &tokens, &roles, line, options);
+        previous = Some(line);
+    }
+
+    while output.ends_with("\n\n") {
+        output.pop();
+    }
+    if output == "\n" {
+        output.clear();
+    }
+
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast::ReplaceSpans;
+
+    fn fmt(input: &str) -> String {
+        format(input, &FormatOptions::default()).unwrap()
+    }
+
+    #[test]
+    fn spacing() {
+        assert_eq!(fmt("1+2*3"), "1 + 2 * 3\n");
+        assert_eq!(fmt("let  x=-3 m"), "let x = -3 m\n");
+        assert_eq!(fmt("2 ^ 3 - - 4"), "2^3 - -4\n");
+        assert_eq!(fmt("3 km/h -> m / s"), "3 km/h -> m / s\n");
+        assert_eq!(fmt("f( 1 ,2 )"), "f(1, 2)\n");
+        assert_eq!(fmt("5 ! == 120 && ! true"), "5! == 120 && !true\n");
+        assert_eq!(fmt("x² · y"), "x² · y\n");
+        assert_eq!(fmt("2π + 2 π"), "2π + 2 π\n");
+        assert_eq!(fmt("\"a {x+1 :.2} b\""), "\"a {x + 1:.2} b\"\n");
+        assert_eq!(
+            fmt("fn f < D : Dim > (xs:List < D >) -> D=head( xs )"),
+            "fn f<D: Dim>(xs: List<D>) -> D = head(xs)\n"
+        );
+        assert_eq!(fmt("use core :: scalar"), "use core::scalar\n");
+        assert_eq!(
+            fmt("struct Foo {a:Scalar}\nFoo {a : 1}.a"),
+            "struct Foo { a: Scalar }\nFoo { a: 1 }.a\n"
+        );
+    }
+
+    #[test]
+    fn multiplication_operator() {
+        let options = FormatOptions {
+            multiplication_operator: MultiplicationOperator::Times,
+        };
+        assert_eq!(format("2*3 · 4", &options).unwrap(), "2 × 3 × 4\n");
+    }
+
+    #[test]
+    fn layout() {
+        assert_eq!(
+            fmt("\n\n@metric_prefixes @aliases(metres:short)\n\nunit metre: Length  # SI\n\n\n\nlet x = 1\n\n"),
+            "@metric_prefixes\n@aliases(metres: short)\nunit metre: Length  # SI\n\nlet x = 1\n"
+        );
+        assert_eq!(
+            fmt("fn f(x) = y + z\n      where\n  y = x and z = x\n"),
+            "fn f(x) = y + z\n  where y = x\n    and z = x\n"
+        );
+        assert_eq!(
+            fmt("  fn f(x) =\n      y + z\nwhere y = x and z = x\n"),
+            "  fn f(x) =\n      y + z\n    where y = x\n      and z = x\n"
+        );
+        assert_eq!(
+            fmt("# comment\n  # indented comment\nlet x = 1"),
+            "# comment\n  # indented comment\nlet x = 1\n"
+        );
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert!(format("let x =", &FormatOptions::default()).is_err());
+    }
+
+    /// Debug representation of the AST, with all spans removed (including
+    /// the ones in decorators, which are not covered by `ReplaceSpans`).
+    fn ast_without_spans(code: &str) -> String {
+        let statements: Vec<_> = parse(code, 0)
+            .unwrap_or_else(|_| panic!("Failed to parse:\n{code}"))
+            .iter()
+            .map(|s| s.replace_spans())
+            .collect();
+        let mut debug = format!("{statements:?}");
+
+        while let Some(start) = debug.find("Span { start: ByteIndex(") {
+            let end = start + debug[start..].find('}').unwrap();
+            debug.replace_range(start..=end, "Span");
+        }
+
+        debug
+    }
+
+    #[test]
+    fn all_modules_round_trip() {
+        let modules_path = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("modules");
+
+        for entry in walkdir::WalkDir::new(modules_path) {
+            let entry = entry.unwrap();
+            if entry.path().extension() != Some("nbt".as_ref()) {
+                continue;
+            }
+
+            let code = std::fs::read_to_string(entry.path()).unwrap();
+            let formatted = fmt(&code);
+
+            let original_ast = ast_without_spans(&code);
+            let formatted_ast = ast_without_spans(&formatted);
+            assert_eq!(original_ast, formatted_ast, "{:?}", entry.path());
+
+            assert_eq!(formatted, fmt(&formatted), "{:?}", entry.path());
+        }
+    }
+}
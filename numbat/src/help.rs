@@ -5,21 +5,24 @@ use crate::module_importer::BuiltinModuleImporter;
 use crate::resolver::CodeSource;
 use crate::Context;
 use crate::InterpreterSettings;
+use crate::Result;
 
 use std::sync::{Arc, Mutex};
 
-fn evaluate_example(context: &mut Context, input: &str) -> m::Markup {
+/// Evaluate an example in the given context, and return the printed output as well
+/// as the result of the last statement.
+pub fn evaluate_example(context: &mut Context, input: &str) -> Result<m::Markup> {
     let statement_output: Arc<Mutex<Vec<m::Markup>>> = Arc::new(Mutex::new(vec![]));
     let statement_output_c = statement_output.clone();
     let mut settings = InterpreterSettings {
         print_fn: Box::new(move |s: &m::Markup| {
             statement_output_c.lock().unwrap().push(s.clone());
         }),
+        ..Default::default()
     };
 
-    let (statements, interpreter_result) = context
-        .interpret_with_settings(&mut settings, input, CodeSource::Internal)
-        .expect("No error in 'help' examples");
+    let (statements, interpreter_result) =
+        context.interpret_with_settings(&mut settings, input, CodeSource::Internal)?;
 
     let markup =
         statement_output
@@ -36,7 +39,7 @@ fn evaluate_example(context: &mut Context, input: &str) -> m::Markup {
                 true,
             );
 
-    markup
+    Ok(markup)
 }
 
 pub fn help_markup() -> m::Markup {
@@ -60,10 +63,13 @@ pub fn help_markup() -> m::Markup {
         r#"print("Energy of red photons: {ℏ ω -> eV}")"#,
     ];
     let mut example_context = Context::new(BuiltinModuleImporter::default());
-    let _use_prelude_output = evaluate_example(&mut example_context, "use prelude");
+    let _use_prelude_output =
+        evaluate_example(&mut example_context, "use prelude").expect("No error in 'help' examples");
     for example in examples.iter() {
         output += m::text(">>> ") + m::text(*example) + m::nl();
-        output += evaluate_example(&mut example_context, example) + m::nl();
+        output += evaluate_example(&mut example_context, example)
+            .expect("No error in 'help' examples")
+            + m::nl();
     }
     output
 }
@@ -78,6 +78,12 @@ impl std::io::Write for HtmlWriter {
                 let size = self.buffer.write(buf)?;
                 self.buffer.write_all("</span>".as_bytes())?;
                 Ok(size)
+            } else if color.fg() == Some(&Color::Yellow) {
+                self.buffer
+                    .write_all("<span class=\"numbat-diagnostic-yellow\">".as_bytes())?;
+                let size = self.buffer.write(buf)?;
+                self.buffer.write_all("</span>".as_bytes())?;
+                Ok(size)
             } else if color.fg() == Some(&Color::Blue) {
                 self.buffer
                     .write_all("<span class=\"numbat-diagnostic-blue\">".as_bytes())?;
@@ -1,8 +1,10 @@
 pub(crate) mod assert_eq;
 
 use crate::{
+    debugger::Debugger,
     dimension::DimensionRegistry,
     markup::Markup,
+    module_importer::ModuleImporter,
     pretty_print::PrettyPrint,
     quantity::QuantityError,
     span::Span,
@@ -12,7 +14,11 @@ use crate::{
 
 pub use crate::markup as m;
 
+use std::io::Write;
+use std::sync::{atomic::AtomicBool, Arc};
+
 use assert_eq::{AssertEq2Error, AssertEq3Error};
+use codespan_reporting::files::SimpleFiles;
 use compact_str::{CompactString, ToCompactString};
 use thiserror::Error;
 
@@ -65,6 +71,46 @@ pub enum RuntimeError {
 
     #[error("Could not write to file: {0:?}")]
     FileWrite(std::path::PathBuf),
+    #[error("Could not read from file: {0:?}")]
+    FileRead(std::path::PathBuf),
+    #[error("Invalid data in file {0:?}: {1}")]
+    InvalidDataFile(std::path::PathBuf, String),
+    #[error("Column '{1}' not found in file {0:?}")]
+    ColumnNotFound(std::path::PathBuf, String),
+    #[error("Could not parse '{3}' as a number in file {0:?} (line {1}, column {2})")]
+    InvalidNumberInDataFile(std::path::PathBuf, u64, usize, String),
+    #[error("Got {0} header(s) for {1} column(s)")]
+    CsvHeaderCountMismatch(usize, usize),
+    #[error("All columns must have the same length, got columns with {0} and {1} elements")]
+    CsvColumnLengthMismatch(usize, usize),
+
+    #[error("Exceeded the maximum number of instructions ({0})")]
+    InstructionLimitExceeded(u64),
+    #[error("Exceeded the maximum call depth ({0})")]
+    CallDepthLimitExceeded(usize),
+    #[error("Exceeded the maximum list length ({0})")]
+    ListLengthLimitExceeded(usize),
+    #[error("Exceeded the maximum number of allocated list elements ({0})")]
+    ListElementLimitExceeded(usize),
+    #[error("Exceeded the time limit ({0:?})")]
+    TimeLimitExceeded(std::time::Duration),
+    #[error("Function '{0}' is not available because side effects are disabled")]
+    SideEffectsDisabled(String),
+    #[error("Function '{0}' returned a value that is not of its declared return type '{1}'")]
+    InvalidReturnValue(String, String),
+    #[error("The body of a for loop is too large")]
+    LoopTooLarge,
+    #[error("Interrupted")]
+    Interrupted,
+    #[error("Environment variable '{0}' is not set")]
+    EnvironmentVariableNotSet(String),
+    #[error("Could not read a line of input")]
+    EndOfInput,
+    #[error("Exit code must be an integer between 0 and 255, got {0}")]
+    InvalidExitCode(f64),
+    /// Not an actual error: the program called `exit` to stop with the given exit code
+    #[error("Program exited with code {0}")]
+    Exit(i32),
 }
 
 #[derive(Debug, PartialEq)]
@@ -141,8 +187,57 @@ pub type Result<T> = std::result::Result<T, Box<RuntimeError>>;
 
 pub type PrintFunction = dyn FnMut(&Markup) + Send;
 
+pub type ResultFunction = dyn FnMut(&Value) + Send;
+
+/// Receives plots as standalone HTML documents.
+pub type PlotFunction = dyn FnMut(&str) + Send;
+
+/// Reads a line of input after showing the given prompt. Returns `None` at the
+/// end of the input.
+pub type ReadLineFunction = dyn FnMut(&str) -> Option<String> + Send;
+
+/// Limits on the resources that the VM may use for a single call to
+/// [`Context::interpret`](crate::Context::interpret). All limits are disabled by default.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ResourceLimits {
+    /// Maximum number of bytecode instructions that are executed
+    pub max_instructions: Option<u64>,
+    /// Maximum number of nested function calls
+    pub max_call_depth: Option<usize>,
+    /// Maximum number of elements in a single list
+    pub max_list_length: Option<usize>,
+    /// Maximum total number of list elements that are allocated. Every element
+    /// that is added to a list counts towards this budget, even if the list is
+    /// no longer in use.
+    pub max_list_elements: Option<usize>,
+    /// Maximum wall-clock time that is spent executing bytecode. Not supported on
+    /// `wasm32-unknown-unknown`, which has no system clock.
+    pub timeout: Option<std::time::Duration>,
+}
+
 pub struct InterpreterSettings {
     pub print_fn: Box<PrintFunction>,
+    pub debugger: Option<Debugger>,
+    pub limits: ResourceLimits,
+    /// Whether foreign functions with side effects outside of the interpreter
+    /// (like `show`, which opens a plot in the browser) may be called. This also
+    /// covers access to the environment, like `args`, `env`, `read_line` and `exit`,
+    /// and to files, like `read_csv` and `write_csv`.
+    pub allow_side_effects: bool,
+    /// When set to `true` (e.g. from another thread or a signal handler), the
+    /// running evaluation stops with [`RuntimeError::Interrupted`]. The flag is
+    /// not reset by the interpreter.
+    pub interrupt: Option<Arc<AtomicBool>>,
+    /// Called with the value of every top-level expression statement, in order.
+    pub result_fn: Option<Box<ResultFunction>>,
+    /// If set, plots created with `show` are passed to this function instead of
+    /// being opened in the browser.
+    pub plot_fn: Option<Box<PlotFunction>>,
+    /// Command-line arguments of the program, returned by `args()`
+    pub arguments: Vec<CompactString>,
+    /// Used by `read_line` to read input from the user. Reads from the standard
+    /// input by default.
+    pub read_line_fn: Box<ReadLineFunction>,
 }
 
 impl Default for InterpreterSettings {
@@ -151,6 +246,23 @@ impl Default for InterpreterSettings {
             print_fn: Box::new(move |s: &Markup| {
                 print!("{s}");
             }),
+            debugger: None,
+            limits: ResourceLimits::default(),
+            allow_side_effects: true,
+            interrupt: None,
+            result_fn: None,
+            plot_fn: None,
+            arguments: vec![],
+            read_line_fn: Box::new(|prompt: &str| {
+                print!("{prompt}");
+                std::io::stdout().flush().ok()?;
+
+                let mut line = String::new();
+                match std::io::stdin().read_line(&mut line) {
+                    Ok(0) | Err(_) => None,
+                    Ok(_) => Some(line),
+                }
+            }),
         }
     }
 }
@@ -163,6 +275,8 @@ pub trait Interpreter {
         settings: &mut InterpreterSettings,
         statements: &[Statement],
         dimension_registry: &DimensionRegistry,
+        files: &SimpleFiles<String, String>,
+        importer: &dyn ModuleImporter,
     ) -> Result<InterpreterResult>;
     fn get_unit_registry(&self) -> &UnitRegistry;
 }
@@ -171,6 +285,7 @@ pub trait Interpreter {
 mod tests {
     use compact_str::CompactString;
 
+    use crate::module_importer::NullImporter;
     use crate::prefix_parser::AcceptsPrefix;
     use crate::quantity::Quantity;
     use crate::unit::{CanonicalName, Unit};
@@ -220,6 +335,8 @@ mod tests {
             &mut InterpreterSettings::default(),
             &statements_typechecked,
             typechecker.registry(),
+            &SimpleFiles::new(),
+            &NullImporter::default(),
         )
     }
 
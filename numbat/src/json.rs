@@ -0,0 +1,280 @@
+//! Machine-readable representations of values and diagnostics, for tools that
+//! consume the results of Numbat programs. All types implement [`Serialize`] and
+//! are meant to be serialized to JSON.
+
+use codespan_reporting::diagnostic::{LabelStyle, Severity};
+use codespan_reporting::files::Files;
+use compact_str::{CompactString, ToCompactString};
+use indexmap::IndexMap;
+use serde::Serialize;
+
+use crate::diagnostic::ErrorDiagnostic;
+use crate::interpreter::Interpreter;
+use crate::pretty_print::PrettyPrint;
+use crate::quantity::Quantity;
+use crate::typed_ast::Statement;
+use crate::value::Value;
+use crate::Context;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum JsonValue {
+    Quantity {
+        value: f64,
+        unit: CompactString,
+        /// The value after conversion to base units
+        base_value: f64,
+        base_unit: CompactString,
+        /// Name of the physical dimension, e.g. `Velocity` or `Length / Time`
+        dimension: CompactString,
+    },
+    Boolean {
+        value: bool,
+    },
+    String {
+        value: CompactString,
+    },
+    /// A date and time in RFC 9557 format, e.g. `2024-03-01T12:00:00+01:00[Europe/Berlin]`
+    #[serde(rename = "datetime")]
+    DateTime {
+        value: CompactString,
+    },
+    Function {
+        name: CompactString,
+    },
+    FormatSpecifiers {
+        value: Option<CompactString>,
+    },
+    Struct {
+        name: CompactString,
+        fields: IndexMap<CompactString, JsonValue>,
+    },
+    List {
+        elements: Vec<JsonValue>,
+    },
+}
+
+/// The result of an expression statement.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct JsonResult {
+    /// The pretty-printed statement
+    pub statement: Option<String>,
+    #[serde(rename = "type")]
+    pub type_: Option<String>,
+    pub value: JsonValue,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct JsonLabel {
+    pub message: String,
+    /// Whether this label points to the primary cause of the diagnostic
+    pub primary: bool,
+    /// Name of the file (or other code source) that the label refers to
+    pub source: String,
+    /// One-based line and column numbers of the start and end of the labeled span
+    pub start: (usize, usize),
+    pub end: (usize, usize),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct JsonDiagnostic {
+    pub severity: &'static str,
+    pub message: String,
+    pub labels: Vec<JsonLabel>,
+    pub notes: Vec<String>,
+}
+
+impl Context {
+    /// A machine-readable representation of the given value.
+    pub fn json_value(&self, value: &Value) -> JsonValue {
+        match value {
+            Value::Quantity(q) => {
+                let base = q.to_base_unit_representation();
+                JsonValue::Quantity {
+                    value: q.unsafe_value().to_f64(),
+                    unit: q.unit().to_compact_string(),
+                    base_value: base.unsafe_value().to_f64(),
+                    base_unit: base.unit().to_compact_string(),
+                    dimension: self.dimension_of(q),
+                }
+            }
+            Value::Boolean(value) => JsonValue::Boolean { value: *value },
+            Value::String(value) => JsonValue::String {
+                value: value.clone(),
+            },
+            Value::DateTime(dt) => JsonValue::DateTime {
+                value: dt.to_compact_string(),
+            },
+            Value::FunctionReference(reference) => JsonValue::Function {
+                name: reference.to_compact_string(),
+            },
+            Value::FormatSpecifiers(value) => JsonValue::FormatSpecifiers {
+                value: value.clone(),
+            },
+            Value::StructInstance(info, values) => JsonValue::Struct {
+                name: info.name.clone(),
+                fields: info
+                    .fields
+                    .keys()
+                    .zip(values)
+                    .map(|(name, value)| (name.clone(), self.json_value(value)))
+                    .collect(),
+            },
+            Value::List(elements) => JsonValue::List {
+                elements: elements.iter().map(|e| self.json_value(e)).collect(),
+            },
+        }
+    }
+
+    /// A machine-readable representation of the value of an expression statement,
+    /// together with the statement and its type, if available.
+    pub fn json_result(&self, statement: Option<&Statement>, value: &Value) -> JsonResult {
+        let expression = statement.and_then(Statement::as_expression);
+        JsonResult {
+            statement: statement.map(|s| s.pretty_print().to_string()),
+            type_: expression.map(|e| {
+                e.get_type_scheme()
+                    .to_readable_type(self.dimension_registry(), false)
+                    .to_string()
+            }),
+            value: self.json_value(value),
+        }
+    }
+
+    /// The name of the physical dimension of a quantity. If the dimension can not be
+    /// determined, the unit of the quantity is used instead.
+    fn dimension_of(&self, quantity: &Quantity) -> CompactString {
+        match self
+            .interpreter
+            .get_unit_registry()
+            .dimension_of(quantity.unit())
+        {
+            Ok(dtype) => dtype
+                .to_readable_type(self.dimension_registry())
+                .to_compact_string(),
+            Err(_) => quantity.unit().to_compact_string(),
+        }
+    }
+
+    /// Machine-readable representations of the diagnostics for the given error.
+    pub fn json_diagnostics(&self, error: &impl ErrorDiagnostic) -> Vec<JsonDiagnostic> {
+        let files = &self.resolver.files;
+        let position = |file_id: usize, byte_index: usize| {
+            files
+                .location(file_id, byte_index)
+                .map(|l| (l.line_number, l.column_number))
+                .unwrap_or((0, 0))
+        };
+
+        error
+            .diagnostics()
+            .into_iter()
+            .map(|diagnostic| JsonDiagnostic {
+                severity: match diagnostic.severity {
+                    Severity::Bug | Severity::Error => "error",
+                    Severity::Warning => "warning",
+                    Severity::Note | Severity::Help => "note",
+                },
+                message: diagnostic.message,
+                labels: diagnostic
+                    .labels
+                    .into_iter()
+                    .map(|label| JsonLabel {
+                        primary: label.style == LabelStyle::Primary,
+                        source: files
+                            .name(label.file_id)
+                            .map(|n| n.to_string())
+                            .unwrap_or_default(),
+                        start: position(label.file_id, label.range.start),
+                        end: position(label.file_id, label.range.end),
+                        message: label.message,
+                    })
+                    .collect(),
+                notes: diagnostic.notes,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::module_importer::BuiltinModuleImporter;
+    use crate::resolver::CodeSource;
+    use crate::{InterpreterResult, NumbatError};
+
+    fn evaluate(code: &str) -> serde_json::Value {
+        let mut ctx = Context::new(BuiltinModuleImporter::default());
+        let _ = ctx.interpret("use prelude", CodeSource::Internal).unwrap();
+        let InterpreterResult::Value(value) = ctx.interpret(code, CodeSource::Text).unwrap().1
+        else {
+            panic!("Expected a value");
+        };
+        serde_json::to_value(ctx.json_value(&value)).unwrap()
+    }
+
+    #[test]
+    fn values() {
+        assert_eq!(
+            evaluate("36 km/h"),
+            serde_json::json!({
+                "type": "quantity",
+                "value": 36.0,
+                "unit": "km/h",
+                "base_value": 10.0,
+                "base_unit": "m/s",
+                "dimension": "Velocity",
+            })
+        );
+        assert_eq!(
+            evaluate("[true, false]"),
+            serde_json::json!({
+                "type": "list",
+                "elements": [
+                    { "type": "boolean", "value": true },
+                    { "type": "boolean", "value": false },
+                ],
+            })
+        );
+        assert_eq!(
+            evaluate("struct P { name: String, mass: Mass } \n P { name: \"x\", mass: 2 g }")
+                ["fields"]["mass"]["dimension"],
+            "Mass"
+        );
+        assert_eq!(
+            evaluate("datetime(\"2024-03-01 12:00:00 UTC\")"),
+            serde_json::json!({ "type": "datetime", "value": "2024-03-01T12:00:00+00:00[UTC]" })
+        );
+    }
+
+    #[test]
+    fn unknown_dimension() {
+        // Units that are not in the registry are not dropped from the dimension
+        let ctx = Context::new(BuiltinModuleImporter::default());
+        let value = Value::Quantity(Quantity::new_f64(2.0, crate::unit::Unit::meter()));
+        assert_eq!(
+            serde_json::to_value(ctx.json_value(&value)).unwrap()["dimension"],
+            "m"
+        );
+    }
+
+    #[test]
+    fn diagnostics() {
+        let mut ctx = Context::new(BuiltinModuleImporter::default());
+        let _ = ctx.interpret("use prelude", CodeSource::Internal).unwrap();
+        let error = ctx
+            .interpret("1 + 1\n2 m + 3 s", CodeSource::Text)
+            .unwrap_err();
+        let NumbatError::TypeCheckError(error) = *error else {
+            panic!("Expected a type check error");
+        };
+
+        let diagnostics = ctx.json_diagnostics(&error);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].severity, "error");
+        assert!(diagnostics[0]
+            .labels
+            .iter()
+            .any(|l| l.source == "<input:1>" && l.start == (2, 1) && l.end == (2, 4)));
+    }
+}
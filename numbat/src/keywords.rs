@@ -11,6 +11,7 @@ pub const KEYWORDS: &[&str] = &[
     "unit ",
     "use ",
     "struct ",
+    "for ",
     // 'inline' keywords
     "long",
     "short",
@@ -40,4 +41,5 @@ pub const KEYWORDS: &[&str] = &[
     "aliases",
     "name",
     "url",
+    "deprecated",
 ];
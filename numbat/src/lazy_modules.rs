@@ -0,0 +1,316 @@
+//! Modules that are loaded on demand.
+//!
+//! Instead of importing a module (and all of its dependencies) right away, we only
+//! parse it and record which identifiers it defines. The module is then loaded the
+//! first time one of these identifiers is used (or redefined).
+
+use std::collections::HashSet;
+
+use compact_str::{CompactString, ToCompactString};
+use serde::{Deserialize, Serialize};
+
+use crate::ast::Statement;
+use crate::decorator;
+use crate::module_importer::ModuleImporter;
+use crate::parser::parse;
+use crate::prefix_parser::{PrefixParser, PrefixParserResult};
+use crate::prefix_transformer::Transformer;
+use crate::resolver::{ModulePath, ResolverError};
+use crate::span::Span;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct LazyModule {
+    pub path: ModulePath,
+
+    /// Modules that are imported by this module, in order.
+    pub imports: Vec<ModulePath>,
+
+    /// Names of all variables, functions, dimensions, structs and units (including
+    /// aliases) that are defined in this module.
+    names: HashSet<CompactString>,
+
+    /// Names of function parameters, type parameters and local variables. These may
+    /// not clash with units or dimensions that are defined *before* this module.
+    local_names: HashSet<CompactString>,
+
+    /// Used to recognize prefixed versions of the units in this module.
+    prefix_parser: PrefixParser,
+}
+
+impl LazyModule {
+    fn defines(&self, name: &str) -> bool {
+        self.names.contains(name)
+            || matches!(
+                self.prefix_parser.parse(name),
+                PrefixParserResult::UnitIdentifier(..)
+            )
+    }
+}
+
+/// Modules that have not been loaded yet, in the order in which they would have
+/// been loaded by a `use` statement (dependencies first).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub(crate) struct LazyModules {
+    modules: Vec<LazyModule>,
+}
+
+/// The names of the top-level definitions in the given statement. Unit aliases
+/// are included, local variables of functions are not.
+pub(crate) fn defined_names<'a>(statement: &'a Statement) -> Vec<&'a str> {
+    match statement {
+        Statement::DefineVariable(define_variable) => {
+            decorator::name_and_aliases(define_variable.identifier, &define_variable.decorators)
+                .map(|(name, _)| name)
+                .collect()
+        }
+        Statement::DefineBaseUnit(_, name, _, decorators)
+        | Statement::DefineDerivedUnit {
+            identifier: name,
+            decorators,
+            ..
+        } => decorator::name_and_aliases(name, decorators)
+            .map(|(name, _)| name)
+            .collect(),
+        Statement::DefineFunction { function_name, .. } => vec![function_name],
+        Statement::DefineDimension(_, name, _) => vec![name],
+        Statement::DefineStruct { struct_name, .. } => vec![struct_name],
+        Statement::Expression(_)
+        | Statement::ProcedureCall(..)
+        | Statement::ModuleImport(..)
+        | Statement::ForLoop { .. } => vec![],
+    }
+}
+
+/// The names of the parameters, type parameters and local variables of a function,
+/// or of the loop variable and the definitions in the body of a for loop.
+pub(crate) fn local_names<'a>(statement: &'a Statement) -> Vec<&'a str> {
+    match statement {
+        Statement::DefineFunction {
+            type_parameters,
+            parameters,
+            local_variables,
+            ..
+        } => type_parameters
+            .iter()
+            .map(|(_, name, _)| *name)
+            .chain(parameters.iter().map(|(_, name, _)| *name))
+            .chain(local_variables.iter().map(|v| v.identifier))
+            .collect(),
+        Statement::ForLoop {
+            loop_variable,
+            body,
+            ..
+        } => std::iter::once(*loop_variable)
+            .chain(body.iter().flat_map(|statement| {
+                defined_names(statement)
+                    .into_iter()
+                    .chain(local_names(statement))
+            }))
+            .collect(),
+        _ => vec![],
+    }
+}
+
+impl LazyModules {
+    pub fn is_empty(&self) -> bool {
+        self.modules.is_empty()
+    }
+
+    /// Index the given module and all modules that it imports (transitively), except
+    /// for modules that have already been imported or indexed.
+    pub fn add(
+        &mut self,
+        importer: &dyn ModuleImporter,
+        module_path: &ModulePath,
+        span: Span,
+        imported_modules: &[ModulePath],
+    ) -> Result<(), ResolverError> {
+        self.add_inner(importer, module_path, span, imported_modules, &mut vec![])
+    }
+
+    fn add_inner(
+        &mut self,
+        importer: &dyn ModuleImporter,
+        module_path: &ModulePath,
+        span: Span,
+        imported_modules: &[ModulePath],
+        visited: &mut Vec<ModulePath>,
+    ) -> Result<(), ResolverError> {
+        if imported_modules.contains(module_path) || visited.contains(module_path) {
+            return Ok(());
+        }
+        visited.push(module_path.clone());
+
+        let Some((code, _)) = importer.import(module_path) else {
+            return Err(ResolverError::UnknownModule(span, module_path.clone()));
+        };
+
+        let statements =
+            parse(&code, usize::MAX).map_err(|(_, errors)| ResolverError::ParseErrors(errors))?;
+
+        let mut names = HashSet::new();
+        let mut locals = HashSet::new();
+        let mut imports = vec![];
+        for statement in &statements {
+            names.extend(
+                defined_names(statement)
+                    .into_iter()
+                    .map(CompactString::from),
+            );
+            locals.extend(local_names(statement).into_iter().map(CompactString::from));
+
+            if let Statement::ModuleImport(span, path) = statement {
+                let path = ModulePath(path.0.iter().map(|p| p.to_compact_string()).collect());
+                self.add_inner(importer, &path, *span, imported_modules, visited)?;
+                imports.push(path);
+            }
+        }
+
+        // Run the prefix transformer on this module alone to register all units
+        // with their prefixes.
+        let mut transformer = Transformer::new();
+        let _ = transformer.transform(statements);
+
+        self.modules.push(LazyModule {
+            path: module_path.clone(),
+            imports,
+            names,
+            local_names: locals,
+            prefix_parser: transformer.prefix_parser,
+        });
+
+        Ok(())
+    }
+
+    /// The first module that defines the given identifier (possibly with a prefix).
+    pub fn module_defining(&self, name: &str) -> Option<&ModulePath> {
+        self.modules
+            .iter()
+            .find(|m| m.defines(name))
+            .map(|m| &m.path)
+    }
+
+    /// Remove a module from the index. Also returns the modules that come before it
+    /// and which would clash with its definitions if they were loaded afterwards.
+    pub fn take(&mut self, module_path: &ModulePath) -> Option<(LazyModule, Vec<ModulePath>)> {
+        let position = self.modules.iter().position(|m| &m.path == module_path)?;
+        let module = self.modules.remove(position);
+
+        let earlier_modules = self.modules[..position]
+            .iter()
+            .filter(|m| m.local_names.iter().any(|name| module.defines(name)))
+            .map(|m| m.path.clone())
+            .collect();
+
+        Some((module, earlier_modules))
+    }
+
+    /// The next module that would be loaded by a `use` statement.
+    pub fn first(&self) -> Option<&ModulePath> {
+        self.modules.first().map(|m| &m.path)
+    }
+
+    /// All names that are available from modules that have not been loaded yet.
+    pub fn names(&self) -> impl Iterator<Item = &CompactString> {
+        self.modules.iter().flat_map(|m| m.names.iter())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::module_importer::BuiltinModuleImporter;
+    use crate::resolver::{CodeSource, ModulePath};
+    use crate::{Context, InterpreterResult, NumbatError};
+
+    fn lazy_prelude() -> Context {
+        let mut ctx = Context::new(BuiltinModuleImporter::default());
+        ctx.load_module_on_demand("prelude").unwrap();
+        ctx
+    }
+
+    fn evaluate(ctx: &mut Context, code: &str) -> String {
+        match ctx.interpret(code, CodeSource::Text).unwrap().1 {
+            InterpreterResult::Value(value) => value.to_string(),
+            InterpreterResult::Continue => String::new(),
+        }
+    }
+
+    fn is_imported(ctx: &Context, module: &str) -> bool {
+        let module = ModulePath(module.split("::").map(|p| p.into()).collect());
+        ctx.resolver().imported_modules.contains(&module)
+    }
+
+    #[test]
+    fn loads_modules_on_first_use() {
+        let mut ctx = lazy_prelude();
+        assert!(ctx.resolver().imported_modules.is_empty());
+
+        assert_eq!(evaluate(&mut ctx, "2 km + 3 m -> m"), "2003 m");
+        assert!(is_imported(&ctx, "units::si"));
+        assert!(!is_imported(&ctx, "units::imperial"));
+
+        assert_eq!(evaluate(&mut ctx, "let x: Length = 1 mile"), "");
+        assert!(is_imported(&ctx, "units::imperial"));
+
+        assert_eq!(evaluate(&mut ctx, "round_in(ft, x)"), "5280 ft");
+        assert!(!is_imported(&ctx, "chemistry::elements"));
+
+        let mut eager = Context::new(BuiltinModuleImporter::default());
+        let _ = eager
+            .interpret("use prelude", CodeSource::Internal)
+            .unwrap();
+        for code in [
+            "sqrt(2 m²) + 3 cm",
+            "element(\"H\").symbol",
+            "kibibyte -> bits",
+        ] {
+            assert_eq!(evaluate(&mut ctx, code), evaluate(&mut eager, code));
+        }
+    }
+
+    #[test]
+    fn redefinitions_are_detected() {
+        let mut ctx = lazy_prelude();
+        assert!(matches!(
+            *ctx.interpret("let m = 2", CodeSource::Text).unwrap_err(),
+            NumbatError::NameResolutionError(_)
+        ));
+        assert!(matches!(
+            *ctx.interpret("fn ms() = 1", CodeSource::Text).unwrap_err(),
+            NumbatError::NameResolutionError(_)
+        ));
+        assert_eq!(evaluate(&mut ctx, "let meters_per_parsec = 2"), "");
+    }
+
+    #[test]
+    fn info_and_list_load_modules() {
+        let plain = |markup: crate::markup::Markup| markup.to_string();
+
+        let mut ctx = lazy_prelude();
+        assert!(plain(ctx.print_info_for_keyword("foot")).contains("Unit: Foot"));
+        assert!(plain(ctx.print_info_for_keyword("sqrt")).contains("Square root"));
+        assert!(!is_imported(&ctx, "chemistry::elements"));
+
+        let units = plain(ctx.print_units());
+        assert!(units.contains("parsec"));
+        assert!(is_imported(&ctx, "chemistry::elements"));
+    }
+
+    #[test]
+    fn unknown_identifiers() {
+        let mut ctx = lazy_prelude();
+        assert!(matches!(
+            *ctx.interpret("2 foo", CodeSource::Text).unwrap_err(),
+            NumbatError::TypeCheckError(_)
+        ));
+    }
+
+    #[test]
+    fn unknown_module() {
+        let mut ctx = Context::new(BuiltinModuleImporter::default());
+        assert!(matches!(
+            *ctx.load_module_on_demand("does::not::exist").unwrap_err(),
+            NumbatError::ResolverError(_)
+        ));
+    }
+}
@@ -7,20 +7,25 @@ mod column_formatter;
 pub mod command;
 mod currency;
 mod datetime;
+pub mod debugger;
 mod decorator;
 pub mod diagnostic;
 mod dimension;
 mod ffi;
+pub mod formatter;
 mod gamma;
 pub mod help;
 #[cfg(feature = "html-formatter")]
 pub mod html_formatter;
 mod interpreter;
+pub mod json;
 pub mod keywords;
+mod lazy_modules;
 pub mod list;
 pub mod markup;
 mod math;
 pub mod module_importer;
+pub mod module_info;
 mod name_resolution;
 mod number;
 mod parser;
@@ -30,10 +35,12 @@ mod prefix_parser;
 mod prefix_transformer;
 pub mod pretty_print;
 mod product;
+pub mod profiler;
 mod quantity;
 mod registry;
 pub mod resolver;
 pub mod session_history;
+pub mod snapshot;
 mod span;
 mod suggestion;
 mod tokenizer;
@@ -46,8 +53,11 @@ mod unit;
 mod unit_registry;
 pub mod value;
 mod vm;
+pub mod warning;
 
 use std::borrow::Cow;
+use std::collections::HashSet;
+use std::sync::Arc;
 
 use bytecode_interpreter::BytecodeInterpreter;
 use column_formatter::ColumnFormatter;
@@ -57,32 +67,45 @@ use compact_str::ToCompactString;
 use currency::ExchangeRatesCache;
 use diagnostic::ErrorDiagnostic;
 use dimension::DimensionRegistry;
+use ffi::RegisteredFunction;
 use interpreter::Interpreter;
+use itertools::Itertools;
 use keywords::KEYWORDS;
+use lazy_modules::LazyModules;
 use markup as m;
 use markup::FormatType;
 use markup::Markup;
 use module_importer::{ModuleImporter, NullImporter};
+use module_info::ModuleInfo;
+use parser::ParseErrorKind;
 use prefix_transformer::Transformer;
 
+use registry::RegistryError;
 use resolver::CodeSource;
+use resolver::ModulePath;
 use resolver::Resolver;
 use resolver::ResolverError;
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 use typechecker::{TypeCheckError, TypeChecker};
 
 pub use diagnostic::Diagnostic;
 pub use interpreter::InterpreterResult;
 pub use interpreter::InterpreterSettings;
+pub use interpreter::ResourceLimits;
 pub use interpreter::RuntimeError;
 pub use name_resolution::NameResolutionError;
 pub use parser::ParseError;
+pub use prefix_parser::UnitReading;
+pub use quantity::Quantity;
 pub use registry::BaseRepresentation;
 pub use registry::BaseRepresentationFactor;
+pub use span::Span;
 pub use typed_ast::Statement;
 pub use typed_ast::Type;
 use unit::BaseUnitAndFactor;
 use unit_registry::UnitMetadata;
+use warning::{Warning, WarningCategory};
 
 use crate::prefix_parser::PrefixParserResult;
 use crate::unicode_input::UNICODE_INPUT;
@@ -103,14 +126,21 @@ pub enum NumbatError {
 
 type Result<T> = std::result::Result<T, Box<NumbatError>>;
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Context {
     prefix_transformer: Transformer,
     typechecker: TypeChecker,
     interpreter: BytecodeInterpreter,
     resolver: Resolver,
+    lazy_modules: LazyModules,
     load_currency_module_on_demand: bool,
     terminal_width: Option<usize>,
+    #[serde(skip)]
+    plain_diagnostics: bool,
+    #[serde(skip)]
+    warnings: Vec<Warning>,
+    #[serde(skip)]
+    suppressed_warnings: HashSet<WarningCategory>,
 }
 
 impl Context {
@@ -120,8 +150,12 @@ impl Context {
             typechecker: TypeChecker::default(),
             interpreter: BytecodeInterpreter::new(),
             resolver: Resolver::new(module_importer),
+            lazy_modules: LazyModules::default(),
             load_currency_module_on_demand: false,
             terminal_width: None,
+            plain_diagnostics: false,
+            warnings: vec![],
+            suppressed_warnings: HashSet::new(),
         }
     }
 
@@ -129,10 +163,291 @@ impl Context {
         Self::new(NullImporter::default())
     }
 
+    /// Serialize the complete state of this context (without the module importer),
+    /// e.g. after loading the prelude.
+    pub fn snapshot(&self) -> Vec<u8> {
+        snapshot::encode(self)
+    }
+
+    /// Restore a context from a snapshot created by [`Context::snapshot`]. This fails
+    /// if the snapshot was created by a different version of Numbat, or if the source
+    /// code of one of the imported modules has changed in the meantime.
+    pub fn from_snapshot(
+        snapshot: &[u8],
+        module_importer: impl ModuleImporter + 'static,
+    ) -> std::result::Result<Self, snapshot::SnapshotError> {
+        let mut context: Context = snapshot::decode(snapshot)?;
+        context.resolver.set_importer(module_importer);
+
+        if let Some(module_path) = context.resolver.outdated_module() {
+            return Err(snapshot::SnapshotError::OutdatedModule(module_path.clone()));
+        }
+
+        Ok(context)
+    }
+
     pub fn set_debug(&mut self, activate: bool) {
         self.interpreter.set_debug(activate);
     }
 
+    /// Enable or disable the profiler. Enabling it discards any previously
+    /// collected data.
+    pub fn set_profiling(&mut self, activate: bool) {
+        self.interpreter.set_profiling(activate);
+    }
+
+    /// The data collected by the profiler, if profiling is enabled.
+    pub fn profiler(&self) -> Option<&profiler::Profiler> {
+        self.interpreter.profiler()
+    }
+
+    /// Make a native Rust function available to Numbat code. The `signature` is the
+    /// part of a function declaration that follows the name, with type annotations
+    /// for all parameters and the return type, e.g. `(item: String, amount: Mass) -> Money`.
+    /// Calls are type checked against this signature like calls to any other function.
+    ///
+    /// The function receives the arguments in order and needs to return a value of the
+    /// declared type (including the physical dimension of quantities), otherwise the call
+    /// fails with [`RuntimeError::InvalidReturnValue`].
+    /// Functions with `side_effects` (like reading from a database or a sensor) can not
+    /// be called if [`InterpreterSettings::allow_side_effects`] is disabled. Contexts
+    /// with registered functions can not be restored from a [snapshot](Self::snapshot).
+    pub fn register_function<F>(
+        &mut self,
+        name: &str,
+        signature: &str,
+        side_effects: bool,
+        function: F,
+    ) -> Result<()>
+    where
+        F: Fn(Vec<value::Value>) -> std::result::Result<value::Value, Box<RuntimeError>>
+            + Send
+            + Sync
+            + 'static,
+    {
+        let code = format!("fn {name}{signature}");
+        let statements = self
+            .resolver
+            .resolve(&code, CodeSource::Internal)
+            .map_err(NumbatError::ResolverError)?;
+
+        // Make sure that we only declare this single function
+        let unexpected_span = match statements.as_slice() {
+            [ast::Statement::DefineFunction {
+                function_name,
+                body: None,
+                ..
+            }] if *function_name == name => None,
+            [ast::Statement::DefineFunction {
+                body: Some(body), ..
+            }] => Some(body.full_span()),
+            _ => {
+                let code_source_id = self.resolver.next_code_source_id() - 1;
+                Some(Span {
+                    start: ((code.len() - signature.len()) as u32).into(),
+                    end: (code.len() as u32).into(),
+                    code_source_id,
+                })
+            }
+        };
+        if let Some(span) = unexpected_span {
+            let trailing = &code[span.start.0 as usize..span.end.0 as usize];
+            return Err(Box::new(NumbatError::ResolverError(
+                ResolverError::ParseErrors(vec![ParseError {
+                    kind: ParseErrorKind::TrailingCharacters(trailing.into()),
+                    span,
+                }]),
+            )));
+        }
+
+        let previous = self.interpreter.register_native_function(
+            name,
+            RegisteredFunction {
+                function: Arc::new(function),
+                side_effects,
+            },
+        );
+        self.typechecker.register_native_function(name);
+
+        let mut no_print_settings = InterpreterSettings {
+            print_fn: Box::new(move |_: &m::Markup| {}),
+            ..Default::default()
+        };
+        let result = self.interpret_resolved_statements(
+            &mut no_print_settings,
+            statements,
+            &code,
+            CodeSource::Internal,
+        );
+
+        if result.is_err() {
+            if let Some(previous) = previous {
+                self.interpreter.register_native_function(name, previous);
+            } else {
+                self.interpreter.unregister_native_function(name);
+                self.typechecker.unregister_native_function(name);
+            }
+        }
+
+        result.map(|_| ())
+    }
+
+    /// Make the given module (and all modules that it imports) available without
+    /// loading it right away. Each module is only loaded (type checked and compiled)
+    /// the first time one of its definitions is used. This is a faster alternative
+    /// to `use <module>` for large modules like the prelude.
+    pub fn load_module_on_demand(&mut self, module_path: &str) -> Result<()> {
+        // Register the corresponding 'use' statement as a code source, such that errors
+        // can point to it.
+        let code = format!("use {module_path}");
+        let code_source_id = self.resolver.add_code_source(CodeSource::Internal, &code);
+        let span = Span {
+            start: 4.into(),
+            end: (code.len() as u32).into(),
+            code_source_id,
+        };
+
+        let module_path = ModulePath(module_path.split("::").map(|p| p.into()).collect());
+        self.lazy_modules
+            .add(
+                self.resolver.get_importer(),
+                &module_path,
+                span,
+                &self.resolver.imported_modules,
+            )
+            .map_err(|e| Box::new(NumbatError::ResolverError(e)))
+    }
+
+    /// Load the modules which provide the unknown identifiers in the given error, if any.
+    /// Returns whether a module has been loaded.
+    fn load_module_for_error(&mut self, error: &NumbatError) -> Result<bool> {
+        let NumbatError::TypeCheckError(error) = error else {
+            return Ok(false);
+        };
+
+        let mut loaded = false;
+        for error in error.errors() {
+            let identifier = match error {
+                TypeCheckError::UnknownIdentifier(_, identifier, _)
+                | TypeCheckError::UnknownStruct(_, identifier)
+                | TypeCheckError::RegistryError(RegistryError::UnknownEntry(identifier, _)) => {
+                    identifier
+                }
+                _ => continue,
+            };
+
+            loaded |= self.load_module_defining(identifier)?;
+        }
+
+        Ok(loaded)
+    }
+
+    /// Load the module which defines the given identifier, if it has not been
+    /// loaded yet. Returns whether a module has been loaded.
+    fn load_module_defining(&mut self, identifier: &str) -> Result<bool> {
+        let Some(module_path) = self.lazy_modules.module_defining(identifier).cloned() else {
+            return Ok(false);
+        };
+
+        self.load_lazy_module(&module_path)?;
+
+        Ok(true)
+    }
+
+    /// Load a module that has been registered via [`Context::load_module_on_demand`].
+    /// Modules are loaded one by one, in the same order as for a 'use' statement.
+    fn load_lazy_module(&mut self, module_path: &ModulePath) -> Result<()> {
+        // Removing the module from the index first makes sure that we never try to
+        // load it twice, even if loading fails.
+        let Some((module, earlier_modules)) = self.lazy_modules.take(module_path) else {
+            return Ok(());
+        };
+
+        for dependency in module.imports.iter().chain(&earlier_modules) {
+            self.load_lazy_module(dependency)?;
+        }
+
+        let code = format!("use {module_path}");
+        let statements = self
+            .resolver
+            .resolve(&code, CodeSource::Internal)
+            .map_err(NumbatError::ResolverError)?;
+
+        let mut no_print_settings = InterpreterSettings {
+            print_fn: Box::new(move |_: &m::Markup| {}),
+            ..Default::default()
+        };
+        let _ = self.interpret_resolved_statements(
+            &mut no_print_settings,
+            statements,
+            &code,
+            CodeSource::Internal,
+        )?;
+
+        Ok(())
+    }
+
+    /// Load all modules that have been registered via [`Context::load_module_on_demand`],
+    /// e.g. in order to list all available definitions. Errors in modules are reported
+    /// once their definitions are used in code.
+    fn load_all_lazy_modules(&mut self) {
+        while let Some(module_path) = self.lazy_modules.first().cloned() {
+            let _ = self.load_lazy_module(&module_path);
+        }
+    }
+
+    /// If modules are loaded on demand, we also load the modules that are imported
+    /// in the given code one by one (before resolving the code), such that they can
+    /// make use of other modules that have not been loaded yet.
+    fn load_imported_modules(&mut self, code: &str) -> Result<()> {
+        if self.lazy_modules.is_empty() {
+            return Ok(());
+        }
+
+        let Ok(statements) = parser::parse(code, self.resolver.next_code_source_id()) else {
+            return Ok(());
+        };
+
+        for statement in statements {
+            if let ast::Statement::ModuleImport(span, path) = statement {
+                let module_path =
+                    ModulePath(path.0.iter().map(|p| p.to_compact_string()).collect());
+
+                // Unknown modules are reported when resolving the code
+                let added = self.lazy_modules.add(
+                    self.resolver.get_importer(),
+                    &module_path,
+                    span,
+                    &self.resolver.imported_modules,
+                );
+                if added.is_ok() {
+                    self.load_lazy_module(&module_path)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Load all modules which define names that are redefined (or shadowed) in the
+    /// given statements, such that we get the same errors as if these modules had
+    /// been loaded eagerly.
+    fn load_modules_for_definitions(&mut self, statements: &[ast::Statement]) -> Result<()> {
+        if self.lazy_modules.is_empty() {
+            return Ok(());
+        }
+
+        for statement in statements {
+            let defined_names = lazy_modules::defined_names(statement);
+            let local_names = lazy_modules::local_names(statement);
+            for name in defined_names.into_iter().chain(local_names) {
+                self.load_module_defining(name)?;
+            }
+        }
+
+        Ok(())
+    }
+
     pub fn load_currency_module_on_demand(&mut self, yes: bool) {
         self.load_currency_module_on_demand = yes;
     }
@@ -166,6 +481,7 @@ impl Context {
             .cloned()
     }
 
+    #[allow(clippy::type_complexity)]
     pub fn functions(
         &self,
     ) -> impl Iterator<
@@ -200,6 +516,21 @@ impl Context {
             })
     }
 
+    /// Names of the functions that are decorated with `@test`, in the order in
+    /// which they have been defined.
+    pub fn test_functions(&self) -> impl Iterator<Item = CompactString> + '_ {
+        self.prefix_transformer
+            .function_names
+            .iter()
+            .unique()
+            .filter(|name| {
+                self.typechecker
+                    .lookup_function(name)
+                    .is_some_and(|(_, meta)| meta.is_test)
+            })
+            .cloned()
+    }
+
     pub fn unit_names(&self) -> &[Vec<CompactString>] {
         &self.prefix_transformer.unit_names
     }
@@ -208,7 +539,7 @@ impl Context {
         &self.prefix_transformer.dimension_names
     }
 
-    pub fn print_environment(&self) -> Markup {
+    pub fn print_environment(&mut self) -> Markup {
         let mut output = m::empty();
 
         output += m::emphasized("List of functions:") + m::nl();
@@ -233,19 +564,23 @@ impl Context {
         formatter.format(entries, format_type)
     }
 
-    pub fn print_functions(&self) -> Markup {
+    pub fn print_functions(&mut self) -> Markup {
+        self.load_all_lazy_modules();
         self.print_sorted(self.function_names().collect(), FormatType::Identifier)
     }
 
-    pub fn print_dimensions(&self) -> Markup {
+    pub fn print_dimensions(&mut self) -> Markup {
+        self.load_all_lazy_modules();
         self.print_sorted(self.dimension_names().into(), FormatType::TypeIdentifier)
     }
 
-    pub fn print_variables(&self) -> Markup {
+    pub fn print_variables(&mut self) -> Markup {
+        self.load_all_lazy_modules();
         self.print_sorted(self.variable_names().collect(), FormatType::Identifier)
     }
 
-    pub fn print_units(&self) -> Markup {
+    pub fn print_units(&mut self) -> Markup {
+        self.load_all_lazy_modules();
         let units = self.unit_names().iter().flatten().cloned().collect();
         self.print_sorted(units, FormatType::Unit)
     }
@@ -303,6 +638,10 @@ impl Context {
             add_if_valid(dimension.into());
         }
 
+        for name in self.lazy_modules.names() {
+            add_if_valid(name.as_str().into());
+        }
+
         for (_, (_, meta)) in self.unit_representations() {
             for (unit, accepts_prefix) in meta.aliases {
                 // Add some of the common long prefixes for units that accept them.
@@ -340,8 +679,12 @@ impl Context {
         }
 
         if keyword.is_empty() {
-            return m::text("Usage: info <unit or variable>");
+            return m::text("Usage: info <unit, variable, function or module>");
         }
+
+        // Errors in modules are reported once their definitions are used in code
+        let _ = self.load_module_defining(keyword);
+
         let reg = self.interpreter.get_unit_registry();
 
         if let PrefixParserResult::UnitIdentifier(_span, prefix, _, full_name) =
@@ -359,6 +702,9 @@ impl Context {
                     help += m::text(" (") + m::string(url_encode(url)) + m::text(")");
                 }
                 help += m::nl();
+                if let Some(message) = &md.deprecated {
+                    help += m::text("Deprecated: ") + m::text(message.clone()) + m::nl();
+                }
                 if md.aliases.len() > 1 {
                     help += m::text("Aliases: ")
                         + m::text(
@@ -456,6 +802,9 @@ impl Context {
                 help += m::text(" (") + m::string(url_encode(url)) + m::text(")");
             }
             help += m::nl();
+            if let Some(message) = &l.metadata.deprecated {
+                help += m::text("Deprecated: ") + m::text(message.clone()) + m::nl();
+            }
 
             if let Some(description) = &l.metadata.description {
                 let desc = "Description: ";
@@ -491,9 +840,12 @@ impl Context {
                     + m::nl();
             }
 
+            // Looking up the value is not a use of the variable that should be warned about
+            let num_warnings = self.warnings.len();
             if let Ok((_, results)) = self.interpret(keyword, CodeSource::Internal) {
                 help += m::nl() + results.to_markup(None, self.dimension_registry(), true, true);
             }
+            self.warnings.truncate(num_warnings);
 
             return help;
         }
@@ -512,6 +864,10 @@ impl Context {
             }
             help += m::nl();
 
+            if let Some(message) = &metadata.deprecated {
+                help += m::text("Deprecated:  ") + m::text(message.clone()) + m::nl();
+            }
+
             help += m::text("Signature:  ")
                 + m::space()
                 + fn_signature.pretty_print(self.typechecker.registry())
@@ -541,6 +897,45 @@ impl Context {
             return help;
         }
 
+        if let Some(info) = self.module_info(keyword) {
+            let mut help =
+                m::text("Module: ") + m::keyword(info.path.to_compact_string()) + m::nl();
+
+            if let Some(description) = &info.description {
+                let desc = "Description: ";
+                let mut lines = description.lines();
+                help += m::text(desc)
+                    + m::text(lines.next().unwrap_or("").to_compact_string())
+                    + m::nl();
+
+                for line in lines {
+                    help += m::whitespace(CompactString::const_new(" ").repeat(desc.len()))
+                        + m::text(line.to_compact_string())
+                        + m::nl();
+                }
+            }
+
+            let formatter = ColumnFormatter::new(self.terminal_width.unwrap_or(80));
+            let imports = info.imports.iter().map(|p| p.to_compact_string()).collect();
+            for (heading, names, format_type) in [
+                ("Imports:", &imports, FormatType::Text),
+                ("Functions:", &info.functions, FormatType::Identifier),
+                ("Dimensions:", &info.dimensions, FormatType::TypeIdentifier),
+                ("Units:", &info.units, FormatType::Unit),
+                ("Constants:", &info.variables, FormatType::Identifier),
+                ("Structs:", &info.structs, FormatType::TypeIdentifier),
+            ] {
+                if !names.is_empty() {
+                    help += m::nl()
+                        + m::emphasized(heading)
+                        + m::nl()
+                        + formatter.format(names.clone(), format_type);
+                }
+            }
+
+            return help;
+        }
+
         m::text("Not found")
     }
 
@@ -549,6 +944,47 @@ impl Context {
         modules.into_iter().map(|m| m.0.join_compact("::"))
     }
 
+    /// Get the description of a module (like `units::imperial`) and the names it
+    /// defines. The module does not need to be loaded.
+    pub fn module_info(&self, module_path: &str) -> Option<ModuleInfo> {
+        let path = ModulePath(module_path.split("::").map(CompactString::from).collect());
+        let (code, _) = self.resolver.get_importer().import(&path)?;
+        Some(ModuleInfo::from_code(path, &code))
+    }
+
+    /// A list of all available modules with their descriptions and the names they
+    /// define.
+    pub fn print_modules(&self) -> Markup {
+        let mut output = m::empty();
+
+        for module_path in self.list_modules() {
+            let Some(info) = self.module_info(&module_path) else {
+                continue;
+            };
+
+            output += m::keyword(module_path);
+            if let Some(description) = &info.description {
+                output += m::text(": ")
+                    + m::text(description.lines().next().unwrap_or("").to_compact_string());
+            }
+            output += m::nl();
+
+            let names = info
+                .functions
+                .iter()
+                .chain(&info.dimensions)
+                .chain(&info.units)
+                .chain(&info.variables)
+                .chain(&info.structs)
+                .join(", ");
+            if !names.is_empty() {
+                output += m::whitespace("    ") + m::text(names.to_compact_string()) + m::nl();
+            }
+        }
+
+        output
+    }
+
     pub fn dimension_registry(&self) -> &DimensionRegistry {
         self.typechecker.registry()
     }
@@ -601,16 +1037,29 @@ impl Context {
         code: &'a str,
         code_source: CodeSource,
     ) -> Result<(Vec<typed_ast::Statement<'a>>, InterpreterResult)> {
-        let statements = self
-            .resolver
-            .resolve(code, code_source.clone())
-            .map_err(NumbatError::ResolverError)?;
+        self.load_imported_modules(code)?;
+
+        let statements = self.resolver.resolve(code, code_source.clone());
+        self.collect_warnings();
+        let statements = statements.map_err(NumbatError::ResolverError)?;
 
+        self.load_modules_for_definitions(&statements)?;
+
+        self.interpret_resolved_statements(settings, statements, code, code_source)
+    }
+
+    fn interpret_resolved_statements<'a>(
+        &mut self,
+        settings: &mut InterpreterSettings,
+        statements: Vec<ast::Statement<'a>>,
+        code: &'a str,
+        code_source: CodeSource,
+    ) -> Result<(Vec<typed_ast::Statement<'a>>, InterpreterResult)> {
         let prefix_transformer_old = self.prefix_transformer.clone();
 
         let result = self
             .prefix_transformer
-            .transform(statements)
+            .transform(statements.clone())
             .map_err(NumbatError::NameResolutionError);
 
         if result.is_err() {
@@ -635,6 +1084,8 @@ impl Context {
             .check(&transformed_statements)
             .map_err(|err| NumbatError::TypeCheckError(*err));
 
+        self.collect_warnings();
+
         if result.is_err() {
             // Reset the state of the prefix transformer to what we had before. This is necessary
             // for REPL use cases where we want to back track from type-check errors.
@@ -649,198 +1100,54 @@ impl Context {
             self.prefix_transformer = prefix_transformer_old.clone();
             self.typechecker = typechecker_old.clone();
 
-            if self.load_currency_module_on_demand {
-                if let Err(NumbatError::TypeCheckError(TypeCheckError::UnknownIdentifier(
-                    _,
-                    identifier,
-                    _,
-                ))) = &result
-                {
-                    // TODO: maybe we can somehow load this list of identifiers from units::currencies?
-                    const CURRENCY_IDENTIFIERS: &[&str] = &[
-                        "$",
-                        "USD",
-                        "usd",
-                        "dollar",
-                        "dollars",
-                        "A$",
-                        "AUD",
-                        "aud",
-                        "australian_dollar",
-                        "australian_dollars",
-                        "C$",
-                        "CAD",
-                        "cad",
-                        "canadian_dollar",
-                        "canadian_dollars",
-                        "CHF",
-                        "chf",
-                        "swiss_franc",
-                        "swiss_francs",
-                        "CNY",
-                        "cny",
-                        "yuan",
-                        "renminbi",
-                        "元",
-                        "EUR",
-                        "eur",
-                        "euro",
-                        "euros",
-                        "€",
-                        "GBP",
-                        "gbp",
-                        "british_pound",
-                        "pound_sterling",
-                        "£",
-                        "JPY",
-                        "jpy",
-                        "yen",
-                        "yens",
-                        "¥",
-                        "円",
-                        "bulgarian_lev",
-                        "bulgarian_leva",
-                        "BGN",
-                        "bgn",
-                        "czech_koruna",
-                        "czech_korunas",
-                        "CZK",
-                        "czk",
-                        "Kč",
-                        "hungarian_forint",
-                        "hungarian_forints",
-                        "HUF",
-                        "huf",
-                        "Ft",
-                        "polish_zloty",
-                        "polish_zlotys",
-                        "PLN",
-                        "pln",
-                        "zł",
-                        "romanian_leu",
-                        "romanian_leus",
-                        "RON",
-                        "ron",
-                        "lei",
-                        "turkish_lira",
-                        "turkish_liras",
-                        "TRY",
-                        "try",
-                        "₺",
-                        "brazilian_real",
-                        "brazilian_reals",
-                        "BRL",
-                        "brl",
-                        "R$",
-                        "hong_kong_dollar",
-                        "hong_kong_dollars",
-                        "HKD",
-                        "hkd",
-                        "HK$",
-                        "hk$",
-                        "indonesian_rupiah",
-                        "indonesian_rupiahs",
-                        "IDR",
-                        "idr",
-                        "Rp",
-                        "indian_rupee",
-                        "indian_rupees",
-                        "INR",
-                        "inr",
-                        "₹",
-                        "south_korean_won",
-                        "south_korean_wons",
-                        "KRW",
-                        "krw",
-                        "₩",
-                        "malaysian_ringgit",
-                        "malaysian_ringgits",
-                        "MYR",
-                        "RM",
-                        "new_zealand_dollar",
-                        "new_zealand_dollars",
-                        "NZD",
-                        "nzd",
-                        "NZ$",
-                        "nz$",
-                        "philippine_peso",
-                        "philippine_pesos",
-                        "PHP",
-                        "php",
-                        "₱",
-                        "singapore_dollar",
-                        "singapore_dollars",
-                        "SGD",
-                        "sgd",
-                        "S$",
-                        "thai_baht",
-                        "thai_bahts",
-                        "THB",
-                        "thb",
-                        "฿",
-                        "danish_krone",
-                        "danish_kroner",
-                        "DKK",
-                        "dkk",
-                        "swedish_krona",
-                        "swedish_kronor",
-                        "SEK",
-                        "sek",
-                        "icelandic_króna",
-                        "icelandic_krónur",
-                        "ISK",
-                        "isk",
-                        "norwegian_krone",
-                        "norwegian_kroner",
-                        "NOK",
-                        "nok",
-                        "israeli_new_shekel",
-                        "israeli_new_shekels",
-                        "ILS",
-                        "ils",
-                        "₪",
-                        "NIS",
-                        "nis",
-                        "south_african_rand",
-                        "ZAR",
-                        "zar",
-                    ];
-                    if CURRENCY_IDENTIFIERS.contains(&identifier.as_str()) {
-                        let mut no_print_settings = InterpreterSettings {
-                            print_fn: Box::new(
-                                move |_: &m::Markup| { // ignore any print statements when loading this module asynchronously
-                                },
-                            ),
-                        };
-
-                        // We also call this from a thread at program startup, so if a user only starts
-                        // to use currencies later on, this will already be available and return immediately.
-                        // Otherwise, we fetch it now and make sure to block on this call.
-                        {
-                            let erc = ExchangeRatesCache::fetch();
-
-                            if erc.is_none() {
-                                return Err(Box::new(NumbatError::RuntimeError(
-                                    RuntimeError::CouldNotLoadExchangeRates,
-                                )));
-                            }
+            if let Err(error) = &result {
+                if self.load_module_for_error(error)? {
+                    return self.interpret_resolved_statements(
+                        settings,
+                        statements,
+                        code,
+                        code_source,
+                    );
+                }
+            }
+
+            if let Err(error) = &result {
+                if self.needs_currency_module(error) {
+                    let mut no_print_settings = InterpreterSettings {
+                        print_fn: Box::new(
+                            move |_: &m::Markup| { // ignore any print statements when loading this module asynchronously
+                            },
+                        ),
+                        ..Default::default()
+                    };
+
+                    // We also call this from a thread at program startup, so if a user only starts
+                    // to use currencies later on, this will already be available and return immediately.
+                    // Otherwise, we fetch it now and make sure to block on this call.
+                    {
+                        let erc = ExchangeRatesCache::fetch();
+
+                        if erc.is_none() {
+                            return Err(Box::new(NumbatError::RuntimeError(
+                                RuntimeError::CouldNotLoadExchangeRates,
+                            )));
                         }
+                    }
 
-                        let _ = self.interpret_with_settings(
-                            &mut no_print_settings,
-                            "use units::currencies",
-                            CodeSource::Internal,
-                        )?;
+                    let _ = self.interpret_with_settings(
+                        &mut no_print_settings,
+                        "use units::currencies",
+                        CodeSource::Internal,
+                    )?;
 
-                        // Make sure we do not run into an infinite loop in case loading that
-                        // module did not bring in the required currency unit identifier. This
-                        // can happen if the list of currency identifiers is not in sync with
-                        // what the module actually defines.
-                        self.load_currency_module_on_demand = false;
+                    // Make sure we do not run into an infinite loop in case loading that
+                    // module did not bring in the required currency unit identifier. This
+                    // can happen if the list of currency identifiers is not in sync with
+                    // what the module actually defines.
+                    self.load_currency_module_on_demand = false;
 
-                        // Now we try to evaluate the user expression again:
-                        return self.interpret_with_settings(settings, code, code_source);
-                    }
+                    // Now we try to evaluate the user expression again:
+                    return self.interpret_with_settings(settings, code, code_source);
                 }
             }
         }
@@ -849,10 +1156,16 @@ impl Context {
 
         let interpreter_old = self.interpreter.clone();
 
+        if let Some(debugger) = &mut settings.debugger {
+            debugger.set_files(self.resolver.files.clone());
+        }
+
         let result = self.interpreter.interpret_statements(
             settings,
             &typed_statements,
             self.typechecker.registry(),
+            &self.resolver.files,
+            self.resolver.get_importer(),
         );
 
         if result.is_err() {
@@ -876,6 +1189,378 @@ impl Context {
         Ok((typed_statements, result))
     }
 
+    /// Move the warnings from the different stages of the pipeline into this context,
+    /// skipping suppressed categories and warnings that have already been reported.
+    fn collect_warnings(&mut self) {
+        let warnings = self
+            .resolver
+            .take_warnings()
+            .into_iter()
+            .chain(self.prefix_transformer.take_warnings())
+            .chain(self.typechecker.take_warnings());
+
+        for warning in warnings {
+            if self.suppressed_warnings.contains(&warning.category())
+                || self.warnings.contains(&warning)
+            {
+                continue;
+            }
+
+            // Redefining a constant is common in interactive sessions. Only warn if the
+            // original definition is in the same file, or if it comes from a module.
+            if let Warning::Redefinition {
+                span,
+                original_span,
+                ..
+            } = &warning
+            {
+                if span.code_source_id != original_span.code_source_id
+                    && !matches!(
+                        self.resolver.get_code_source(original_span.code_source_id),
+                        CodeSource::Module(..)
+                    )
+                {
+                    continue;
+                }
+            }
+
+            // Modules are checked with `numbat audit-units` instead, so that ambiguities
+            // in the standard library are not reported on every startup.
+            if let Warning::AmbiguousUnit { span, .. } = &warning {
+                if matches!(
+                    self.resolver.get_code_source(span.code_source_id),
+                    CodeSource::Module(..)
+                ) {
+                    continue;
+                }
+            }
+
+            self.warnings.push(warning);
+        }
+    }
+
+    /// Take the warnings that were found since the last call, e.g. while
+    /// running [`Context::interpret`] or [`Context::check`]. Warnings are
+    /// reported even if the code could not be run because of an error.
+    pub fn take_warnings(&mut self) -> Vec<Warning> {
+        std::mem::take(&mut self.warnings)
+    }
+
+    /// Enable or disable warnings of the given category. All warnings are enabled
+    /// by default.
+    pub fn set_warning_enabled(&mut self, category: WarningCategory, enabled: bool) {
+        if enabled {
+            self.suppressed_warnings.remove(&category);
+        } else {
+            self.suppressed_warnings.insert(category);
+        }
+    }
+
+    /// Whether the given error is caused by a currency unit that can be provided by
+    /// loading the `units::currencies` module on demand.
+    fn needs_currency_module(&self, error: &NumbatError) -> bool {
+        if !self.load_currency_module_on_demand {
+            return false;
+        }
+
+        let NumbatError::TypeCheckError(error) = error else {
+            return false;
+        };
+
+        // TODO: maybe we can somehow load this list of identifiers from units::currencies?
+        const CURRENCY_IDENTIFIERS: &[&str] = &[
+            "$",
+            "USD",
+            "usd",
+            "dollar",
+            "dollars",
+            "A$",
+            "AUD",
+            "aud",
+            "australian_dollar",
+            "australian_dollars",
+            "C$",
+            "CAD",
+            "cad",
+            "canadian_dollar",
+            "canadian_dollars",
+            "CHF",
+            "chf",
+            "swiss_franc",
+            "swiss_francs",
+            "CNY",
+            "cny",
+            "yuan",
+            "renminbi",
+            "元",
+            "EUR",
+            "eur",
+            "euro",
+            "euros",
+            "€",
+            "GBP",
+            "gbp",
+            "british_pound",
+            "pound_sterling",
+            "£",
+            "JPY",
+            "jpy",
+            "yen",
+            "yens",
+            "¥",
+            "円",
+            "bulgarian_lev",
+            "bulgarian_leva",
+            "BGN",
+            "bgn",
+            "czech_koruna",
+            "czech_korunas",
+            "CZK",
+            "czk",
+            "Kč",
+            "hungarian_forint",
+            "hungarian_forints",
+            "HUF",
+            "huf",
+            "Ft",
+            "polish_zloty",
+            "polish_zlotys",
+            "PLN",
+            "pln",
+            "zł",
+            "romanian_leu",
+            "romanian_leus",
+            "RON",
+            "ron",
+            "lei",
+            "turkish_lira",
+            "turkish_liras",
+            "TRY",
+            "try",
+            "₺",
+            "brazilian_real",
+            "brazilian_reals",
+            "BRL",
+            "brl",
+            "R$",
+            "hong_kong_dollar",
+            "hong_kong_dollars",
+            "HKD",
+            "hkd",
+            "HK$",
+            "hk$",
+            "indonesian_rupiah",
+            "indonesian_rupiahs",
+            "IDR",
+            "idr",
+            "Rp",
+            "indian_rupee",
+            "indian_rupees",
+            "INR",
+            "inr",
+            "₹",
+            "south_korean_won",
+            "south_korean_wons",
+            "KRW",
+            "krw",
+            "₩",
+            "malaysian_ringgit",
+            "malaysian_ringgits",
+            "MYR",
+            "RM",
+            "new_zealand_dollar",
+            "new_zealand_dollars",
+            "NZD",
+            "nzd",
+            "NZ$",
+            "nz$",
+            "philippine_peso",
+            "philippine_pesos",
+            "PHP",
+            "php",
+            "₱",
+            "singapore_dollar",
+            "singapore_dollars",
+            "SGD",
+            "sgd",
+            "S$",
+            "thai_baht",
+            "thai_bahts",
+            "THB",
+            "thb",
+            "฿",
+            "danish_krone",
+            "danish_kroner",
+            "DKK",
+            "dkk",
+            "swedish_krona",
+            "swedish_kronor",
+            "SEK",
+            "sek",
+            "icelandic_króna",
+            "icelandic_krónur",
+            "ISK",
+            "isk",
+            "norwegian_krone",
+            "norwegian_kroner",
+            "NOK",
+            "nok",
+            "israeli_new_shekel",
+            "israeli_new_shekels",
+            "ILS",
+            "ils",
+            "₪",
+            "NIS",
+            "nis",
+            "south_african_rand",
+            "ZAR",
+            "zar",
+        ];
+        error.errors().iter().any(|error| {
+            matches!(
+                error,
+                TypeCheckError::UnknownIdentifier(_, identifier, _)
+                    if CURRENCY_IDENTIFIERS.contains(&identifier.as_str())
+            )
+        })
+    }
+
+    /// Parse, resolve and type check the given code without evaluating it.
+    ///
+    /// If successful, all definitions are registered in the type checker (but not in
+    /// the interpreter). This is meant for tooling that only needs static information,
+    /// e.g. editor integrations.
+    pub fn check<'a>(
+        &mut self,
+        code: &'a str,
+        code_source: CodeSource,
+    ) -> Result<Vec<typed_ast::Statement<'a>>> {
+        self.load_imported_modules(code)?;
+
+        let statements = self.resolver.resolve(code, code_source.clone());
+        self.collect_warnings();
+        let statements = statements.map_err(NumbatError::ResolverError)?;
+
+        self.load_modules_for_definitions(&statements)?;
+
+        let result = self.check_resolved_statements(statements);
+
+        // Currency units are only type checked here, so that exchange rates do not
+        // need to be fetched.
+        if let Err(error) = &result {
+            if self.needs_currency_module(error) {
+                self.check("use units::currencies", CodeSource::Internal)?;
+                self.load_currency_module_on_demand = false;
+
+                return self.check(code, code_source);
+            }
+        }
+
+        result
+    }
+
+    fn check_resolved_statements<'a>(
+        &mut self,
+        statements: Vec<ast::Statement<'a>>,
+    ) -> Result<Vec<typed_ast::Statement<'a>>> {
+        let prefix_transformer_old = self.prefix_transformer.clone();
+
+        let result = self
+            .prefix_transformer
+            .transform(statements.clone())
+            .map_err(NumbatError::NameResolutionError);
+
+        if result.is_err() {
+            self.prefix_transformer = prefix_transformer_old.clone();
+        }
+
+        let transformed_statements = result?;
+
+        let typechecker_old = self.typechecker.clone();
+
+        let result = self
+            .typechecker
+            .check(&transformed_statements)
+            .map_err(|err| NumbatError::TypeCheckError(*err));
+
+        self.collect_warnings();
+
+        if let Err(error) = &result {
+            self.prefix_transformer = prefix_transformer_old;
+            self.typechecker = typechecker_old;
+
+            if self.load_module_for_error(error)? {
+                return self.check_resolved_statements(statements);
+            }
+        }
+
+        Ok(result?)
+    }
+
+    /// Get the span of the definition of a variable, function, unit (possibly with
+    /// a prefix), dimension or struct.
+    pub fn definition_span(&self, identifier: &str) -> Option<Span> {
+        let name = match self.prefix_transformer.prefix_parser.parse(identifier) {
+            PrefixParserResult::UnitIdentifier(_, _, _, full_name) => full_name,
+            PrefixParserResult::Identifier(_) => identifier.to_compact_string(),
+        };
+
+        self.typechecker.lookup_definition_span(&name)
+    }
+
+    /// Get the message of the `@deprecated` decorator of a variable, function or unit
+    /// (possibly with a prefix), if it is deprecated.
+    pub fn deprecation_message(&self, identifier: &str) -> Option<CompactString> {
+        let span = self.definition_span(identifier)?;
+        self.typechecker.lookup_deprecation(span).cloned()
+    }
+
+    /// Identifiers that are parsed as a unit, but could also be read as a prefix and
+    /// a different unit (like 'min', which could mean milli-inch), together with all
+    /// ways to read them.
+    pub fn ambiguous_unit_identifiers(&self) -> Vec<(CompactString, Vec<UnitReading>)> {
+        self.prefix_transformer
+            .prefix_parser
+            .ambiguous_identifiers()
+    }
+
+    /// Get the span and the readable type of the innermost expression in `statements`
+    /// that contains the given byte offset in the code source with ID `code_source_id`.
+    pub fn type_at(
+        &self,
+        statements: &[typed_ast::Statement],
+        code_source_id: usize,
+        offset: u32,
+    ) -> Option<(Span, Markup)> {
+        use traversal::ForAllExpressions;
+
+        let mut innermost: Option<(Span, Markup)> = None;
+        for statement in statements {
+            statement.for_all_expressions(&mut |expr| {
+                let span = expr.full_span();
+                if span.code_source_id != code_source_id
+                    || offset < span.start.0
+                    || offset > span.end.0
+                {
+                    return;
+                }
+
+                let is_smaller = innermost
+                    .as_ref()
+                    .map(|(s, _)| span.end.0 - span.start.0 < s.end.0 - s.start.0)
+                    .unwrap_or(true);
+                if is_smaller {
+                    let readable_type = expr
+                        .get_type_scheme()
+                        .to_readable_type(self.typechecker.registry(), true);
+                    innermost = Some((span, readable_type));
+                }
+            });
+        }
+
+        innermost
+    }
+
     pub fn print_diagnostic(&self, error: impl ErrorDiagnostic) {
         use codespan_reporting::term::{
             self,
@@ -883,7 +1568,11 @@ impl Context {
             Config,
         };
 
-        let writer = StandardStream::stderr(ColorChoice::Auto);
+        let writer = StandardStream::stderr(if self.plain_diagnostics {
+            ColorChoice::Never
+        } else {
+            ColorChoice::Auto
+        });
         let config = Config::default();
 
         // we want to be sure no one can write between our diagnostics
@@ -896,4 +1585,9 @@ impl Context {
     pub fn set_terminal_width(&mut self, width: Option<usize>) {
         self.terminal_width = width;
     }
+
+    /// Whether [`Context::print_diagnostic`] may use colors (if supported by the terminal).
+    pub fn set_colored_diagnostics(&mut self, enabled: bool) {
+        self.plain_diagnostics = !enabled;
+    }
 }
@@ -7,10 +7,12 @@
 
 use std::{collections::VecDeque, fmt, sync::Arc};
 
+use serde::{Deserialize, Serialize};
+
 use crate::{value::Value, RuntimeError};
 
 /// Reference counted list / list view
-#[derive(Clone, Eq)]
+#[derive(Clone, Eq, Serialize, Deserialize)]
 pub struct NumbatList<T> {
     /// The original alloc shared between all values
     alloc: Arc<VecDeque<T>>,
@@ -1,8 +1,9 @@
 use std::fmt::Display;
 
 use compact_str::CompactString;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Copy, Clone, PartialEq)]
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
 pub enum FormatType {
     Whitespace,
     Emphasized,
@@ -18,7 +19,7 @@ pub enum FormatType {
     Decorator,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum OutputType {
     Normal,
     Optional,
@@ -48,6 +49,18 @@ impl From<CompactStrCow> for CompactString {
     }
 }
 
+impl Serialize for CompactStrCow {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for CompactStrCow {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        CompactString::deserialize(deserializer).map(Self::Owned)
+    }
+}
+
 impl std::ops::Deref for CompactStrCow {
     type Target = str;
 
@@ -71,10 +84,10 @@ impl From<&'static str> for CompactStrCow {
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct FormattedString(pub OutputType, pub FormatType, pub CompactStrCow);
 
-#[derive(Debug, Clone, Default, PartialEq)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct Markup(pub Vec<FormattedString>);
 
 impl Markup {
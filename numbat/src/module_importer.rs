@@ -12,6 +12,12 @@ use crate::resolver::ModulePath;
 pub trait ModuleImporter: Send + Sync {
     fn import(&self, path: &ModulePath) -> Option<(String, Option<PathBuf>)>;
     fn list_modules(&self) -> Vec<ModulePath>;
+
+    /// Look up a data file (e.g. a CSV file for `read_csv`) with the given
+    /// relative path in the search paths of this importer.
+    fn find_file(&self, _path: &Path) -> Option<PathBuf> {
+        None
+    }
 }
 
 #[derive(Debug, Clone, Default)]
@@ -86,6 +92,13 @@ impl ModuleImporter for FileSystemImporter {
         }
         modules
     }
+
+    fn find_file(&self, path: &Path) -> Option<PathBuf> {
+        self.root_paths
+            .iter()
+            .map(|root_path| root_path.join(path))
+            .find(|path| path.is_file())
+    }
 }
 
 #[derive(RustEmbed)]
@@ -159,4 +172,10 @@ impl ModuleImporter for ChainedImporter {
 
         modules
     }
+
+    fn find_file(&self, path: &Path) -> Option<PathBuf> {
+        self.main
+            .find_file(path)
+            .or_else(|| self.fallback.find_file(path))
+    }
 }
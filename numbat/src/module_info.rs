@@ -0,0 +1,162 @@
+//! Documentation for modules.
+//!
+//! A module can be documented with a block of comment lines at the very top of its
+//! file (the module docstring):
+//!
+//! ```nbt
+//! # Units from the imperial system,
+//! # like inch, foot and pound.
+//!
+//! use units::si
+//! ```
+
+use compact_str::{CompactString, ToCompactString};
+
+use crate::ast::Statement;
+use crate::parser::parse;
+use crate::resolver::ModulePath;
+
+/// The description of a module and the names it defines.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModuleInfo {
+    pub path: ModulePath,
+    pub description: Option<CompactString>,
+    /// Modules that are imported by this module
+    pub imports: Vec<ModulePath>,
+    pub functions: Vec<CompactString>,
+    pub dimensions: Vec<CompactString>,
+    pub units: Vec<CompactString>,
+    pub variables: Vec<CompactString>,
+    pub structs: Vec<CompactString>,
+}
+
+impl ModuleInfo {
+    /// Collects the documentation of a module from its source code, without loading it.
+    pub(crate) fn from_code(path: ModulePath, code: &str) -> Self {
+        let mut info = ModuleInfo {
+            path,
+            description: docstring(code),
+            imports: vec![],
+            functions: vec![],
+            dimensions: vec![],
+            units: vec![],
+            variables: vec![],
+            structs: vec![],
+        };
+
+        let statements = match parse(code, 0) {
+            Ok(statements) => statements,
+            Err((statements, _)) => statements,
+        };
+
+        for statement in &statements {
+            let (names, name) = match statement {
+                Statement::DefineVariable(define_variable) => {
+                    (&mut info.variables, define_variable.identifier)
+                }
+                Statement::DefineFunction { function_name, .. } => {
+                    (&mut info.functions, *function_name)
+                }
+                Statement::DefineDimension(_, name, _) => (&mut info.dimensions, *name),
+                Statement::DefineBaseUnit(_, name, _, _)
+                | Statement::DefineDerivedUnit {
+                    identifier: name, ..
+                } => (&mut info.units, *name),
+                Statement::DefineStruct { struct_name, .. } => (&mut info.structs, *struct_name),
+                Statement::ModuleImport(_, module_path) => {
+                    info.imports.push(ModulePath(
+                        module_path
+                            .0
+                            .iter()
+                            .map(|p| p.to_compact_string())
+                            .collect(),
+                    ));
+                    continue;
+                }
+                Statement::Expression(_)
+                | Statement::ProcedureCall(..)
+                | Statement::ForLoop { .. } => continue,
+            };
+
+            // Names starting with an underscore are private
+            if !name.starts_with('_') {
+                names.push(name.to_compact_string());
+            }
+        }
+
+        info
+    }
+}
+
+/// The docstring of a module: the comment lines at the very top of the file, up to
+/// the first line that is not a comment. Leading `#` characters are removed.
+pub fn docstring(code: &str) -> Option<CompactString> {
+    let lines: Vec<_> = code
+        .lines()
+        .take_while(|line| line.starts_with('#'))
+        .filter(|line| !line.starts_with("#!"))
+        .map(|line| {
+            let line = line.trim_start_matches('#');
+            line.strip_prefix(' ').unwrap_or(line).trim_end()
+        })
+        .collect();
+
+    let docstring = lines.join("\n");
+    let docstring = docstring.trim();
+
+    (!docstring.is_empty()).then(|| docstring.to_compact_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn docstrings() {
+        assert_eq!(docstring("use units::si\n"), None);
+        assert_eq!(docstring("\n# Not a docstring\n"), None);
+        assert_eq!(
+            docstring("# Imperial units\n# like inch and foot\n\n# Length\nunit inch"),
+            Some("Imperial units\nlike inch and foot".into())
+        );
+        assert_eq!(
+            docstring("### Physical dimensions\n#\ndimension Length"),
+            Some("Physical dimensions".into())
+        );
+        assert_eq!(
+            docstring("#!/usr/bin/env numbat\n# Script\nprint(1)"),
+            Some("Script".into())
+        );
+    }
+
+    #[test]
+    fn exported_names() {
+        let info = ModuleInfo::from_code(
+            ModulePath(vec!["test".into()]),
+            "# A test module
+use core::scalar
+
+dimension Foo
+@aliases(foos)
+unit foo: Foo
+unit bar = 2 foo
+let baz = 3 bar
+fn quux(x) = _helper(x)
+fn _helper(x) = x
+let _private = 1
+struct Point { x: Scalar, y: Scalar }
+",
+        );
+
+        assert_eq!(info.description.as_deref(), Some("A test module"));
+        assert_eq!(
+            info.imports,
+            [ModulePath(vec!["core".into(), "scalar".into()])]
+        );
+        assert_eq!(info.dimensions, ["Foo"]);
+        assert_eq!(info.units, ["foo", "bar"]);
+        assert_eq!(info.variables, ["baz"]);
+        assert_eq!(info.functions, ["quux"]);
+        assert_eq!(info.structs, ["Point"]);
+    }
+}
@@ -1,4 +1,5 @@
 use compact_str::CompactString;
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 use crate::{span::Span, typechecker::map_stack::MapStack};
@@ -20,7 +21,7 @@ pub enum NameResolutionError {
     ReservedIdentifier(Span),
 }
 
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Namespace {
     seen: MapStack<CompactString, (CompactString, Span)>,
 }
@@ -34,6 +35,10 @@ impl Namespace {
         self.seen.restore()
     }
 
+    pub(crate) fn restore_all(&mut self) {
+        self.seen.restore_all()
+    }
+
     pub fn add_identifier_allow_override(
         &mut self,
         name: CompactString,
@@ -56,6 +61,14 @@ impl Namespace {
         self.seen.contains_key(name)
     }
 
+    pub(crate) fn get_definition_span(&self, name: &str) -> Option<Span> {
+        self.seen.get(name).map(|(_, span)| *span)
+    }
+
+    pub(crate) fn get_item_type(&self, name: &str) -> Option<&str> {
+        self.seen.get(name).map(|(item_type, _)| item_type.as_str())
+    }
+
     fn add_impl(
         &mut self,
         name: CompactString,
@@ -2,7 +2,7 @@
 //!
 //! Grammar:
 //! ```txt
-//! statement       ::=   variable_decl | struct_decl | function_decl | dimension_decl | unit_decl | module_import | procedure_call | expression
+//! statement       ::=   variable_decl | struct_decl | function_decl | dimension_decl | unit_decl | module_import | procedure_call | for_loop | expression
 //!
 //! variable_decl   ::=   "let" identifier ( ":" type_annotation ) ? "=" expression
 //! struct_decl     ::=   "struct" identifier "{" ( identifier ":" type_annotation "," )* ( identifier ":" type_annotation "," ? ) ? "}"
@@ -13,6 +13,7 @@
 //! unit_decl       ::=   decorator * "unit" ( ":" dimension_expr ) ? ( "=" expression ) ?
 //! module_import   ::=   "use" ident ( "::" ident) *
 //! procedure_call  ::=   ( "print" | "assert" | "assert_eq" | "type" ) "(" arguments? ")"
+//! for_loop        ::=   "for" identifier "in" expression "{" ( ( variable_decl | procedure_call | for_loop ) ( "\n" | "}" ) ) * "}"
 //!
 //! decorator       ::=   "@" ( "metric_prefixes" | "binary_prefixes" | ( "aliases(" list_of_aliases ")" ) )
 //!
@@ -73,6 +74,7 @@ use crate::prefix_parser::AcceptsPrefix;
 use crate::resolver::ModulePathBorrowed;
 use crate::span::Span;
 use crate::tokenizer::{Token, TokenKind, TokenizerError, TokenizerErrorKind};
+use crate::warning::Warning;
 
 use compact_str::{CompactString, ToCompactString};
 use num_traits::{CheckedDiv, FromPrimitive, Zero};
@@ -205,6 +207,9 @@ pub enum ParseErrorKind {
     #[error("Example decorators can only be used on functions.")]
     ExampleUsedOnUnsuitableKind,
 
+    #[error("The @test decorator can only be used on functions.")]
+    TestUsedOnUnsuitableKind,
+
     #[error("Numerical overflow in dimension exponent")]
     OverflowInDimensionExponent,
 
@@ -246,6 +251,26 @@ pub enum ParseErrorKind {
 
     #[error("Invalid command: {0}")]
     InvalidCommand(String),
+
+    #[error("Expected identifier (loop variable) after 'for'")]
+    ExpectedLoopVariable,
+
+    #[error("Expected 'in' after the loop variable")]
+    ExpectedInAfterLoopVariable,
+
+    #[error("Expected '{{' after the list in a for loop")]
+    ExpectedLeftCurlyInForLoop,
+
+    #[error(
+        "Only 'let' definitions, procedure calls and nested for loops can be used in a for loop"
+    )]
+    UnsupportedStatementInForLoop,
+
+    #[error("Expected new line or '}}' after a statement in a for loop")]
+    ExpectedNewlineOrRightCurlyInForLoop,
+
+    #[error("Missing closing '}}' in for loop")]
+    MissingClosingCurlyInForLoop,
 }
 
 #[derive(Debug, Clone, Error)]
@@ -274,6 +299,10 @@ static PROCEDURES: &[TokenKind] = &[
 struct Parser<'a> {
     current: usize,
     decorator_stack: Vec<Decorator<'a>>,
+    warnings: Vec<Warning>,
+    /// Set while parsing the list of a for loop, where `xs { … }` is the loop body,
+    /// not a struct instantiation.
+    no_struct_instantiation: bool,
 }
 
 impl<'a> Parser<'a> {
@@ -281,6 +310,8 @@ impl<'a> Parser<'a> {
         Parser {
             current: 0,
             decorator_stack: vec![],
+            warnings: vec![],
+            no_struct_instantiation: false,
         }
     }
 
@@ -373,10 +404,10 @@ impl<'a> Parser<'a> {
             } else if self.match_exact(tokens, TokenKind::None).is_some() {
                 Ok(Some(AcceptsPrefix::none()))
             } else {
-                return Err(ParseError::new(
+                Err(ParseError::new(
                     ParseErrorKind::UnknownAliasAnnotation,
                     self.peek(tokens).span,
-                ));
+                ))
             }
         } else {
             Ok(None)
@@ -439,6 +470,8 @@ impl<'a> Parser<'a> {
             self.parse_struct(tokens)
         } else if self.match_any(tokens, PROCEDURES).is_some() {
             self.parse_procedure(tokens)
+        } else if self.match_exact(tokens, TokenKind::For).is_some() {
+            self.parse_for_loop(tokens)
         } else {
             Ok(Statement::Expression(self.expression(tokens)?))
         }
@@ -483,6 +516,13 @@ impl<'a> Parser<'a> {
                         });
                     }
 
+                    if decorator::contains_test(&self.decorator_stack) {
+                        return Err(ParseError {
+                            kind: ParseErrorKind::TestUsedOnUnsuitableKind,
+                            span: self.peek(tokens).span,
+                        });
+                    }
+
                     std::mem::swap(&mut decorators, &mut self.decorator_stack);
                 }
 
@@ -720,6 +760,7 @@ impl<'a> Parser<'a> {
             let decorator = match decorator.lexeme {
                 "metric_prefixes" => Decorator::MetricPrefixes,
                 "binary_prefixes" => Decorator::BinaryPrefixes,
+                "test" => Decorator::Test,
                 "aliases" => {
                     if self.match_exact(tokens, TokenKind::LeftParen).is_some() {
                         let aliases = self.list_of_aliases(tokens)?;
@@ -731,7 +772,7 @@ impl<'a> Parser<'a> {
                         });
                     }
                 }
-                "url" | "name" | "description" => {
+                "url" | "name" | "description" | "deprecated" => {
                     if self.match_exact(tokens, TokenKind::LeftParen).is_some() {
                         if let Some(token) = self.match_exact(tokens, TokenKind::StringFixed) {
                             if self.match_exact(tokens, TokenKind::RightParen).is_none() {
@@ -747,6 +788,7 @@ impl<'a> Parser<'a> {
                                 "url" => Decorator::Url(content),
                                 "name" => Decorator::Name(content),
                                 "description" => Decorator::Description(content),
+                                "deprecated" => Decorator::Deprecated(content),
                                 _ => unreachable!(),
                             }
                         } else {
@@ -852,6 +894,13 @@ impl<'a> Parser<'a> {
                 });
             }
 
+            if decorator::contains_test(&self.decorator_stack) {
+                return Err(ParseError {
+                    kind: ParseErrorKind::TestUsedOnUnsuitableKind,
+                    span: self.peek(tokens).span,
+                });
+            }
+
             let mut decorators = vec![];
             std::mem::swap(&mut decorators, &mut self.decorator_stack);
 
@@ -1007,6 +1056,88 @@ impl<'a> Parser<'a> {
         }
     }
 
+    fn parse_for_loop(&mut self, tokens: &[Token<'a>]) -> Result<Statement<'a>> {
+        let Some(loop_variable) = self.match_exact(tokens, TokenKind::Identifier) else {
+            return Err(ParseError::new(
+                ParseErrorKind::ExpectedLoopVariable,
+                self.peek(tokens).span,
+            ));
+        };
+
+        // 'in' is not a keyword, as it is also used as an abbreviation for inch
+        let in_token = self.peek(tokens);
+        if in_token.kind != TokenKind::Identifier || in_token.lexeme != "in" {
+            return Err(ParseError::new(
+                ParseErrorKind::ExpectedInAfterLoopVariable,
+                in_token.span,
+            ));
+        }
+        self.advance(tokens);
+
+        self.no_struct_instantiation = true;
+        let list = self.expression(tokens);
+        self.no_struct_instantiation = false;
+        let list = list?;
+
+        if self.match_exact(tokens, TokenKind::LeftCurly).is_none() {
+            return Err(ParseError::new(
+                ParseErrorKind::ExpectedLeftCurlyInForLoop,
+                self.peek(tokens).span,
+            ));
+        }
+
+        self.skip_empty_lines(tokens);
+
+        let mut body = vec![];
+        while self.match_exact(tokens, TokenKind::RightCurly).is_none() {
+            let statement = if self.match_exact(tokens, TokenKind::Let).is_some() {
+                Statement::DefineVariable(self.parse_variable(tokens, false)?)
+            } else if self.match_any(tokens, PROCEDURES).is_some() {
+                self.parse_procedure(tokens)?
+            } else if self.match_exact(tokens, TokenKind::For).is_some() {
+                self.parse_for_loop(tokens)?
+            } else if self.is_at_end(tokens) {
+                return Err(ParseError::new(
+                    ParseErrorKind::MissingClosingCurlyInForLoop,
+                    self.peek(tokens).span,
+                ));
+            } else {
+                return Err(ParseError::new(
+                    ParseErrorKind::UnsupportedStatementInForLoop,
+                    self.peek(tokens).span,
+                ));
+            };
+            body.push(statement);
+
+            if self.match_exact(tokens, TokenKind::Newline).is_none()
+                && self.peek(tokens).kind != TokenKind::RightCurly
+            {
+                return Err(ParseError::new(
+                    ParseErrorKind::ExpectedNewlineOrRightCurlyInForLoop,
+                    self.peek(tokens).span,
+                ));
+            }
+
+            self.skip_empty_lines(tokens);
+        }
+
+        Ok(Statement::ForLoop {
+            loop_variable_span: loop_variable.span,
+            loop_variable: loop_variable.lexeme,
+            list,
+            body,
+        })
+    }
+
+    /// Parse an expression in parentheses, brackets or argument lists, where struct
+    /// instantiations are allowed again.
+    fn delimited_expression(&mut self, tokens: &[Token<'a>]) -> Result<Expression<'a>> {
+        let no_struct_instantiation = std::mem::take(&mut self.no_struct_instantiation);
+        let expr = self.expression(tokens);
+        self.no_struct_instantiation = no_struct_instantiation;
+        expr
+    }
+
     /// Helper function to parse binary operations
     /// - arg `op_symbol` specifiy the separator / symbol of your operation
     /// - arg `op` specifiy the operation you're currently parsing
@@ -1392,7 +1523,7 @@ impl<'a> Parser<'a> {
             return Ok(vec![]);
         }
 
-        let mut args = vec![self.expression(tokens)?];
+        let mut args = vec![self.delimited_expression(tokens)?];
         loop {
             self.skip_empty_lines(tokens);
 
@@ -1401,7 +1532,7 @@ impl<'a> Parser<'a> {
                 if self.match_exact(tokens, TokenKind::RightParen).is_some() {
                     break;
                 }
-                match self.expression(tokens) {
+                match self.delimited_expression(tokens) {
                     Ok(expr) => args.push(expr),
                     Err(_err) => {
                         return Err(ParseError::new(
@@ -1423,6 +1554,16 @@ impl<'a> Parser<'a> {
         Ok(args)
     }
 
+    /// Convert an integer literal to a number, with a warning if it can not be
+    /// represented exactly.
+    fn integer_literal(&mut self, value: i128, span: Span) -> Number {
+        let number = value as f64;
+        if number as i128 != value {
+            self.warnings.push(Warning::PrecisionLoss(span));
+        }
+        Number::from_f64(number)
+    }
+
     fn primary(&mut self, tokens: &[Token<'a>]) -> Result<Expression<'a>> {
         // This function needs to be kept in sync with `next_token_could_start_power_expression` below.
 
@@ -1434,38 +1575,32 @@ impl<'a> Parser<'a> {
         };
 
         if let Some(num) = self.match_exact(tokens, TokenKind::Number) {
+            let span = self.last(tokens).unwrap().span;
             let num_string = num.lexeme.replace('_', "");
-            Ok(Expression::Scalar(
-                self.last(tokens).unwrap().span,
-                Number::from_f64(num_string.parse::<f64>().unwrap()),
-            ))
+            let value = num_string.parse::<f64>().unwrap();
+
+            if num_string.bytes().all(|c| c.is_ascii_digit())
+                && !num_string.parse::<i128>().is_ok_and(|i| value as i128 == i)
+            {
+                self.warnings.push(Warning::PrecisionLoss(span));
+            }
+
+            Ok(Expression::Scalar(span, Number::from_f64(value)))
         } else if let Some(hex_int) = self.match_exact(tokens, TokenKind::IntegerWithBase(16)) {
             let span = self.last(tokens).unwrap().span;
-            Ok(Expression::Scalar(
-                span,
-                Number::from_f64(
-                    i128::from_str_radix(&hex_int.lexeme[2..].replace('_', ""), 16)
-                        .or_else(|_| overflow_error(span))? as f64, // TODO: i128 limits our precision here
-                ),
-            ))
+            let value = i128::from_str_radix(&hex_int.lexeme[2..].replace('_', ""), 16)
+                .or_else(|_| overflow_error(span))?; // TODO: i128 limits our precision here
+            Ok(Expression::Scalar(span, self.integer_literal(value, span)))
         } else if let Some(oct_int) = self.match_exact(tokens, TokenKind::IntegerWithBase(8)) {
             let span = self.last(tokens).unwrap().span;
-            Ok(Expression::Scalar(
-                span,
-                Number::from_f64(
-                    i128::from_str_radix(&oct_int.lexeme[2..].replace('_', ""), 8)
-                        .or_else(|_| overflow_error(span))? as f64, // TODO: i128 limits our precision here
-                ),
-            ))
+            let value = i128::from_str_radix(&oct_int.lexeme[2..].replace('_', ""), 8)
+                .or_else(|_| overflow_error(span))?; // TODO: i128 limits our precision here
+            Ok(Expression::Scalar(span, self.integer_literal(value, span)))
         } else if let Some(bin_int) = self.match_exact(tokens, TokenKind::IntegerWithBase(2)) {
             let span = self.last(tokens).unwrap().span;
-            Ok(Expression::Scalar(
-                span,
-                Number::from_f64(
-                    i128::from_str_radix(&bin_int.lexeme[2..].replace('_', ""), 2)
-                        .or_else(|_| overflow_error(span))? as f64, // TODO: i128 limits our precision here
-                ),
-            ))
+            let value = i128::from_str_radix(&bin_int.lexeme[2..].replace('_', ""), 2)
+                .or_else(|_| overflow_error(span))?; // TODO: i128 limits our precision here
+            Ok(Expression::Scalar(span, self.integer_literal(value, span)))
         } else if self.match_exact(tokens, TokenKind::NaN).is_some() {
             let span = self.last(tokens).unwrap().span;
             Ok(Expression::Scalar(span, Number::from_f64(f64::NAN)))
@@ -1480,7 +1615,7 @@ impl<'a> Parser<'a> {
             while self.match_exact(tokens, TokenKind::RightBracket).is_none() {
                 self.skip_empty_lines(tokens);
 
-                elements.push(self.expression(tokens)?);
+                elements.push(self.delimited_expression(tokens)?);
 
                 self.skip_empty_lines(tokens);
 
@@ -1504,7 +1639,9 @@ impl<'a> Parser<'a> {
         } else if let Some(identifier) = self.match_exact(tokens, TokenKind::Identifier) {
             let span = self.last(tokens).unwrap().span;
 
-            if self.match_exact(tokens, TokenKind::LeftCurly).is_some() {
+            if !self.no_struct_instantiation
+                && self.match_exact(tokens, TokenKind::LeftCurly).is_some()
+            {
                 self.skip_empty_lines(tokens);
 
                 let mut fields = vec![];
@@ -1608,7 +1745,7 @@ impl<'a> Parser<'a> {
 
             Ok(Expression::String(span_full_string, parts))
         } else if self.match_exact(tokens, TokenKind::LeftParen).is_some() {
-            let inner = self.expression(tokens)?;
+            let inner = self.delimited_expression(tokens)?;
 
             if self.match_exact(tokens, TokenKind::RightParen).is_none() {
                 return Err(ParseError::new(
@@ -2044,17 +2181,24 @@ fn strip_and_escape(s: &str) -> CompactString {
 /// while stacking all the errors in a `Vec`. At the end, it returns the complete
 /// list of statements parsed + the list of errors accumulated.
 pub fn parse(input: &str, code_source_id: usize) -> ParseResult<'_> {
+    parse_with_warnings(input, code_source_id).0
+}
+
+/// Like [`parse`], but also returns the warnings that were found while parsing.
+pub fn parse_with_warnings(input: &str, code_source_id: usize) -> (ParseResult<'_>, Vec<Warning>) {
     use crate::tokenizer::tokenize;
 
-    let tokens = tokenize(input, code_source_id)
-        .map_err(|TokenizerError { kind, span }| {
-            ParseError::new(ParseErrorKind::TokenizerError(kind), span)
-        })
-        .map_err(|e| (Vec::new(), vec![e]))?;
-    let tokens = &tokens;
+    let tokens = match tokenize(input, code_source_id) {
+        Ok(tokens) => tokens,
+        Err(TokenizerError { kind, span }) => {
+            let error = ParseError::new(ParseErrorKind::TokenizerError(kind), span);
+            return (Err((Vec::new(), vec![error])), vec![]);
+        }
+    };
 
     let mut parser = Parser::new();
-    parser.parse(tokens)
+    let result = parser.parse(&tokens);
+    (result, parser.warnings)
 }
 
 #[cfg(test)]
@@ -2920,6 +3064,29 @@ mod tests {
             },
         );
 
+        parse_as(
+            &["@test fn some_test() = 1 + 1 == 2"],
+            Statement::DefineFunction {
+                function_name_span: Span::dummy(),
+                function_name: "some_test",
+                type_parameters: vec![],
+                parameters: vec![],
+                body: Some(binop!(
+                    binop!(scalar!(1.0), Add, scalar!(1.0)),
+                    Equal,
+                    scalar!(2.0)
+                )),
+                local_variables: vec![],
+                return_type_annotation: None,
+                decorators: vec![decorator::Decorator::Test],
+            },
+        );
+
+        should_fail_with(
+            &["@test let x = 1"],
+            ParseErrorKind::TestUsedOnUnsuitableKind,
+        );
+
         parse_as(
             &["fn double_kef(x) = y where y = x * 2"],
             Statement::DefineFunction {
@@ -3515,6 +3682,81 @@ mod tests {
         should_fail_with(&["[1,\n2,\n,\n"], ParseErrorKind::ExpectedPrimary);
     }
 
+    #[test]
+    fn for_loops() {
+        parse_as(
+            &[
+                "for x in xs { print(x) }",
+                "for x in xs {\n  print(x)\n}",
+                "for x in xs {\n\n  print(x)\n\n}",
+            ],
+            Statement::ForLoop {
+                loop_variable_span: Span::dummy(),
+                loop_variable: "x",
+                list: identifier!("xs"),
+                body: vec![Statement::ProcedureCall(
+                    Span::dummy(),
+                    ProcedureKind::Print,
+                    vec![identifier!("x")],
+                )],
+            },
+        );
+
+        parse_as(
+            &["for x in [1, 2] {}"],
+            Statement::ForLoop {
+                loop_variable_span: Span::dummy(),
+                loop_variable: "x",
+                list: list!(scalar!(1.0), scalar!(2.0)),
+                body: vec![],
+            },
+        );
+
+        parse_as(
+            &["for row in rows {\n  for x in row {\n    print(x)\n  }\n}"],
+            Statement::ForLoop {
+                loop_variable_span: Span::dummy(),
+                loop_variable: "row",
+                list: identifier!("rows"),
+                body: vec![Statement::ForLoop {
+                    loop_variable_span: Span::dummy(),
+                    loop_variable: "x",
+                    list: identifier!("row"),
+                    body: vec![Statement::ProcedureCall(
+                        Span::dummy(),
+                        ProcedureKind::Print,
+                        vec![identifier!("x")],
+                    )],
+                }],
+            },
+        );
+
+        should_fail_with(
+            &["for", "for 1 in xs {}"],
+            ParseErrorKind::ExpectedLoopVariable,
+        );
+        should_fail_with(
+            &["for x xs {}", "for x = xs {}"],
+            ParseErrorKind::ExpectedInAfterLoopVariable,
+        );
+        should_fail_with(
+            &["for x in xs print(x)"],
+            ParseErrorKind::ExpectedLeftCurlyInForLoop,
+        );
+        should_fail_with(
+            &["for x in xs { x + 1 }", "for x in xs { fn f(y) = y }"],
+            ParseErrorKind::UnsupportedStatementInForLoop,
+        );
+        should_fail_with(
+            &["for x in xs { print(x) print(x) }"],
+            ParseErrorKind::ExpectedNewlineOrRightCurlyInForLoop,
+        );
+        should_fail_with(
+            &["for x in xs {", "for x in xs {\n  print(x)\n"],
+            ParseErrorKind::MissingClosingCurlyInForLoop,
+        );
+    }
+
     #[test]
     fn accumulate_errors() {
         // error on the last character of a line
@@ -1,8 +1,9 @@
 use compact_str::{format_compact, CompactString};
+use serde::{Deserialize, Serialize};
 
 use crate::number::Number;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub enum Prefix {
     /// Represents a metric/decimal prefix symbolizing 10^n
     Metric(i32),
@@ -1,5 +1,6 @@
-use compact_str::{CompactString, ToCompactString};
+use compact_str::{format_compact, CompactString, ToCompactString};
 use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::OnceLock;
 
@@ -17,7 +18,7 @@ pub enum PrefixParserResult<'a> {
 
 type Result<T> = std::result::Result<T, NameResolutionError>;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub struct AcceptsPrefix {
     pub short: bool,
     pub long: bool,
@@ -72,7 +73,7 @@ impl AliasSpanInfo {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 struct UnitInfo {
     definition_span: Span,
     accepts_prefix: AcceptsPrefix,
@@ -81,12 +82,57 @@ struct UnitInfo {
     full_name: CompactString,
 }
 
-#[derive(Debug, Clone)]
+/// One way to read an identifier as a (possibly prefixed) unit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnitReading {
+    /// The prefix, as written in the identifier (empty if there is none)
+    pub prefix: &'static str,
+    /// The long name of the prefix, e.g. 'milli' for 'm'
+    pub prefix_name: &'static str,
+    /// The name of the unit, as written in the identifier
+    pub unit_name: CompactString,
+    pub full_name: CompactString,
+    pub definition_span: Span,
+    /// Whether the unit accepts this prefix
+    pub valid: bool,
+}
+
+impl std::fmt::Display for UnitReading {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.prefix.is_empty() {
+            write!(f, "{}", self.full_name)
+        } else {
+            write!(f, "{}-{}", self.prefix_name, self.full_name)
+        }
+    }
+}
+
+impl UnitReading {
+    fn new(
+        prefix: &'static str,
+        prefix_name: &'static str,
+        unit_name: &str,
+        info: &UnitInfo,
+        valid: bool,
+    ) -> Self {
+        Self {
+            prefix,
+            prefix_name,
+            unit_name: unit_name.into(),
+            full_name: info.full_name.clone(),
+            definition_span: info.definition_span,
+            valid,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PrefixParser {
     units: IndexMap<CompactString, UnitInfo>,
 
     other_identifiers: HashMap<CompactString, Span>,
 
+    #[serde(skip, default = "PrefixParser::default_reserved_identifiers")]
     reserved_identifiers: &'static [&'static str],
 }
 
@@ -95,10 +141,14 @@ impl PrefixParser {
         Self {
             units: IndexMap::new(),
             other_identifiers: HashMap::new(),
-            reserved_identifiers: &["_", "ans"],
+            reserved_identifiers: Self::default_reserved_identifiers(),
         }
     }
 
+    fn default_reserved_identifiers() -> &'static [&'static str] {
+        &["_", "ans"]
+    }
+
     fn prefixes() -> &'static [(&'static str, &'static [&'static str], Prefix)] {
         PREFIXES.get_or_init(|| {
             vec![
@@ -246,6 +296,133 @@ impl PrefixParser {
         Ok(())
     }
 
+    /// All ways to read the given identifier as a (possibly prefixed) unit, including
+    /// readings that are not valid because the unit does not accept the prefix.
+    pub fn readings(&self, input: &str) -> Vec<UnitReading> {
+        let mut readings = vec![];
+
+        if let Some(info) = self.units.get(input) {
+            readings.push(UnitReading::new("", "", input, info, true));
+        }
+
+        for (split, _) in input.char_indices().skip(1) {
+            let (prefix_in_input, unit_name) = input.split_at(split);
+            let Some(info) = self.units.get(unit_name) else {
+                continue;
+            };
+
+            for (prefix_long, prefixes_short, prefix) in Self::prefixes() {
+                let valid = prefix.is_metric() && info.metric_prefixes
+                    || prefix.is_binary() && info.binary_prefixes;
+
+                if info.accepts_prefix.long && prefix_in_input == *prefix_long {
+                    readings.push(UnitReading::new(
+                        prefix_long,
+                        prefix_long,
+                        unit_name,
+                        info,
+                        valid,
+                    ));
+                }
+
+                if info.accepts_prefix.short {
+                    if let Some(prefix_short) =
+                        prefixes_short.iter().find(|p| prefix_in_input == **p)
+                    {
+                        readings.push(UnitReading::new(
+                            prefix_short,
+                            prefix_long,
+                            unit_name,
+                            info,
+                            valid,
+                        ));
+                    }
+                }
+            }
+        }
+
+        readings
+    }
+
+    /// Whether the identifier is parsed as a unit, but could also be read as (a prefix
+    /// and) a different unit.
+    fn is_ambiguous(readings: &[UnitReading]) -> bool {
+        readings.iter().any(|r| r.valid)
+            && readings
+                .iter()
+                .any(|r| r.full_name != readings[0].full_name)
+    }
+
+    /// Identifiers that are parsed as a unit, but could also be read as a prefix and
+    /// a different unit, e.g. 'min' (minute or milli-inch). The second reading is not
+    /// valid, since the other unit does not accept this prefix, but it might still be
+    /// what a user has in mind.
+    pub fn ambiguous_identifiers(&self) -> Vec<(CompactString, Vec<UnitReading>)> {
+        let mut identifiers = vec![];
+        for (unit_name, info) in &self.units {
+            identifiers.push(unit_name.clone());
+
+            for (prefix_long, prefixes_short, prefix) in Self::prefixes() {
+                if !(prefix.is_metric() && info.metric_prefixes
+                    || prefix.is_binary() && info.binary_prefixes)
+                {
+                    continue;
+                }
+                if info.accepts_prefix.long {
+                    identifiers.push(format_compact!("{prefix_long}{unit_name}"));
+                }
+                if info.accepts_prefix.short {
+                    for prefix_short in *prefixes_short {
+                        identifiers.push(format_compact!("{prefix_short}{unit_name}"));
+                    }
+                }
+            }
+        }
+
+        identifiers.sort();
+        identifiers.dedup();
+
+        identifiers
+            .into_iter()
+            .filter_map(|identifier| {
+                let readings = self.readings(&identifier);
+                Self::is_ambiguous(&readings).then_some((identifier, readings))
+            })
+            .collect()
+    }
+
+    /// Like [`PrefixParser::ambiguous_identifiers`], but only for the ambiguities that
+    /// involve the given unit name: the name itself could be read as a prefixed unit,
+    /// or an existing unit could be read as the given unit with a prefix.
+    pub(crate) fn ambiguous_identifiers_for(
+        &self,
+        unit_name: &str,
+    ) -> Vec<(CompactString, Vec<UnitReading>)> {
+        let mut identifiers = vec![unit_name.to_compact_string()];
+
+        if let Some(info) = self.units.get(unit_name) {
+            for (prefix_long, prefixes_short, _) in Self::prefixes() {
+                if info.accepts_prefix.long {
+                    identifiers.push(format_compact!("{prefix_long}{unit_name}"));
+                }
+                if info.accepts_prefix.short {
+                    for prefix_short in *prefixes_short {
+                        identifiers.push(format_compact!("{prefix_short}{unit_name}"));
+                    }
+                }
+            }
+        }
+
+        identifiers
+            .into_iter()
+            .filter(|identifier| identifier == unit_name || self.units.contains_key(identifier))
+            .filter_map(|identifier| {
+                let readings = self.readings(&identifier);
+                Self::is_ambiguous(&readings).then_some((identifier, readings))
+            })
+            .collect()
+    }
+
     pub fn parse<'a>(&self, input: &'a str) -> PrefixParserResult<'a> {
         if let Some(info) = self.units.get(input) {
             return PrefixParserResult::UnitIdentifier(
@@ -568,4 +745,58 @@ mod tests {
             PrefixParserResult::Identifier("Kim")
         );
     }
+
+    #[test]
+    fn ambiguous_identifiers() {
+        let mut prefix_parser = PrefixParser::new();
+        prefix_parser
+            .add_unit(
+                "in",
+                AcceptsPrefix::only_short(),
+                false,
+                false,
+                "inch",
+                AliasSpanInfo::dummy(),
+            )
+            .unwrap();
+        prefix_parser
+            .add_unit(
+                "min",
+                AcceptsPrefix::only_short(),
+                true,
+                false,
+                "minute",
+                AliasSpanInfo::dummy(),
+            )
+            .unwrap();
+        prefix_parser
+            .add_unit(
+                "m",
+                AcceptsPrefix::only_short(),
+                true,
+                false,
+                "meter",
+                AliasSpanInfo::dummy(),
+            )
+            .unwrap();
+
+        let readings = prefix_parser.readings("min");
+        assert_eq!(readings.len(), 2);
+        assert_eq!(readings[0].to_string(), "minute");
+        assert!(readings[0].valid);
+        assert_eq!(readings[1].to_string(), "milli-inch");
+        assert!(!readings[1].valid);
+
+        let readings = prefix_parser.readings("km");
+        assert_eq!(readings.len(), 1);
+        assert_eq!(readings[0].to_string(), "kilo-meter");
+        assert!(readings[0].valid);
+
+        let ambiguous = prefix_parser.ambiguous_identifiers();
+        assert_eq!(ambiguous.len(), 1);
+        assert_eq!(ambiguous[0].0, "min");
+
+        assert_eq!(prefix_parser.ambiguous_identifiers_for("in").len(), 1);
+        assert_eq!(prefix_parser.ambiguous_identifiers_for("m").len(), 0);
+    }
 }
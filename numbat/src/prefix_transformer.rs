@@ -1,4 +1,5 @@
 use compact_str::{CompactString, ToCompactString};
+use serde::{Deserialize, Serialize};
 
 use crate::{
     ast::{DefineVariable, Expression, Statement, StringPart},
@@ -6,11 +7,12 @@ use crate::{
     name_resolution::NameResolutionError,
     prefix_parser::{AliasSpanInfo, PrefixParser, PrefixParserResult},
     span::Span,
+    warning::Warning,
 };
 
 type Result<T> = std::result::Result<T, NameResolutionError>;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub(crate) struct Transformer {
     pub prefix_parser: PrefixParser,
 
@@ -18,6 +20,9 @@ pub(crate) struct Transformer {
     pub function_names: Vec<CompactString>,
     pub unit_names: Vec<Vec<CompactString>>,
     pub dimension_names: Vec<CompactString>,
+
+    #[serde(skip)]
+    warnings: Vec<Warning>,
 }
 
 impl Transformer {
@@ -28,9 +33,14 @@ impl Transformer {
             function_names: vec![],
             unit_names: vec![],
             dimension_names: vec![],
+            warnings: vec![],
         }
     }
 
+    pub(crate) fn take_warnings(&mut self) -> Vec<Warning> {
+        std::mem::take(&mut self.warnings)
+    }
+
     fn transform_expression(&self, expression: &mut Expression) {
         match expression {
             Expression::Scalar(..) | Expression::Boolean(_, _) | Expression::TypedHole(_) => {}
@@ -119,6 +129,7 @@ impl Transformer {
                 },
             )?;
             unit_names.push(alias.to_compact_string());
+            self.warn_about_ambiguities(name, alias, alias_span);
         }
 
         unit_names.sort();
@@ -127,6 +138,33 @@ impl Transformer {
         Ok(())
     }
 
+    /// Warn if the new unit name (or one of its prefixed forms) could also be read as
+    /// a prefix and a different unit, like 'min' (minute or milli-inch).
+    fn warn_about_ambiguities(&mut self, name: &str, alias: &str, alias_span: Span) {
+        for (identifier, readings) in self.prefix_parser.ambiguous_identifiers_for(alias) {
+            let Some(reading) = readings.iter().find(|r| r.valid) else {
+                continue;
+            };
+            let Some(alternative) = readings.iter().find(|r| r.full_name != reading.full_name)
+            else {
+                continue;
+            };
+            let other = if reading.full_name == name {
+                alternative
+            } else {
+                reading
+            };
+
+            self.warnings.push(Warning::AmbiguousUnit {
+                identifier,
+                reading: reading.to_compact_string(),
+                alternative: alternative.to_compact_string(),
+                span: alias_span,
+                other_span: other.definition_span,
+            });
+        }
+    }
+
     fn transform_define_variable(&mut self, define_variable: &mut DefineVariable) -> Result<()> {
         let DefineVariable {
             identifier_span,
@@ -212,6 +250,26 @@ impl Transformer {
                     self.transform_expression(arg);
                 }
             }
+            Statement::ForLoop {
+                loop_variable_span,
+                loop_variable,
+                list,
+                body,
+            } => {
+                self.transform_expression(list);
+
+                // Similar to function parameters, the loop variable and the definitions
+                // in the loop body are only registered in a clone of the transformer,
+                // as they are not visible after the loop.
+                let mut body_transformer = self.clone();
+                body_transformer
+                    .prefix_parser
+                    .add_other_identifier(loop_variable, *loop_variable_span)?;
+
+                for statement in body {
+                    body_transformer.transform_statement(statement)?;
+                }
+            }
         }
 
         Ok(())
@@ -6,6 +6,7 @@ use compact_str::{CompactString, ToCompactString};
 use itertools::Itertools;
 use num_rational::Ratio;
 use num_traits::Signed;
+use serde::{Deserialize, Serialize};
 
 pub trait Canonicalize {
     type MergeKey: PartialEq;
@@ -15,7 +16,7 @@ pub trait Canonicalize {
     fn is_trivial(&self) -> bool;
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Product<Factor, const CANONICALIZE: bool = false> {
     factors: Vec<Factor>,
 }
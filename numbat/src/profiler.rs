@@ -0,0 +1,205 @@
+//! An opt-in profiler for the bytecode VM. It counts the executed instructions
+//! and measures the wall time spent in each Numbat function (including foreign
+//! functions and procedures).
+
+use std::time::{Duration, Instant};
+
+use compact_str::{format_compact, CompactString};
+use indexmap::IndexMap;
+
+use crate::markup::{self as m, Markup};
+
+/// Statistics for a single function.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FunctionProfile {
+    pub name: CompactString,
+    pub calls: u64,
+    /// Number of bytecode instructions executed in this function itself
+    pub instructions: u64,
+    /// Time spent in this function, excluding calls to other functions
+    pub self_time: Duration,
+    /// Time spent in this function, including calls to other functions
+    pub total_time: Duration,
+}
+
+#[derive(Debug, Clone)]
+struct ActiveCall {
+    name: CompactString,
+    start: Instant,
+    children_time: Duration,
+    instructions: u64,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Profiler {
+    functions: IndexMap<CompactString, FunctionProfile>,
+
+    /// Self time for each call stack, keyed by the semicolon-separated names of the
+    /// functions on the stack.
+    folded_stacks: IndexMap<String, Duration>,
+
+    call_stack: Vec<ActiveCall>,
+}
+
+impl Profiler {
+    pub(crate) fn enter(&mut self, name: &str) {
+        self.call_stack.push(ActiveCall {
+            name: name.into(),
+            start: Instant::now(),
+            children_time: Duration::ZERO,
+            instructions: 0,
+        });
+    }
+
+    pub(crate) fn exit(&mut self) {
+        let Some(call) = self.call_stack.last() else {
+            return;
+        };
+
+        let total_time = call.start.elapsed();
+        let self_time = total_time.saturating_sub(call.children_time);
+
+        let stack = self
+            .call_stack
+            .iter()
+            .map(|c| c.name.as_str())
+            .collect::<Vec<_>>();
+        *self.folded_stacks.entry(stack.join(";")).or_default() += self_time;
+
+        let call = self.call_stack.pop().unwrap();
+
+        // For recursive functions, only the outermost call contributes to the total time
+        let is_recursive = self.call_stack.iter().any(|c| c.name == call.name);
+
+        let profile = self
+            .functions
+            .entry(call.name.clone())
+            .or_insert_with(|| FunctionProfile {
+                name: call.name,
+                calls: 0,
+                instructions: 0,
+                self_time: Duration::ZERO,
+                total_time: Duration::ZERO,
+            });
+        profile.calls += 1;
+        profile.instructions += call.instructions;
+        profile.self_time += self_time;
+        if !is_recursive {
+            profile.total_time += total_time;
+        }
+
+        if let Some(caller) = self.call_stack.last_mut() {
+            caller.children_time += total_time;
+        }
+    }
+
+    /// Finish all calls that are still active, e.g. after a runtime error.
+    pub(crate) fn exit_all(&mut self) {
+        while !self.call_stack.is_empty() {
+            self.exit();
+        }
+    }
+
+    pub(crate) fn count_instruction(&mut self) {
+        if let Some(call) = self.call_stack.last_mut() {
+            call.instructions += 1;
+        }
+    }
+
+    /// Statistics for all functions that have been called, sorted by self time
+    /// (in descending order).
+    pub fn functions(&self) -> Vec<&FunctionProfile> {
+        let mut functions = self.functions.values().collect::<Vec<_>>();
+        functions.sort_by_key(|f| std::cmp::Reverse(f.self_time));
+        functions
+    }
+
+    pub fn report(&self) -> Markup {
+        let functions = self.functions();
+
+        let name_width = functions
+            .iter()
+            .map(|f| f.name.chars().count())
+            .chain(std::iter::once("function".len()))
+            .max()
+            .unwrap_or(0);
+
+        let to_ms = |d: Duration| d.as_secs_f64() * 1000.0;
+
+        let mut markup = m::emphasized(format_compact!(
+            "{:<name_width$}  {:>8}  {:>12}  {:>12}  {:>12}",
+            "function",
+            "calls",
+            "instructions",
+            "self [ms]",
+            "total [ms]"
+        )) + m::nl();
+
+        for f in functions {
+            markup += m::identifier(format_compact!("{:<name_width$}", f.name))
+                + m::text(format_compact!(
+                    "  {:>8}  {:>12}  {:>12.3}  {:>12.3}",
+                    f.calls,
+                    f.instructions,
+                    to_ms(f.self_time),
+                    to_ms(f.total_time)
+                ))
+                + m::nl();
+        }
+
+        markup
+    }
+
+    /// Self times (in microseconds) per call stack, in the "folded stacks" format
+    /// that is understood by flamegraph tools.
+    pub fn folded_stacks(&self) -> String {
+        self.folded_stacks
+            .iter()
+            .map(|(stack, time)| format!("{stack} {}\n", time.as_micros()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::resolver::CodeSource;
+    use crate::Context;
+
+    #[test]
+    fn counts_calls_and_instructions() {
+        let mut ctx = Context::new_without_importer();
+        ctx.set_profiling(true);
+
+        let _ = ctx
+            .interpret(
+                "dimension Scalar = 1
+                 fn abs(x: Scalar) -> Scalar
+                 fn g(x) = abs(x) + 1
+                 fn f(x) = if x > 0 then f(x - 1) else g(x)
+                 f(3)",
+                CodeSource::Internal,
+            )
+            .unwrap();
+
+        let profiler = ctx.profiler().unwrap();
+        let calls = |name: &str| {
+            profiler
+                .functions()
+                .iter()
+                .find(|f| f.name == name)
+                .map(|f| (f.calls, f.instructions > 0))
+        };
+
+        assert_eq!(calls("<main>"), Some((1, true)));
+        assert_eq!(calls("f"), Some((4, true)));
+        assert_eq!(calls("g"), Some((1, true)));
+        assert_eq!(calls("abs"), Some((1, false)));
+
+        let folded_stacks = profiler.folded_stacks();
+        assert!(folded_stacks.contains("<main>;f;f;f;f;g;abs "));
+        assert!(folded_stacks.contains("<main>;f;f;f;f;g "));
+
+        ctx.set_profiling(false);
+        assert!(ctx.profiler().is_none());
+    }
+}
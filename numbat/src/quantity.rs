@@ -8,6 +8,7 @@ use itertools::Itertools;
 use num_rational::Ratio;
 use num_traits::{FromPrimitive, Zero};
 use pretty_dtoa::FmtFloatConfig;
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 #[derive(Clone, Debug, Error, PartialEq, Eq)]
@@ -21,7 +22,7 @@ pub enum QuantityError {
 
 pub type Result<T> = std::result::Result<T, QuantityError>;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Quantity {
     value: Number,
     unit: Unit,
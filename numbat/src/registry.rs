@@ -3,6 +3,7 @@ use std::{collections::HashMap, fmt::Display};
 use compact_str::{CompactString, ToCompactString};
 use itertools::Itertools;
 use num_traits::Zero;
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 use crate::{
@@ -25,7 +26,7 @@ pub type Result<T> = std::result::Result<T, RegistryError>;
 
 pub type BaseEntry = CompactString;
 
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub struct BaseRepresentationFactor(pub BaseEntry, pub Exponent);
 
 impl Display for BaseRepresentationFactor {
@@ -80,7 +81,7 @@ impl PrettyPrint for BaseRepresentation {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Registry<Metadata> {
     base_entries: Vec<(CompactString, Metadata)>,
     derived_entries: HashMap<CompactString, (BaseRepresentation, Metadata)>,
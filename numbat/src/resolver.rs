@@ -1,14 +1,20 @@
 use std::{collections::HashMap, path::PathBuf, sync::Arc};
 
 use crate::{
-    ast::Statement, module_importer::ModuleImporter, parser::parse, span::Span, ParseError,
+    ast::Statement,
+    module_importer::{ModuleImporter, NullImporter},
+    parser::parse_with_warnings,
+    span::Span,
+    warning::Warning,
+    ParseError,
 };
 
 use codespan_reporting::files::SimpleFiles;
 use compact_str::{CompactString, ToCompactString};
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub struct ModulePath(pub Vec<CompactString>);
 
 impl std::fmt::Display for ModulePath {
@@ -20,7 +26,7 @@ impl std::fmt::Display for ModulePath {
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub struct ModulePathBorrowed<'a>(pub Vec<&'a str>);
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum CodeSource {
     /// User input from the command line or a REPL
     Text,
@@ -47,14 +53,45 @@ pub enum ResolverError {
 
 type Result<T> = std::result::Result<T, ResolverError>;
 
-#[derive(Clone)]
+/// (De)serialization of the source files, as a list of names and contents.
+mod serde_files {
+    use codespan_reporting::files::SimpleFiles;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub(super) fn serialize<S: Serializer>(
+        files: &SimpleFiles<String, String>,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        let files: Vec<_> = (0..)
+            .map_while(|id| files.get(id).ok())
+            .map(|file| (file.name(), file.source()))
+            .collect();
+        serializer.collect_seq(files)
+    }
+
+    pub(super) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<SimpleFiles<String, String>, D::Error> {
+        let mut files = SimpleFiles::new();
+        for (name, source) in Vec::<(String, String)>::deserialize(deserializer)? {
+            files.add(name, source);
+        }
+        Ok(files)
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Resolver {
+    #[serde(skip, default = "Resolver::null_importer")]
     importer: Arc<dyn ModuleImporter>,
+    #[serde(with = "serde_files")]
     pub files: SimpleFiles<String, String>,
     text_code_source_count: usize,
     internal_code_source_count: usize,
     pub imported_modules: Vec<ModulePath>,
     codesources: HashMap<usize, CodeSource>,
+    #[serde(skip)]
+    warnings: Vec<Warning>,
 }
 
 impl Resolver {
@@ -66,9 +103,33 @@ impl Resolver {
             internal_code_source_count: 0,
             imported_modules: vec![],
             codesources: HashMap::new(),
+            warnings: vec![],
         }
     }
 
+    fn null_importer() -> Arc<dyn ModuleImporter> {
+        Arc::new(NullImporter::default())
+    }
+
+    pub(crate) fn set_importer(&mut self, importer: impl ModuleImporter + 'static) {
+        self.importer = Arc::new(importer);
+    }
+
+    /// Find a module whose source code differs from the source that the importer
+    /// currently provides (or that can not be imported anymore).
+    pub(crate) fn outdated_module(&self) -> Option<&ModulePath> {
+        self.codesources.iter().find_map(|(id, code_source)| {
+            let CodeSource::Module(module_path, _) = code_source else {
+                return None;
+            };
+            let source = self.files.get(*id).ok()?.source();
+            match self.importer.import(module_path) {
+                Some((code, _)) if &code == source => None,
+                _ => Some(module_path),
+            }
+        })
+    }
+
     pub fn add_code_source(&mut self, code_source: CodeSource, content: &str) -> usize {
         let code_source_name = match &code_source {
             CodeSource::Text => {
@@ -96,12 +157,24 @@ impl Resolver {
         id
     }
 
+    /// The ID that will be assigned to the next code source that is added
+    pub fn next_code_source_id(&self) -> usize {
+        self.codesources.len()
+    }
+
     pub fn get_code_source(&self, id: usize) -> CodeSource {
         self.codesources.get(&id).cloned().unwrap()
     }
 
-    fn parse<'a>(&self, code: &'a str, code_source_id: usize) -> Result<Vec<Statement<'a>>> {
-        parse(code, code_source_id).map_err(|e| ResolverError::ParseErrors(e.1))
+    fn parse<'a>(&mut self, code: &'a str, code_source_id: usize) -> Result<Vec<Statement<'a>>> {
+        let (result, warnings) = parse_with_warnings(code, code_source_id);
+        self.warnings.extend(warnings);
+        result.map_err(|e| ResolverError::ParseErrors(e.1))
+    }
+
+    /// Take the warnings that were found while parsing.
+    pub(crate) fn take_warnings(&mut self) -> Vec<Warning> {
+        std::mem::take(&mut self.warnings)
     }
 
     fn inlining_pass<'a>(&mut self, program: &[Statement<'a>]) -> Result<Vec<Statement<'a>>> {
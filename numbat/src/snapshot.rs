@@ -0,0 +1,108 @@
+//! Snapshots of the complete state of a [`Context`](crate::Context).
+//!
+//! Loading the prelude requires parsing, type checking and compiling dozens of
+//! modules. A snapshot of a context after the prelude has been loaded can be
+//! stored (e.g. embedded into a binary or cached on disk) and restored with
+//! [`Context::from_snapshot`](crate::Context::from_snapshot), which is much
+//! faster than loading the prelude again.
+
+use thiserror::Error;
+
+use crate::resolver::ModulePath;
+
+/// Identifies snapshots. Snapshots are only compatible with the exact same
+/// version of Numbat that created them.
+const HEADER: &str = concat!("numbat-snapshot ", env!("CARGO_PKG_VERSION"), "\n");
+
+#[derive(Debug, Error)]
+pub enum SnapshotError {
+    #[error("The snapshot was created by a different version of Numbat.")]
+    IncompatibleVersion,
+
+    #[error("The snapshot could not be decoded: {0}")]
+    Decoding(#[from] bincode::Error),
+
+    #[error("The source of module '{0}' has changed since the snapshot was created.")]
+    OutdatedModule(ModulePath),
+}
+
+pub(crate) fn encode<T: serde::Serialize>(value: &T) -> Vec<u8> {
+    let mut bytes = HEADER.as_bytes().to_vec();
+    bincode::serialize_into(&mut bytes, value).expect("Serialization of snapshots never fails");
+    bytes
+}
+
+pub(crate) fn decode<T: serde::de::DeserializeOwned>(snapshot: &[u8]) -> Result<T, SnapshotError> {
+    let payload = snapshot
+        .strip_prefix(HEADER.as_bytes())
+        .ok_or(SnapshotError::IncompatibleVersion)?;
+    Ok(bincode::deserialize(payload)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::module_importer::{BuiltinModuleImporter, NullImporter};
+    use crate::resolver::CodeSource;
+    use crate::{Context, InterpreterResult};
+
+    fn evaluate(ctx: &mut Context, code: &str) -> String {
+        match ctx.interpret(code, CodeSource::Text).unwrap().1 {
+            InterpreterResult::Value(value) => value.to_string(),
+            InterpreterResult::Continue => String::new(),
+        }
+    }
+
+    #[test]
+    fn restored_context_behaves_like_original() {
+        let mut ctx = Context::new(BuiltinModuleImporter::default());
+        let _ = ctx.interpret("use prelude", CodeSource::Internal).unwrap();
+        evaluate(&mut ctx, "let speed = 30 km/h");
+
+        let snapshot = ctx.snapshot();
+        let mut restored =
+            Context::from_snapshot(&snapshot, BuiltinModuleImporter::default()).unwrap();
+
+        for code in [
+            "speed -> m/s",
+            "sqrt(2 m²) + 3 cm",
+            "fn f(x: Length) -> Area = x² \n f(2 ft) -> cm²",
+            "use numerics::solve \n fn poly(x) = x² - 2 \n root_bisect(poly, 0, 2, 1e-10, 1e-10)",
+        ] {
+            assert_eq!(evaluate(&mut restored, code), evaluate(&mut ctx, code));
+        }
+
+        assert!(restored.interpret("2 m + 3 s", CodeSource::Text).is_err());
+    }
+
+    #[test]
+    fn invalid_snapshots() {
+        let ctx = Context::new_without_importer();
+        let snapshot = ctx.snapshot();
+
+        let mut other_version = snapshot.clone();
+        other_version[HEADER.len() - 2] ^= 1;
+        assert!(matches!(
+            Context::from_snapshot(&other_version, NullImporter::default()),
+            Err(SnapshotError::IncompatibleVersion)
+        ));
+
+        assert!(matches!(
+            Context::from_snapshot(&snapshot[..snapshot.len() / 2], NullImporter::default()),
+            Err(SnapshotError::Decoding(_))
+        ));
+    }
+
+    #[test]
+    fn outdated_modules() {
+        let mut ctx = Context::new(BuiltinModuleImporter::default());
+        let _ = ctx
+            .interpret("use core::scalar", CodeSource::Internal)
+            .unwrap();
+
+        assert!(matches!(
+            Context::from_snapshot(&ctx.snapshot(), NullImporter::default()),
+            Err(SnapshotError::OutdatedModule(ModulePath(path))) if path == ["core", "scalar"]
+        ));
+    }
+}
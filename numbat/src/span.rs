@@ -1,7 +1,8 @@
 use codespan_reporting::diagnostic::{Label, LabelStyle};
+use serde::{Deserialize, Serialize};
 use std::ops::{Add, AddAssign};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub struct ByteIndex(pub u32);
 
 impl From<u32> for ByteIndex {
@@ -41,7 +42,7 @@ impl ByteIndex {
 /// The span of text from `start` to `end`, associated with `code_source_id`. `start`
 /// and `end` are both inclusive byte indices (so that if `start == end` we get a
 /// one-byte span).
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Span {
     pub start: ByteIndex,
     pub end: ByteIndex,
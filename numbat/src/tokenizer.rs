@@ -92,6 +92,7 @@ pub enum TokenKind {
     Unit,
     Use,
     Struct,
+    For,
 
     Long,
     Short,
@@ -402,6 +403,7 @@ impl Tokenizer {
             m.insert("unit", TokenKind::Unit);
             m.insert("use", TokenKind::Use);
             m.insert("struct", TokenKind::Struct);
+            m.insert("for", TokenKind::For);
             m.insert("long", TokenKind::Long);
             m.insert("short", TokenKind::Short);
             m.insert("both", TokenKind::Both);
@@ -765,7 +767,7 @@ impl Tokenizer {
     }
 }
 
-pub fn tokenize(input: &str, code_source_id: usize) -> Result<Vec<Token>> {
+pub fn tokenize(input: &str, code_source_id: usize) -> Result<Vec<Token<'_>>> {
     let mut tokenizer = Tokenizer::new(code_source_id);
     tokenizer.scan(input)
 }
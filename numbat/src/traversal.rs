@@ -107,6 +107,12 @@ impl ForAllTypeSchemes for Statement<'_> {
                 }
             }
             Statement::DefineStruct(info) => info.for_all_type_schemes(f),
+            Statement::ForLoop(_, list, body) => {
+                list.for_all_type_schemes(f);
+                for statement in body {
+                    statement.for_all_type_schemes(f);
+                }
+            }
         }
     }
 }
@@ -139,6 +145,12 @@ impl ForAllExpressions for Statement<'_> {
                 }
             }
             Statement::DefineStruct(_) => {}
+            Statement::ForLoop(_, list, body) => {
+                list.for_all_expressions(f);
+                for statement in body {
+                    statement.for_all_expressions(f);
+                }
+            }
         }
     }
 }
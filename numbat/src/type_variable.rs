@@ -1,6 +1,7 @@
 use compact_str::CompactString;
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum TypeVariable {
     Named(CompactString),
     Quantified(usize),
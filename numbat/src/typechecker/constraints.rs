@@ -1,6 +1,7 @@
 use std::sync::Arc;
 
 use compact_str::{format_compact, CompactString};
+use serde::{Deserialize, Serialize};
 
 use super::substitutions::{ApplySubstitution, Substitution, SubstitutionError};
 use crate::type_variable::TypeVariable;
@@ -48,7 +49,7 @@ impl Satisfied {
     }
 }
 
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ConstraintSet {
     constraints: Vec<Constraint>,
 }
@@ -186,7 +187,7 @@ impl TrivialResolution {
 /// - A unification constraint `Type1 ~ Type2` which constrains two types to be equal
 /// - A 'type class' constraint `Type: DType` which constrains `Type` to be a dimension type (like `Scalar`, `Length`, or `Length × Mass / Time²`).
 /// - A constraint `DType ~ Scalar` which constrains a dimension type to be dimensionless.
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum Constraint {
     Equal(Type, Type),
     IsDType(Type),
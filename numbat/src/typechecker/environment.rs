@@ -1,4 +1,5 @@
 use compact_str::CompactString;
+use serde::{Deserialize, Serialize};
 
 use crate::ast::{TypeAnnotation, TypeParameterBound};
 use crate::dimension::DimensionRegistry;
@@ -14,7 +15,7 @@ use super::type_scheme::TypeScheme;
 
 type Identifier = CompactString;
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct FunctionSignature {
     pub name: CompactString,
     pub definition_span: Span,
@@ -64,19 +65,24 @@ impl FunctionSignature {
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct FunctionMetadata {
     pub name: Option<CompactString>,
     pub url: Option<CompactString>,
     pub description: Option<CompactString>,
     pub examples: Vec<(CompactString, Option<CompactString>)>,
+    /// Whether the function is decorated with `@test`
+    pub is_test: bool,
+    /// The message of the `@deprecated` decorator
+    pub deprecated: Option<CompactString>,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[allow(clippy::large_enum_variant)]
 pub enum IdentifierKind {
     /// A normal identifier (variable, unit) with the place where it has been defined.
     /// The boolean flag signifies whether the identifier is a unit or not
-    Normal(TypeScheme, #[allow(dead_code)] Span, bool),
+    Normal(TypeScheme, Span, bool),
     /// A function
     Function(FunctionSignature, FunctionMetadata),
     /// Identifiers that are defined by the language: `_` and `ans` (see LAST_RESULT_IDENTIFIERS)
@@ -93,7 +99,7 @@ impl IdentifierKind {
     }
 }
 
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct Environment {
     identifiers: MapStack<Identifier, IdentifierKind>,
 }
@@ -119,6 +125,10 @@ impl Environment {
         self.identifiers.restore();
     }
 
+    pub(crate) fn restore_all(&mut self) {
+        self.identifiers.restore_all();
+    }
+
     pub(crate) fn add_function(
         &mut self,
         v: CompactString,
@@ -138,6 +148,14 @@ impl Environment {
         self.find(v).map(|k| k.get_type())
     }
 
+    pub(crate) fn get_definition_span(&self, v: &str) -> Option<Span> {
+        match self.find(v)? {
+            IdentifierKind::Normal(_, span, _) => Some(*span),
+            IdentifierKind::Function(signature, _) => Some(signature.definition_span),
+            IdentifierKind::Predefined(_) => None,
+        }
+    }
+
     pub(crate) fn iter_identifiers(&self) -> impl Iterator<Item = &Identifier> {
         self.identifiers.keys()
     }
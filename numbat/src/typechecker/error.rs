@@ -72,6 +72,9 @@ pub enum TypeCheckError {
     #[error("Expected boolean value")]
     ExpectedBool(Span),
 
+    #[error("Expected a list to loop over, got {1} instead")]
+    ExpectedListInForLoop(Span, Type),
+
     #[error("Incompatible types in condition")]
     IncompatibleTypesInCondition(Span, Type, Span, Type, Span),
 
@@ -155,6 +158,19 @@ pub enum TypeCheckError {
 
     #[error("Multiple typed holes in statement")]
     MultipleTypedHoles(Span),
+
+    #[error("{}", .0.iter().map(|e| e.to_string()).collect::<Vec<_>>().join("\n"))]
+    MultipleErrors(Vec<TypeCheckError>),
+}
+
+impl TypeCheckError {
+    /// The individual errors, which are more than one for [`TypeCheckError::MultipleErrors`].
+    pub fn errors(&self) -> &[TypeCheckError] {
+        match self {
+            TypeCheckError::MultipleErrors(errors) => errors,
+            error => std::slice::from_ref(error),
+        }
+    }
 }
 
 pub type Result<T> = std::result::Result<T, Box<TypeCheckError>>;
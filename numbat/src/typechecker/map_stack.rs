@@ -1,6 +1,8 @@
 use std::hash::Hash;
 use std::{borrow::Borrow, collections::HashMap};
 
+use serde::{Deserialize, Serialize};
+
 /// A stack of hash maps. All insertions affect the hash map at the top of the
 /// stack (which is the last element of the `stack` vector), preserving any
 /// entries in maps below. The `save` function can be used to push a new map on
@@ -8,7 +10,11 @@ use std::{borrow::Borrow, collections::HashMap};
 /// one can then restore with `restore`.
 ///
 /// The stack vector should never be empty
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(bound(
+    serialize = "K: Serialize + Hash + Eq, V: Serialize",
+    deserialize = "K: Deserialize<'de> + Hash + Eq, V: Deserialize<'de>"
+))]
 pub(crate) struct MapStack<K, V> {
     stack: Vec<HashMap<K, V>>,
 }
@@ -74,6 +80,11 @@ impl<K: Hash + Eq, V> MapStack<K, V> {
         );
     }
 
+    /// Drop all saved states, keeping the entries at the bottom of the stack.
+    pub(crate) fn restore_all(&mut self) {
+        self.stack.truncate(1);
+    }
+
     /// Save the current state of the map by making the top of the stack a
     /// new empty map.
     pub(crate) fn save(&mut self) {
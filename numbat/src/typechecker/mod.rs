@@ -12,7 +12,7 @@ pub mod qualified_type;
 mod substitutions;
 pub mod type_scheme;
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::ops::Deref;
 use std::sync::Arc;
 
@@ -21,6 +21,7 @@ use crate::ast::{
     self, BinaryOperator, DefineVariable, ProcedureKind, StringPart, TypeAnnotation,
     TypeExpression, TypeParameterBound,
 };
+use crate::decorator::{self, Decorator};
 use crate::dimension::DimensionRegistry;
 use crate::name_resolution::Namespace;
 use crate::name_resolution::LAST_RESULT_IDENTIFIERS;
@@ -28,7 +29,8 @@ use crate::pretty_print::PrettyPrint;
 use crate::span::Span;
 use crate::type_variable::TypeVariable;
 use crate::typed_ast::{self, DType, DTypeFactor, Expression, StructInfo, Type};
-use crate::{decorator, ffi, suggestion};
+use crate::warning::Warning;
+use crate::{ffi, suggestion};
 
 use compact_str::{format_compact, CompactString, ToCompactString};
 use const_evaluation::evaluate_const_expr;
@@ -37,10 +39,11 @@ use environment::{Environment, FunctionMetadata, FunctionSignature};
 use itertools::Itertools;
 use name_generator::NameGenerator;
 use num_traits::Zero;
+use serde::{Deserialize, Serialize};
 
 pub use error::{Result, TypeCheckError};
 pub use incompatible_dimensions::IncompatibleDimensionsError;
-use qualified_type::Bound;
+use qualified_type::{Bound, Bounds, QualifiedType};
 use substitutions::{ApplySubstitution, Substitution};
 use type_scheme::TypeScheme;
 
@@ -179,7 +182,7 @@ fn proper_function_call<'a>(
     ))
 }
 
-#[derive(Clone, Default)]
+#[derive(Clone, Default, Serialize, Deserialize)]
 pub struct TypeChecker {
     structs: HashMap<CompactString, StructInfo>,
     registry: DimensionRegistry,
@@ -190,6 +193,26 @@ pub struct TypeChecker {
 
     name_generator: NameGenerator,
     constraints: ConstraintSet,
+
+    /// Messages of `@deprecated` units, functions and constants, by the span of
+    /// their definition
+    deprecations: HashMap<Span, CompactString>,
+
+    /// Names of native functions that have been registered by the embedding application
+    #[serde(skip)]
+    native_functions: HashSet<CompactString>,
+
+    /// Names of dimensions and structs whose definitions could not be type checked
+    #[serde(skip)]
+    error_types: HashSet<CompactString>,
+
+    /// Type variables for the types of expressions that could not be type checked,
+    /// see [`TypeChecker::error_type`].
+    #[serde(skip)]
+    error_type_variables: HashSet<TypeVariable>,
+
+    #[serde(skip)]
+    warnings: Vec<Warning>,
 }
 
 struct ElaborationDefinitionArgs<'a, 'b> {
@@ -209,6 +232,36 @@ impl TypeChecker {
         Type::TVar(self.name_generator.fresh_type_variable())
     }
 
+    /// The type of identifiers whose definitions could not be type checked, which is
+    /// compatible with every use.
+    fn error_type_scheme() -> TypeScheme {
+        TypeScheme::quantified(
+            1,
+            QualifiedType::new(Type::TVar(TypeVariable::Quantified(0)), Bounds::none()),
+        )
+    }
+
+    /// A fresh type variable for something whose definition could not be type checked.
+    /// Fields of such values can be accessed without constraints.
+    fn error_type(&mut self) -> Type {
+        let type_variable = self.name_generator.fresh_type_variable();
+        self.error_type_variables.insert(type_variable.clone());
+        Type::TVar(type_variable)
+    }
+
+    /// Whether the given type expression refers to a dimension or struct whose
+    /// definition could not be type checked.
+    fn mentions_error_type(&self, dexpr: &TypeExpression) -> bool {
+        match dexpr {
+            TypeExpression::Unity(_) => false,
+            TypeExpression::TypeIdentifier(_, name) => self.error_types.contains(name),
+            TypeExpression::Multiply(_, lhs, rhs) | TypeExpression::Divide(_, lhs, rhs) => {
+                self.mentions_error_type(lhs) || self.mentions_error_type(rhs)
+            }
+            TypeExpression::Power(_, base, _, _) => self.mentions_error_type(base),
+        }
+    }
+
     fn add_equal_constraint(&mut self, lhs: &Type, rhs: &Type) -> TrivialResolution {
         self.constraints.add_equal_constraint(lhs, rhs)
     }
@@ -232,9 +285,13 @@ impl TypeChecker {
         Ok(())
     }
 
-    fn type_from_annotation(&self, annotation: &TypeAnnotation) -> Result<Type> {
+    fn type_from_annotation(&mut self, annotation: &TypeAnnotation) -> Result<Type> {
         match annotation {
             TypeAnnotation::TypeExpression(dexpr) => {
+                if self.mentions_error_type(dexpr) {
+                    return Ok(self.error_type());
+                }
+
                 if let TypeExpression::TypeIdentifier(_, name) = dexpr {
                     if let Some(info) = self.structs.get(name) {
                         // if we see a struct name here, it's safe to assume it
@@ -292,13 +349,35 @@ impl TypeChecker {
                     .iter_identifiers()
                     .map(|k| k.as_str())
                     .chain(["true", "false"]) // These are parsed as keywords, but can act like identifiers
-                    .chain(ffi::procedures().values().map(|p| p.name)),
+                    .chain(ffi::procedures().values().map(|p| p.name.as_str())),
                 name,
             );
             TypeCheckError::UnknownIdentifier(span, name.into(), suggestion)
         })?)
     }
 
+    /// Warn if the identifier refers to a definition that is marked as `@deprecated`.
+    fn warn_if_deprecated(&mut self, span: Span, name: &str) {
+        let Some(definition_span) = self.env.get_definition_span(name) else {
+            return;
+        };
+        if let Some(message) = self.deprecations.get(&definition_span) {
+            self.warnings.push(Warning::Deprecated {
+                name: name.to_compact_string(),
+                message: message.clone(),
+                span,
+                definition_span,
+            });
+        }
+    }
+
+    fn add_deprecation(&mut self, definition_span: Span, decorators: &[Decorator]) {
+        if let Some(message) = decorator::deprecated(decorators) {
+            self.deprecations
+                .insert(definition_span, message.to_compact_string());
+        }
+    }
+
     fn elaborate_expression<'a>(
         &mut self,
         ast: &ast::Expression<'a>,
@@ -320,9 +399,11 @@ impl TypeChecker {
             }
             ast::Expression::Identifier(span, name) => {
                 let type_scheme = self.identifier_type(*span, name)?.clone();
+                self.warn_if_deprecated(*span, name);
 
                 let ty = match type_scheme {
                     TypeScheme::Concrete(ty) => ty,
+                    _ if type_scheme == Self::error_type_scheme() => self.error_type(),
                     TypeScheme::Quantified(_, _) => {
                         let qt = type_scheme.instantiate(&mut self.name_generator);
 
@@ -337,6 +418,7 @@ impl TypeChecker {
             }
             ast::Expression::UnitIdentifier(span, prefix, name, full_name) => {
                 let type_scheme = self.identifier_type(*span, name)?.clone();
+                self.warn_if_deprecated(*span, name);
 
                 let qt = type_scheme.instantiate(&mut self.name_generator);
 
@@ -647,6 +729,11 @@ impl TypeChecker {
                                     // Skip evaluating the exponent if the lhs is a scalar. This allows
                                     // for arbitrary (decimal) exponents, if the base is a scalar.
 
+                                    if !type_exponent_inferred.is_closed() {
+                                        self.warnings
+                                            .push(Warning::ImplicitScalarExponent(rhs.full_span()));
+                                    }
+
                                     if self
                                         .add_equal_constraint(
                                             &type_exponent_inferred,
@@ -781,6 +868,12 @@ impl TypeChecker {
                 // to a (proper) function, or it can be an arbitrary complicated expression
                 // that evaluates to a function "pointer".
 
+                if let ast::Expression::Identifier(name_span, name) = callable.as_ref() {
+                    if self.env.get_proper_function_reference(callable).is_some() {
+                        self.warn_if_deprecated(*name_span, name);
+                    }
+                }
+
                 if let Some((function_name, signature)) =
                     self.env.get_proper_function_reference(callable)
                 {
@@ -946,6 +1039,27 @@ impl TypeChecker {
                     .map(|(_, n, v)| Ok((*n, self.elaborate_expression(v)?)))
                     .collect::<Result<Vec<_>>>()?;
 
+                if self.error_types.contains(name) {
+                    // The definition of this struct could not be type checked. We assume
+                    // that it has exactly the given fields to avoid follow-up errors.
+                    let struct_info = StructInfo {
+                        definition_span: *ident_span,
+                        name: name.to_compact_string(),
+                        fields: fields
+                            .iter()
+                            .zip(&fields_checked)
+                            .map(|((span, field, _), (_, expr))| {
+                                (field.to_compact_string(), (*span, expr.get_type()))
+                            })
+                            .collect(),
+                    };
+                    return Ok(typed_ast::Expression::InstantiateStruct(
+                        *full_span,
+                        fields_checked,
+                        struct_info,
+                    ));
+                }
+
                 let Some(struct_info) = self.structs.get(name).cloned() else {
                     return Err(Box::new(TypeCheckError::UnknownStruct(
                         *ident_span,
@@ -1042,6 +1156,9 @@ impl TypeChecker {
                     };
 
                     field_type.clone()
+                } else if matches!(&type_, Type::TVar(v) if self.error_type_variables.contains(v)) {
+                    // Fields of values that could not be type checked can have any type
+                    self.error_type()
                 } else {
                     let field_type = self.fresh_type_variable();
 
@@ -1211,7 +1328,23 @@ impl TypeChecker {
             elaboration_kind: "definition",
         })?;
 
+        self.add_deprecation(*identifier_span, decorators);
+
         for (name, _) in decorator::name_and_aliases(identifier, decorators) {
+            if self.value_namespace.get_item_type(name) == Some("constant") {
+                if let Some(original_span) = self
+                    .value_namespace
+                    .get_definition_span(name)
+                    .filter(|span| span != identifier_span)
+                {
+                    self.warnings.push(Warning::Redefinition {
+                        name: name.to_compact_string(),
+                        span: *identifier_span,
+                        original_span,
+                    });
+                }
+            }
+
             self.env.add(
                 name.to_compact_string(),
                 type_deduced.clone(),
@@ -1284,6 +1417,7 @@ impl TypeChecker {
                         .map_err(TypeCheckError::RegistryError)?
                         .into()
                 };
+                self.add_deprecation(*span, decorators);
                 for (name, _) in decorator::name_and_aliases(unit_name, decorators) {
                     self.env.add(
                         name.to_compact_string(),
@@ -1321,6 +1455,7 @@ impl TypeChecker {
                         elaboration_kind: "unit definition",
                     })?;
 
+                self.add_deprecation(*identifier_span, decorators);
                 for (name, _) in decorator::name_and_aliases(identifier, decorators) {
                     self.env.add(
                         name.to_compact_string(),
@@ -1466,6 +1601,7 @@ impl TypeChecker {
                 let fn_type =
                     TypeScheme::Concrete(Type::Fn(parameter_types, Box::new(return_type.clone())));
 
+                self.add_deprecation(*function_name_span, decorators);
                 self.env.add_function(
                     function_name.to_compact_string(),
                     FunctionSignature {
@@ -1489,6 +1625,9 @@ impl TypeChecker {
                         url: crate::decorator::url(decorators).map(CompactString::from),
                         description: crate::decorator::description(decorators),
                         examples: crate::decorator::examples(decorators),
+                        is_test: crate::decorator::contains_test(decorators),
+                        deprecated: crate::decorator::deprecated(decorators)
+                            .map(CompactString::from),
                     },
                 );
 
@@ -1562,7 +1701,9 @@ impl TypeChecker {
                     }
                     return_type_inferred
                 } else {
-                    if !ffi::functions().contains_key(*function_name) {
+                    if !ffi::functions().contains_key(*function_name)
+                        && !self.native_functions.contains(*function_name)
+                    {
                         return Err(Box::new(TypeCheckError::UnknownForeignFunction(
                             *function_name_span,
                             function_name.to_string(),
@@ -1630,6 +1771,13 @@ impl TypeChecker {
                     )
                     .map_err(|err| Box::new(err.into()))?;
 
+                // A dimension that is defined in terms of a dimension which could not be
+                // defined can not be defined either, but this is not reported again.
+                if dexprs.iter().any(|dexpr| self.mentions_error_type(dexpr)) {
+                    self.error_types.insert(name.to_compact_string());
+                    return Ok(typed_ast::Statement::DefineDimension(name, dexprs.clone()));
+                }
+
                 if let Some(dexpr) = dexprs.first() {
                     self.registry
                         .add_derived_dimension(name, dexpr)
@@ -1687,7 +1835,7 @@ impl TypeChecker {
                 if !procedure.arity.contains(&args.len()) {
                     return Err(Box::new(TypeCheckError::WrongArity {
                         callable_span: *span,
-                        callable_name: procedure.name.to_owned(),
+                        callable_name: procedure.name.to_string(),
                         callable_definition_span: None,
                         arity: procedure.arity.clone(),
                         num_args: args.len(),
@@ -1799,6 +1947,54 @@ impl TypeChecker {
 
                 typed_ast::Statement::DefineStruct(struct_info)
             }
+            ast::Statement::ForLoop {
+                loop_variable_span,
+                loop_variable,
+                list,
+                body,
+            } => {
+                let list_checked = self.elaborate_expression(list)?;
+                let list_type = list_checked.get_type();
+
+                let element_type = if let Type::List(element_type) = &list_type {
+                    *element_type.clone()
+                } else if list_type.is_closed() {
+                    return Err(Box::new(TypeCheckError::ExpectedListInForLoop(
+                        list_checked.full_span(),
+                        list_type,
+                    )));
+                } else {
+                    let element_type = self.fresh_type_variable();
+                    self.add_equal_constraint(
+                        &list_type,
+                        &Type::List(Box::new(element_type.clone())),
+                    )
+                    .ok();
+                    element_type
+                };
+
+                // The loop variable and the definitions in the loop body are not
+                // visible after the loop
+                self.env.save();
+                self.value_namespace.save();
+
+                self.env.add(
+                    loop_variable.to_compact_string(),
+                    element_type,
+                    *loop_variable_span,
+                    false,
+                );
+
+                let body_checked = body
+                    .iter()
+                    .map(|statement| self.elaborate_statement(statement))
+                    .collect::<Result<Vec<_>>>()?;
+
+                self.value_namespace.restore();
+                self.env.restore();
+
+                typed_ast::Statement::ForLoop(loop_variable, list_checked, body_checked)
+            }
         })
     }
 
@@ -1916,24 +2112,119 @@ impl TypeChecker {
         Ok(elaborated_statement)
     }
 
+    /// Take the warnings that were found since the last call.
+    pub(crate) fn take_warnings(&mut self) -> Vec<Warning> {
+        std::mem::take(&mut self.warnings)
+    }
+
+    /// Type check the given statements. If a statement can not be type checked,
+    /// the remaining statements are still checked, and all errors are reported.
     pub fn check<'a>(
         &mut self,
         statements: &[ast::Statement<'a>],
     ) -> Result<Vec<typed_ast::Statement<'a>>> {
         let mut checked_statements = vec![];
+        let mut errors = vec![];
+        self.error_types.clear();
+        self.error_type_variables.clear();
 
         for statement in statements {
-            checked_statements.push(self.check_statement(statement)?);
+            match self.check_statement(statement) {
+                Ok(checked_statement) => checked_statements.push(checked_statement),
+                Err(error) => {
+                    self.recover_from_error(statement);
+                    errors.push(*error);
+                }
+            }
         }
 
-        Ok(checked_statements)
+        match errors.len() {
+            0 => Ok(checked_statements),
+            1 => Err(Box::new(errors.remove(0))),
+            _ => Err(Box::new(TypeCheckError::MultipleErrors(errors))),
+        }
+    }
+
+    /// Bring the type checker back into a consistent state after a statement could
+    /// not be type checked. The identifiers that the statement would have defined are
+    /// given an error type (`forall A. A`), which is compatible with every use. Dimensions
+    /// and structs that could not be defined are recorded as error types. This way, later
+    /// statements can be checked without follow-up errors.
+    fn recover_from_error(&mut self, statement: &ast::Statement) {
+        self.env.restore_all();
+        self.type_namespace.restore_all();
+        self.value_namespace.restore_all();
+        self.constraints.clear();
+        self.registry.introduced_type_parameters.clear();
+
+        let (name, span, decorators, is_unit) = match statement {
+            ast::Statement::DefineVariable(define_variable) => (
+                define_variable.identifier,
+                define_variable.identifier_span,
+                define_variable.decorators.as_slice(),
+                false,
+            ),
+            ast::Statement::DefineFunction {
+                function_name_span,
+                function_name,
+                ..
+            } => (*function_name, *function_name_span, [].as_slice(), false),
+            ast::Statement::DefineBaseUnit(span, name, _, decorators) => {
+                (*name, *span, decorators.as_slice(), true)
+            }
+            ast::Statement::DefineDerivedUnit {
+                identifier_span,
+                identifier,
+                decorators,
+                ..
+            } => (*identifier, *identifier_span, decorators.as_slice(), true),
+            ast::Statement::DefineDimension(_, name, _)
+            | ast::Statement::DefineStruct {
+                struct_name: name, ..
+            } => {
+                self.error_types.insert(name.to_compact_string());
+                return;
+            }
+            _ => return,
+        };
+
+        for (name, _) in decorator::name_and_aliases(name, decorators) {
+            self.env.add_scheme(
+                name.to_compact_string(),
+                Self::error_type_scheme(),
+                span,
+                is_unit,
+            );
+        }
     }
 
     pub(crate) fn registry(&self) -> &DimensionRegistry {
         &self.registry
     }
 
+    /// Allow declarations of a foreign function that is provided by the embedding application.
+    pub(crate) fn register_native_function(&mut self, name: &str) {
+        self.native_functions.insert(name.into());
+    }
+
+    pub(crate) fn unregister_native_function(&mut self, name: &str) {
+        self.native_functions.remove(name);
+    }
+
     pub fn lookup_function(&self, name: &str) -> Option<(&FunctionSignature, &FunctionMetadata)> {
         self.env.get_function_info(name)
     }
+
+    /// Where the given value (variable, unit, function) or type (dimension, struct)
+    /// has been defined.
+    pub fn lookup_definition_span(&self, name: &str) -> Option<Span> {
+        self.env
+            .get_definition_span(name)
+            .or_else(|| self.type_namespace.get_definition_span(name))
+    }
+
+    /// The message of the `@deprecated` decorator of the definition at the given span.
+    pub fn lookup_deprecation(&self, definition_span: Span) -> Option<&CompactString> {
+        self.deprecations.get(&definition_span)
+    }
 }
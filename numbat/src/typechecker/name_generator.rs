@@ -1,6 +1,8 @@
+use serde::{Deserialize, Serialize};
+
 use crate::type_variable::TypeVariable;
 
-#[derive(Clone, Default)]
+#[derive(Clone, Default, Serialize, Deserialize)]
 pub struct NameGenerator {
     counter: u64,
 }
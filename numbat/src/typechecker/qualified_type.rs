@@ -1,3 +1,5 @@
+use serde::{Deserialize, Serialize};
+
 use crate::{type_variable::TypeVariable, Type};
 
 use super::{
@@ -6,12 +8,12 @@ use super::{
 };
 
 /// A predicate on type variables.
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Bound {
     IsDim(Type),
 }
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Bounds(Vec<Bound>);
 
 impl Bounds {
@@ -23,11 +25,11 @@ impl Bounds {
         self.0.is_empty()
     }
 
-    pub fn iter(&self) -> std::slice::Iter<Bound> {
+    pub fn iter(&self) -> std::slice::Iter<'_, Bound> {
         self.0.iter()
     }
 
-    pub fn iter_mut(&mut self) -> std::slice::IterMut<Bound> {
+    pub fn iter_mut(&mut self) -> std::slice::IterMut<'_, Bound> {
         self.0.iter_mut()
     }
 
@@ -57,7 +59,7 @@ impl FromIterator<Bound> for Bounds {
 /// For example, the type of the square-function (D -> D^2), needs an
 /// additional `D: Dim` bound, as arbitrary types (like Bool) can not
 /// be squared.
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct QualifiedType {
     pub inner: Type,
     pub bounds: Bounds,
@@ -245,6 +245,13 @@ impl ApplySubstitution for Statement<'_> {
 
                 Ok(())
             }
+            Statement::ForLoop(_, list, body) => {
+                list.apply(s)?;
+                for statement in body {
+                    statement.apply(s)?;
+                }
+                Ok(())
+            }
         }
     }
 }
@@ -755,3 +755,89 @@ fn instantiation() {
         TypeCheckError::ConstraintSolverError(..)
     ));
 }
+
+#[test]
+fn recovery_after_errors() {
+    // All errors are reported, not just the first one
+    let error = get_typecheck_error(
+        "
+            let x: A = 2 b
+            let y = 1 a + true
+            fn f(z: A) -> B = z
+            ",
+    );
+    let errors = error.errors();
+    assert_eq!(errors.len(), 3);
+    assert!(matches!(
+        errors[0],
+        TypeCheckError::IncompatibleDimensions(..)
+    ));
+    assert!(matches!(
+        errors[1],
+        TypeCheckError::ExpectedDimensionType(..)
+    ));
+    assert!(matches!(
+        errors[2],
+        TypeCheckError::IncompatibleDimensions(..)
+    ));
+
+    // Failed definitions do not lead to follow-up errors
+    assert!(matches!(
+        get_typecheck_error(
+            "
+            let x: A = 2 b
+            let y = x + 1 a
+            let z = x && true
+            fn f(w: A) -> B = w
+            let v = f(1 a) + 2 c
+            "
+        ),
+        TypeCheckError::MultipleErrors(errors) if errors.len() == 2
+    ));
+
+    // Unknown identifiers are still reported after failed definitions
+    assert!(matches!(
+        get_typecheck_error(
+            "
+            let x: A = 2 b
+            let y = x + unknown
+            "
+        ),
+        TypeCheckError::MultipleErrors(errors)
+            if matches!(errors[1], TypeCheckError::UnknownIdentifier(..))
+    ));
+
+    // Failed struct and dimension definitions do not lead to follow-up errors
+    assert!(matches!(
+        get_typecheck_error(
+            "
+            struct P { x: Unknown, y: A }
+            let p = P { x: 1 a, y: 2 a }
+            let x = p.x
+            fn f(q: P) -> A = q.x + q.y
+            let r: P = p
+            "
+        ),
+        TypeCheckError::RegistryError(..)
+    ));
+    assert!(matches!(
+        get_typecheck_error(
+            "
+            let x = unknown
+            let y = x.field.other
+            "
+        ),
+        TypeCheckError::UnknownIdentifier(..)
+    ));
+    assert!(matches!(
+        get_typecheck_error(
+            "
+            dimension D = Unknown / B
+            dimension E = D * A
+            let d: D = 2 a
+            fn g(e: E) -> D = e
+            "
+        ),
+        TypeCheckError::RegistryError(..)
+    ));
+}
@@ -1,4 +1,5 @@
 use compact_str::ToCompactString;
+use serde::{Deserialize, Serialize};
 
 use super::name_generator::NameGenerator;
 use super::qualified_type::{Bound, Bounds, QualifiedType};
@@ -17,7 +18,7 @@ use crate::typed_ast::Type;
 ///
 /// Type schemes can be created by calling .quantify() on a qualified
 /// type.
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum TypeScheme {
     Concrete(Type),
     Quantified(usize, QualifiedType),
@@ -3,6 +3,7 @@ use std::sync::Arc;
 use compact_str::{format_compact, CompactString, ToCompactString};
 use indexmap::IndexMap;
 use itertools::Itertools;
+use serde::{Deserialize, Serialize};
 
 use crate::arithmetic::Exponent;
 pub use crate::ast::{BinaryOperator, TypeExpression, UnaryOperator};
@@ -21,7 +22,7 @@ use crate::{
 use crate::{markup as m, BaseRepresentation, BaseRepresentationFactor};
 
 /// Dimension type
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum DTypeFactor {
     TVar(TypeVariable),
     TPar(CompactString),
@@ -41,7 +42,7 @@ impl DTypeFactor {
 
 type DtypeFactorPower = (DTypeFactor, Exponent);
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct DType {
     // Always in canonical form
     factors: Arc<Vec<DtypeFactorPower>>,
@@ -283,14 +284,14 @@ impl From<BaseRepresentation> for DType {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct StructInfo {
     pub definition_span: Span,
     pub name: CompactString,
     pub fields: IndexMap<CompactString, (Span, Type)>,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Type {
     TVar(TypeVariable),
     TPar(CompactString),
@@ -637,10 +638,15 @@ pub enum Statement<'a> {
     ),
     ProcedureCall(crate::ast::ProcedureKind, Vec<Expression<'a>>),
     DefineStruct(StructInfo),
+    ForLoop(
+        &'a str,            // loop variable
+        Expression<'a>,     // list
+        Vec<Statement<'a>>, // body
+    ),
 }
 
 impl Statement<'_> {
-    pub fn as_expression(&self) -> Option<&Expression> {
+    pub fn as_expression(&self) -> Option<&Expression<'_>> {
         if let Self::Expression(v) = self {
             Some(v)
         } else {
@@ -729,6 +735,11 @@ impl Statement<'_> {
             }
             Statement::ProcedureCall(_, _) => {}
             Statement::DefineStruct(_) => {}
+            Statement::ForLoop(_, _, body) => {
+                for statement in body {
+                    statement.update_readable_types(registry);
+                }
+            }
         }
     }
 
@@ -904,6 +915,13 @@ fn decorator_markup(decorators: &Vec<Decorator>) -> Markup {
                         }
                         + m::operator(")")
                 }
+                Decorator::Test => m::decorator("@test"),
+                Decorator::Deprecated(message) => {
+                    m::decorator("@deprecated")
+                        + m::operator("(")
+                        + m::string(message.clone())
+                        + m::operator(")")
+                }
             }
             + m::nl();
     }
@@ -1138,10 +1156,42 @@ impl PrettyPrint for Statement<'_> {
                     }
                     + m::operator("}")
             }
+            Statement::ForLoop(loop_variable, list, body) => {
+                m::keyword("for")
+                    + m::space()
+                    + m::identifier(loop_variable.to_compact_string())
+                    + m::space()
+                    + m::keyword("in")
+                    + m::space()
+                    + list.pretty_print()
+                    + m::space()
+                    + m::operator("{")
+                    + body
+                        .iter()
+                        .map(|statement| m::nl() + indented(statement.pretty_print()))
+                        .sum()
+                    + m::nl()
+                    + m::operator("}")
+            }
         }
     }
 }
 
+/// Indent all lines of a (possibly multi-line) statement
+fn indented(markup: Markup) -> Markup {
+    let indentation = || m::whitespace("    ");
+
+    markup.0.into_iter().fold(indentation(), |indented, part| {
+        let is_line_break = part.2.contains('\n');
+        let indented = indented + Markup::from(part);
+        if is_line_break {
+            indented + indentation()
+        } else {
+            indented
+        }
+    })
+}
+
 fn pretty_scalar(n: Number) -> Markup {
     m::value(n.pretty_print())
 }
@@ -1383,7 +1433,7 @@ mod tests {
     use crate::markup::{Formatter, PlainTextFormatter};
     use crate::prefix_transformer::Transformer;
 
-    fn parse(code: &str) -> Statement {
+    fn parse(code: &str) -> Statement<'_> {
         let statements = crate::parser::parse(
             "dimension Scalar = 1
                  dimension Length
@@ -3,6 +3,7 @@ use std::{fmt::Display, ops::Div};
 use compact_str::{CompactString, ToCompactString};
 use itertools::Itertools;
 use num_traits::{ToPrimitive, Zero};
+use serde::{Deserialize, Serialize};
 
 use crate::{
     arithmetic::{pretty_exponent, Exponent, Power, Rational},
@@ -16,13 +17,13 @@ pub type ConversionFactor = Number;
 
 /// A unit can either be a base/fundamental unit or it is derived from another unit.
 /// In the latter case, a conversion factor to the defining unit has to be specified.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum UnitKind {
     Base,
     Derived(ConversionFactor, Unit),
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct CanonicalName {
     pub name: CompactString,
     pub accepts_prefix: AcceptsPrefix,
@@ -37,7 +38,7 @@ impl CanonicalName {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct UnitIdentifier {
     pub name: CompactString,
     pub canonical_name: CanonicalName,
@@ -166,7 +167,7 @@ impl Ord for UnitIdentifier {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub struct UnitFactor {
     pub unit_id: UnitIdentifier,
     pub prefix: Prefix,
@@ -1,21 +1,24 @@
 use crate::markup::Markup;
 use crate::prefix_parser::AcceptsPrefix;
 use crate::registry::{BaseRepresentation, BaseRepresentationFactor, Registry, RegistryError};
-use crate::typed_ast::Type;
+use crate::typed_ast::{DType, Type};
 use crate::unit::{CanonicalName, Unit};
 
 use compact_str::CompactString;
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 #[derive(Clone, Error, Debug, PartialEq, Eq)]
 pub enum UnitRegistryError {
     #[error("{0}")]
     RegistryError(RegistryError),
+    #[error("Unit '{0}' does not have a physical dimension")]
+    NoDimension(CompactString),
 }
 
 pub type Result<T> = std::result::Result<T, UnitRegistryError>;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UnitMetadata {
     pub type_: Type,
     pub readable_type: Markup,
@@ -24,11 +27,13 @@ pub struct UnitMetadata {
     pub canonical_name: CanonicalName,
     pub url: Option<CompactString>,
     pub description: Option<CompactString>,
+    /// The message of the `@deprecated` decorator
+    pub deprecated: Option<CompactString>,
     pub binary_prefixes: bool,
     pub metric_prefixes: bool,
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct UnitRegistry {
     pub inner: Registry<UnitMetadata>,
 }
@@ -62,4 +67,20 @@ impl UnitRegistry {
 
         Ok(())
     }
+
+    /// The physical dimension of a unit, i.e. the product of the dimensions of its factors.
+    pub fn dimension_of(&self, unit: &Unit) -> Result<DType> {
+        let mut dtype = DType::scalar();
+        for factor in unit.iter() {
+            let (_, metadata) = self
+                .inner
+                .get_base_representation_for_name(&factor.unit_id.name)
+                .map_err(UnitRegistryError::RegistryError)?;
+            let Type::Dimension(factor_dtype) = metadata.type_ else {
+                return Err(UnitRegistryError::NoDimension(factor.unit_id.name.clone()));
+            };
+            dtype = dtype.multiply(&factor_dtype.power(factor.exponent));
+        }
+        Ok(dtype)
+    }
 }
@@ -3,12 +3,17 @@ use std::sync::Arc;
 use compact_str::{CompactString, ToCompactString};
 use itertools::Itertools;
 use jiff::Zoned;
+use serde::{Deserialize, Serialize};
 
 use crate::{
-    list::NumbatList, pretty_print::PrettyPrint, quantity::Quantity, typed_ast::StructInfo,
+    list::NumbatList,
+    pretty_print::PrettyPrint,
+    quantity::Quantity,
+    typed_ast::{DTypeFactor, StructInfo, Type},
+    unit_registry::UnitRegistry,
 };
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum FunctionReference {
     Foreign(CompactString),
     Normal(CompactString),
@@ -28,7 +33,7 @@ impl std::fmt::Display for FunctionReference {
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Value {
     Quantity(Quantity),
     Boolean(bool),
@@ -42,6 +47,35 @@ pub enum Value {
 }
 
 impl Value {
+    /// Whether this value can be used where a value of the given type is expected.
+    /// The physical dimension of quantities is looked up in the unit registry.
+    /// Type variables match any value.
+    pub(crate) fn is_of_type(&self, type_: &Type, unit_registry: &UnitRegistry) -> bool {
+        match (self, type_) {
+            (_, Type::TVar(_) | Type::TPar(_)) => true,
+            (Value::Quantity(q), Type::Dimension(dtype)) => {
+                // Generic dimensions are not known at run time
+                let is_generic = dtype
+                    .factors()
+                    .iter()
+                    .any(|(factor, _)| !matches!(factor, DTypeFactor::BaseDimension(_)));
+                is_generic
+                    || unit_registry
+                        .dimension_of(q.unit())
+                        .is_ok_and(|actual| &actual == dtype)
+            }
+            (Value::Boolean(_), Type::Boolean) => true,
+            (Value::String(_), Type::String) => true,
+            (Value::DateTime(_), Type::DateTime) => true,
+            (Value::FunctionReference(_), Type::Fn(..)) => true,
+            (Value::StructInstance(info, _), Type::Struct(expected)) => info.name == expected.name,
+            (Value::List(elements), Type::List(element_type)) => elements
+                .iter()
+                .all(|element| element.is_of_type(element_type, unit_registry)),
+            _ => false,
+        }
+    }
+
     #[track_caller]
     pub fn unsafe_as_quantity(self) -> Quantity {
         if let Value::Quantity(q) = self {
@@ -1,19 +1,29 @@
 use std::collections::{HashMap, VecDeque};
 use std::fmt::Display;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
+use std::time::Instant;
 
+use codespan_reporting::files::SimpleFiles;
 use compact_str::{CompactString, ToCompactString};
 use indexmap::IndexMap;
 use num_traits::ToPrimitive;
+use serde::{Deserialize, Serialize};
 
+use crate::debugger::{Debugger, PausedState, StackFrame};
 use crate::list::NumbatList;
+use crate::profiler::Profiler;
 use crate::span::Span;
-use crate::typed_ast::StructInfo;
+use crate::typed_ast::{StructInfo, Type};
 use crate::{
-    ffi::{self, ArityRange, Callable, ForeignFunction},
-    interpreter::{InterpreterResult, PrintFunction, Result, RuntimeError},
+    ffi::{self, ArityRange, Callable, ForeignFunction, RegisteredFunction},
+    interpreter::{
+        InterpreterResult, PlotFunction, PrintFunction, ReadLineFunction, ResourceLimits, Result,
+        ResultFunction, RuntimeError,
+    },
     markup::Markup,
     math,
+    module_importer::ModuleImporter,
     number::Number,
     prefix::Prefix,
     quantity::{Quantity, QuantityError},
@@ -119,12 +129,25 @@ pub enum Op {
 
     /// Return from the current function
     Return,
+
+    /// Unconditionally move IP backward by the given offset argument
+    Loop,
+
+    /// Take the list on top of the stack. If it is empty, pop it off and move IP
+    /// forward by the given offset argument. Otherwise, replace it by its tail and
+    /// push its first element onto the stack.
+    IterateList,
+    /// Pop the given number of values off the stack
+    Pop,
+    /// Same as `FFICallFunction`, but with a third argument which is an index to
+    /// retrieve the source-span of the arguments
+    FFICallFunctionWithSpans,
 }
 
 impl Op {
     fn num_operands(self) -> usize {
         match self {
-            Op::FFICallProcedure => 3,
+            Op::FFICallProcedure | Op::FFICallFunctionWithSpans => 3,
             Op::SetUnitConstant | Op::Call | Op::FFICallFunction | Op::BuildStructInstance => 2,
             Op::LoadConstant
             | Op::ApplyPrefix
@@ -134,6 +157,9 @@ impl Op {
             | Op::JoinString
             | Op::JumpIfFalse
             | Op::Jump
+            | Op::Loop
+            | Op::IterateList
+            | Op::Pop
             | Op::CallCallable
             | Op::AccessStructField
             | Op::BuildList => 1,
@@ -192,6 +218,10 @@ impl Op {
             Op::LogicalNeg => "LogicalNeg",
             Op::JumpIfFalse => "JumpIfFalse",
             Op::Jump => "Jump",
+            Op::Loop => "Loop",
+            Op::IterateList => "IterateList",
+            Op::Pop => "Pop",
+            Op::FFICallFunctionWithSpans => "FFICallFunctionWithSpans",
             Op::Call => "Call",
             Op::FFICallFunction => "FFICallFunction",
             Op::FFICallProcedure => "FFICallProcedure",
@@ -206,7 +236,7 @@ impl Op {
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum Constant {
     Scalar(f64),
     Unit(Unit),
@@ -242,7 +272,7 @@ impl Display for Constant {
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 struct CallFrame {
     /// The function being executed, index into [Vm]s `bytecode` vector.
     function_idx: usize,
@@ -266,16 +296,147 @@ impl CallFrame {
     }
 }
 
+/// A position in the source code that corresponds to a range of bytecode. Source
+/// locations are the points at which a debugger can pause execution.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SourceLocation {
+    /// Offset of the first instruction of this location
+    offset: usize,
+
+    span: Span,
+
+    /// The number of local variables that are defined when this location is reached
+    num_locals: usize,
+}
+
 pub struct ExecutionContext<'a> {
     pub print_fn: &'a mut PrintFunction,
+    /// The source files, used to report source locations at runtime
+    pub files: &'a SimpleFiles<String, String>,
+    /// The module importer, used to find data files in the module search paths
+    pub importer: &'a dyn ModuleImporter,
+    pub debugger: Option<&'a mut Debugger>,
+    pub limits: &'a ResourceLimits,
+    pub allow_side_effects: bool,
+    pub interrupt: Option<&'a AtomicBool>,
+    pub result_fn: Option<&'a mut ResultFunction>,
+    pub plot_fn: Option<&'a mut PlotFunction>,
+    pub arguments: &'a [CompactString],
+    pub read_line_fn: &'a mut ReadLineFunction,
+    /// Number of list elements that have been allocated so far
+    pub allocated_list_elements: usize,
+}
+
+impl ExecutionContext<'_> {
+    /// Check the resource limits before a list with `length` elements is
+    /// allocated, `new_elements` of which are added to the allocation budget.
+    pub(crate) fn allocate_list(&mut self, length: usize, new_elements: usize) -> Result<()> {
+        if let Some(max_list_length) = self.limits.max_list_length {
+            if length > max_list_length {
+                return Err(Box::new(RuntimeError::ListLengthLimitExceeded(
+                    max_list_length,
+                )));
+            }
+        }
+
+        self.allocated_list_elements = self.allocated_list_elements.saturating_add(new_elements);
+        if let Some(max_list_elements) = self.limits.max_list_elements {
+            if self.allocated_list_elements > max_list_elements {
+                return Err(Box::new(RuntimeError::ListElementLimitExceeded(
+                    max_list_elements,
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Keeps track of the resources used by a single run of the VM.
+struct ResourceUsage<'a> {
+    limits: &'a ResourceLimits,
+    interrupt: Option<&'a AtomicBool>,
+    instructions: u64,
+    start: Option<Instant>,
+}
+
+impl<'a> ResourceUsage<'a> {
+    /// How often (in number of instructions) the wall-clock time and the
+    /// interrupt flag are checked.
+    const CHECK_INTERVAL: u64 = 1024;
+
+    fn new(limits: &'a ResourceLimits, interrupt: Option<&'a AtomicBool>) -> Self {
+        Self {
+            limits,
+            interrupt,
+            instructions: 0,
+            start: limits.timeout.map(|_| Instant::now()),
+        }
+    }
+
+    fn count_instruction(&mut self) -> Result<()> {
+        self.instructions += 1;
+
+        if let Some(max_instructions) = self.limits.max_instructions {
+            if self.instructions > max_instructions {
+                return Err(Box::new(RuntimeError::InstructionLimitExceeded(
+                    max_instructions,
+                )));
+            }
+        }
+
+        if self.instructions % Self::CHECK_INTERVAL != 0 {
+            return Ok(());
+        }
+
+        if self.interrupt.is_some_and(|i| i.load(Ordering::Relaxed)) {
+            return Err(Box::new(RuntimeError::Interrupted));
+        }
+
+        if let (Some(timeout), Some(start)) = (self.limits.timeout, self.start) {
+            if start.elapsed() > timeout {
+                return Err(Box::new(RuntimeError::TimeLimitExceeded(timeout)));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn check_call_depth(&self, depth: usize) -> Result<()> {
+        match self.limits.max_call_depth {
+            Some(max_call_depth) if depth > max_call_depth => Err(Box::new(
+                RuntimeError::CallDepthLimitExceeded(max_call_depth),
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Lists created by the VM and by the builtin functions are checked before
+    /// they are allocated (see [`ExecutionContext::allocate_list`]). This check
+    /// is only needed for lists that are returned by native functions.
+    fn check_value(&self, value: &Value) -> Result<()> {
+        match (self.limits.max_list_length, value) {
+            (Some(max_list_length), Value::List(list)) if list.len() > max_list_length => Err(
+                Box::new(RuntimeError::ListLengthLimitExceeded(max_list_length)),
+            ),
+            _ => Ok(()),
+        }
+    }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Vm {
     /// The actual code of the program, structured by function name. The code
     /// for the global scope is at index 0 under the function name `<main>`.
     bytecode: Vec<(CompactString, Vec<u8>)>,
 
+    /// Source locations for each function in `bytecode`, sorted by offset.
+    source_locations: Vec<Vec<SourceLocation>>,
+
+    /// Names of the local variables for each function in `bytecode`, by slot index.
+    /// For the global scope, these are the names of the global variables.
+    local_names: Vec<Vec<CompactString>>,
+
     /// An index into the `bytecode` vector referring to the function which is
     /// currently being compiled.
     current_chunk_index: usize,
@@ -302,10 +463,16 @@ pub struct Vm {
     last_result: Option<Value>,
 
     /// List of registered native/foreign functions
-    ffi_callables: Vec<&'static ForeignFunction>,
+    #[serde(with = "ffi::serde_by_name")]
+    ffi_callables: Vec<Arc<ForeignFunction>>,
 
-    /// Spans for arguments of procedure calls. This is used for
-    /// assertion error messages, for example.
+    /// Native functions that have been registered by the embedding application. They
+    /// are added to `ffi_callables` once the corresponding Numbat declaration is compiled.
+    #[serde(skip)]
+    native_functions: HashMap<CompactString, RegisteredFunction>,
+
+    /// Spans for arguments of procedure calls (and calls to foreign functions that
+    /// need them). This is used for assertion error messages, for example.
     procedure_arg_spans: Vec<Vec<Span>>,
 
     /// The call stack
@@ -317,6 +484,10 @@ pub struct Vm {
     /// Whether or not to run in debug mode.
     debug: bool,
 
+    /// Collects timing information, if profiling is enabled.
+    #[serde(skip)]
+    profiler: Option<Profiler>,
+
     pub unit_registry: UnitRegistry,
 }
 
@@ -324,6 +495,8 @@ impl Vm {
     pub fn new() -> Self {
         Self {
             bytecode: vec![("<main>".into(), vec![])],
+            source_locations: vec![vec![]],
+            local_names: vec![vec![]],
             current_chunk_index: 0,
             constants: vec![],
             struct_infos: IndexMap::new(),
@@ -331,11 +504,16 @@ impl Vm {
             strings: vec![],
             unit_information: vec![],
             last_result: None,
-            ffi_callables: ffi::procedures().iter().map(|(_, ff)| ff).collect(),
+            ffi_callables: ffi::procedures()
+                .values()
+                .map(|ff| Arc::new(ff.clone()))
+                .collect(),
+            native_functions: HashMap::new(),
             procedure_arg_spans: vec![],
             frames: vec![CallFrame::root()],
             stack: vec![],
             debug: false,
+            profiler: None,
             unit_registry: UnitRegistry::new(),
         }
     }
@@ -343,6 +521,14 @@ impl Vm {
         self.debug = activate;
     }
 
+    pub fn set_profiling(&mut self, activate: bool) {
+        self.profiler = activate.then(Profiler::default);
+    }
+
+    pub fn profiler(&self) -> Option<&Profiler> {
+        self.profiler.as_ref()
+    }
+
     // The following functions are helpers for the compilation process
 
     fn current_chunk_mut(&mut self) -> &mut Vec<u8> {
@@ -380,12 +566,11 @@ impl Vm {
         Self::push_u16(current_chunk, arg3);
     }
 
-    pub fn current_offset(&self) -> u16 {
-        self.bytecode[self.current_chunk_index].1.len() as u16
+    pub fn current_offset(&self) -> usize {
+        self.bytecode[self.current_chunk_index].1.len()
     }
 
-    pub fn patch_u16_value_at(&mut self, offset: u16, arg: u16) {
-        let offset = offset as usize;
+    pub fn patch_u16_value_at(&mut self, offset: usize, arg: u16) {
         let chunk = self.current_chunk_mut();
         chunk[offset] = (arg & 0xff) as u8;
         chunk[offset + 1] = ((arg >> 8) & 0xff) as u8;
@@ -440,6 +625,8 @@ impl Vm {
 
     pub(crate) fn begin_function(&mut self, name: &str) {
         self.bytecode.push((name.into(), vec![]));
+        self.source_locations.push(vec![]);
+        self.local_names.push(vec![]);
         self.current_chunk_index = self.bytecode.len() - 1
     }
 
@@ -462,19 +649,89 @@ impl Vm {
         position as u16
     }
 
-    pub(crate) fn add_foreign_function(&mut self, name: &str, arity: ArityRange) {
-        let ff = ffi::functions().get(name).unwrap();
-        assert!(ff.arity == arity);
-        self.ffi_callables.push(ff);
+    /// Add a foreign function. The return type is only needed for native functions,
+    /// whose return values are checked.
+    pub(crate) fn add_foreign_function(
+        &mut self,
+        name: &str,
+        arity: ArityRange,
+        return_type: &Type,
+        readable_return_type: &Markup,
+    ) {
+        let ff = if let Some(registered) = self.native_functions.get(name) {
+            ForeignFunction {
+                name: name.into(),
+                arity,
+                callable: Callable::Native {
+                    function: registered.function.clone(),
+                    return_type: return_type.clone(),
+                    readable_return_type: readable_return_type.to_compact_string(),
+                },
+                side_effects: registered.side_effects,
+            }
+        } else {
+            let ff = ffi::functions().get(name).unwrap();
+            assert!(ff.arity == arity);
+            ff.clone()
+        };
+        self.ffi_callables.push(Arc::new(ff));
+    }
+
+    /// Register a native function. Returns the function that was previously
+    /// registered under the same name, if any.
+    pub(crate) fn register_native_function(
+        &mut self,
+        name: &str,
+        function: RegisteredFunction,
+    ) -> Option<RegisteredFunction> {
+        self.native_functions.insert(name.into(), function)
+    }
+
+    pub(crate) fn unregister_native_function(&mut self, name: &str) {
+        self.native_functions.remove(name);
     }
 
     pub(crate) fn get_ffi_callable_idx(&self, name: &str) -> Option<u16> {
-        // TODO: this is a linear search that can certainly be optimized
-        let position = self.ffi_callables.iter().position(|ff| ff.name == name)?;
+        // TODO: this is a linear search that can certainly be optimized. We search
+        // from the end, such that redeclared (native) functions take precedence.
+        let position = self.ffi_callables.iter().rposition(|ff| ff.name == name)?;
         assert!(position <= u16::MAX as usize);
         Some(position as u16)
     }
 
+    /// Mark the start of a new source location at the current offset.
+    pub(crate) fn add_source_location(&mut self, span: Span, num_locals: usize) {
+        let offset = self.current_offset();
+        let locations = &mut self.source_locations[self.current_chunk_index];
+
+        // A location that did not generate any code is replaced
+        if locations.last().is_some_and(|l| l.offset == offset) {
+            locations.pop();
+        }
+
+        locations.push(SourceLocation {
+            offset,
+            span,
+            num_locals,
+        });
+    }
+
+    /// Register the name of the next local variable in the current function (or
+    /// the next global variable, if we are in the global scope).
+    pub(crate) fn add_local_name(&mut self, name: &str) {
+        self.local_names[self.current_chunk_index].push(name.to_compact_string());
+    }
+
+    /// Forget the names of all but the first `num_locals` local variables, when the
+    /// others go out of scope.
+    pub(crate) fn truncate_local_names(&mut self, num_locals: usize) {
+        self.local_names[self.current_chunk_index].truncate(num_locals);
+    }
+
+    pub(crate) fn ffi_callable(&self, idx: u16) -> &ForeignFunction {
+        &self.ffi_callables[idx as usize]
+    }
+
     pub(crate) fn add_procedure_arg_span(&mut self, spans: Vec<Span>) -> u16 {
         self.procedure_arg_spans.push(spans);
         assert!(self.procedure_arg_spans.len() <= u16::MAX as usize);
@@ -599,9 +856,26 @@ impl Vm {
         self.stack.pop().expect("stack should not be empty")
     }
 
+    fn profiler_enter(&mut self, name: &str) {
+        if let Some(profiler) = &mut self.profiler {
+            profiler.enter(name);
+        }
+    }
+
+    fn profiler_exit(&mut self) {
+        if let Some(profiler) = &mut self.profiler {
+            profiler.exit();
+        }
+    }
+
     pub fn run(&mut self, ctx: &mut ExecutionContext) -> Result<InterpreterResult> {
         let old_stack = self.stack.clone();
+
+        self.profiler_enter("<main>");
         let result = self.run_without_cleanup(ctx);
+        if let Some(profiler) = &mut self.profiler {
+            profiler.exit_all();
+        }
         if result.is_err() {
             // Perform cleanup: clear the stack and move IP to the end.
             // This is useful for the REPL.
@@ -623,11 +897,108 @@ impl Vm {
         self.current_frame().ip >= self.bytecode[self.current_frame().function_idx].1.len()
     }
 
+    /// The source location that contains the instruction at `offset` in the given
+    /// function. This maps bytecode offsets back to spans in the source code.
+    fn source_location_at(&self, function_idx: usize, offset: usize) -> Option<&SourceLocation> {
+        let locations = &self.source_locations[function_idx];
+        let idx = locations.partition_point(|l| l.offset <= offset);
+        idx.checked_sub(1).map(|idx| &locations[idx])
+    }
+
+    fn stack_frame(&self, frame: &CallFrame, offset: usize, globals_start: usize) -> StackFrame {
+        let location = self.source_location_at(frame.function_idx, offset);
+        let num_locals = location.map(|l| l.num_locals).unwrap_or(0);
+        let first_local = if frame.function_idx == 0 {
+            globals_start
+        } else {
+            0
+        };
+
+        StackFrame {
+            function_name: self.bytecode[frame.function_idx].0.clone(),
+            span: location.map(|l| l.span),
+            locals: self.local_names[frame.function_idx]
+                .iter()
+                .enumerate()
+                .take(num_locals)
+                .skip(first_local)
+                .filter_map(|(slot, name)| {
+                    Some((name.clone(), self.stack.get(frame.fp + slot)?.clone()))
+                })
+                .collect(),
+        }
+    }
+
+    /// Pause execution if we are at the start of a source location and the debugger
+    /// decides to do so.
+    fn debugger_hook(&self, debugger: &mut Debugger, globals_start: usize) {
+        let frame = self.current_frame();
+        let Some(location) = self.source_location_at(frame.function_idx, frame.ip) else {
+            return;
+        };
+        if location.offset != frame.ip {
+            return;
+        }
+
+        let depth = self.frames.len();
+        if !debugger.should_pause(&location.span, depth) {
+            return;
+        }
+
+        // For all but the innermost frame, the IP points to the instruction after the call.
+        let offset_in_frame = |i: usize, frame: &CallFrame| {
+            if i + 1 == depth {
+                frame.ip
+            } else {
+                frame.ip.saturating_sub(1)
+            }
+        };
+
+        debugger.pause(depth, |files| {
+            let call_stack = self
+                .frames
+                .iter()
+                .enumerate()
+                .map(|(i, frame)| self.stack_frame(frame, offset_in_frame(i, frame), globals_start))
+                .collect();
+
+            let num_globals = self
+                .source_location_at(0, offset_in_frame(0, &self.frames[0]))
+                .map(|l| l.num_locals)
+                .unwrap_or(0);
+            let globals = self.local_names[0]
+                .iter()
+                .zip(&self.stack)
+                .take(num_globals)
+                .map(|(name, value)| (name.as_str(), value))
+                .collect();
+
+            PausedState {
+                span: location.span,
+                call_stack,
+                globals,
+                files,
+            }
+        });
+    }
+
     fn run_without_cleanup(&mut self, ctx: &mut ExecutionContext) -> Result<InterpreterResult> {
         let mut result_last_statement = None;
+        let globals_start = self.stack.len();
+        let mut usage = ResourceUsage::new(ctx.limits, ctx.interrupt);
         while !self.is_at_the_end() {
             self.debug();
 
+            usage.count_instruction()?;
+
+            if let Some(debugger) = ctx.debugger.as_deref_mut() {
+                self.debugger_hook(debugger, globals_start);
+            }
+
+            if let Some(profiler) = &mut self.profiler {
+                profiler.count_instruction();
+            }
+
             let op = unsafe { std::mem::transmute::<u8, Op>(self.read_byte()) };
 
             match op {
@@ -837,6 +1208,25 @@ impl Vm {
                     let offset = self.read_u16() as usize;
                     self.current_frame_mut().ip += offset;
                 }
+                Op::Loop => {
+                    let offset = self.read_u16() as usize;
+                    self.current_frame_mut().ip -= offset;
+                }
+                Op::IterateList => {
+                    let offset = self.read_u16() as usize;
+                    let mut list = self.pop().unsafe_as_list();
+                    if let Some(element) = list.clone().head() {
+                        list.tail()?;
+                        self.push(list.into());
+                        self.push(element);
+                    } else {
+                        self.current_frame_mut().ip += offset;
+                    }
+                }
+                Op::Pop => {
+                    let num_values = self.read_u16() as usize;
+                    self.stack.truncate(self.stack.len() - num_values);
+                }
                 Op::Call => {
                     let function_idx = self.read_u16() as usize;
                     let num_args = self.read_u16() as usize;
@@ -844,30 +1234,59 @@ impl Vm {
                         function_idx,
                         ip: 0,
                         fp: self.stack.len() - num_args,
-                    })
+                    });
+                    usage.check_call_depth(self.frames.len() - 1)?;
+                    if let Some(profiler) = &mut self.profiler {
+                        profiler.enter(&self.bytecode[function_idx].0);
+                    }
                 }
-                Op::FFICallFunction | Op::FFICallProcedure => {
+                Op::FFICallFunction | Op::FFICallProcedure | Op::FFICallFunctionWithSpans => {
                     let function_idx = self.read_u16() as usize;
                     let num_args = self.read_u16() as usize;
-                    let foreign_function = &self.ffi_callables[function_idx];
+                    let foreign_function = self.ffi_callables[function_idx].clone();
 
                     debug_assert!(foreign_function.arity.contains(&num_args));
 
+                    if foreign_function.side_effects && !ctx.allow_side_effects {
+                        return Err(Box::new(RuntimeError::SideEffectsDisabled(
+                            foreign_function.name.to_string(),
+                        )));
+                    }
+
                     let mut args = VecDeque::new();
                     for _ in 0..num_args {
                         args.push_front(self.pop());
                     }
 
-                    match &self.ffi_callables[function_idx].callable {
-                        Callable::Function(function) => {
-                            let result = (function)(args);
-                            self.push(result?);
+                    match &foreign_function.callable {
+                        Callable::Function(..)
+                        | Callable::FunctionWithContext(..)
+                        | Callable::Native { .. } => {
+                            self.profiler_enter(&foreign_function.name);
+                            let result = foreign_function.call(ctx, args, &self.unit_registry);
+                            self.profiler_exit();
+                            let result = result?;
+                            usage.check_value(&result)?;
+                            self.push(result);
+                        }
+                        Callable::FunctionWithSpans(function) => {
+                            let span_idx = self.read_u16() as usize;
+                            let spans = self.procedure_arg_spans[span_idx].clone();
+
+                            self.profiler_enter(&foreign_function.name);
+                            let result = (function)(ctx, args, spans);
+                            self.profiler_exit();
+                            let result = result?;
+                            usage.check_value(&result)?;
+                            self.push(result);
                         }
                         Callable::Procedure(procedure) => {
                             let span_idx = self.read_u16() as usize;
-                            let spans = &self.procedure_arg_spans[span_idx];
+                            let spans = self.procedure_arg_spans[span_idx].clone();
 
-                            let result = (procedure)(ctx, args, spans.clone());
+                            self.profiler_enter(&foreign_function.name);
+                            let result = (procedure)(ctx, args, spans);
+                            self.profiler_exit();
 
                             match result {
                                 std::ops::ControlFlow::Continue(()) => {}
@@ -891,7 +1310,9 @@ impl Vm {
                                 function_idx,
                                 ip: 0,
                                 fp: self.stack.len() - num_args,
-                            })
+                            });
+                            usage.check_call_depth(self.frames.len() - 1)?;
+                            self.profiler_enter(name);
                         }
                         FunctionReference::Foreign(ref name) => {
                             let function_idx = self
@@ -904,10 +1325,25 @@ impl Vm {
                                 args.push_front(self.pop());
                             }
 
-                            match &self.ffi_callables[function_idx].callable {
-                                Callable::Function(function) => {
-                                    let result = (function)(args);
-                                    self.push(result?);
+                            let foreign_function = self.ffi_callables[function_idx].clone();
+
+                            if foreign_function.side_effects && !ctx.allow_side_effects {
+                                return Err(Box::new(RuntimeError::SideEffectsDisabled(
+                                    foreign_function.name.to_string(),
+                                )));
+                            }
+
+                            match &foreign_function.callable {
+                                Callable::Function(..)
+                                | Callable::FunctionWithContext(..)
+                                | Callable::FunctionWithSpans(..)
+                                | Callable::Native { .. } => {
+                                    self.profiler_enter(&foreign_function.name);
+                                    let result = foreign_function.call(ctx, args, &self.unit_registry);
+                                    self.profiler_exit();
+                                    let result = result?;
+                                    usage.check_value(&result)?;
+                                    self.push(result);
                                 }
                                 Callable::Procedure(..) => unreachable!("Foreign procedures can not be targeted by a function reference"),
                             }
@@ -999,6 +1435,10 @@ impl Vm {
                     if self.frames.len() == 1 {
                         let return_value = self.pop();
 
+                        if let Some(result_fn) = ctx.result_fn.as_deref_mut() {
+                            result_fn(&return_value);
+                        }
+
                         self.last_result = Some(return_value.clone());
 
                         result_last_statement = Some(return_value);
@@ -1015,6 +1455,8 @@ impl Vm {
 
                         // Push the return value back on top of the stack
                         self.stack.push(return_value);
+
+                        self.profiler_exit();
                     }
                 }
                 Op::BuildStructInstance => {
@@ -1044,6 +1486,7 @@ impl Vm {
                 }
                 Op::BuildList => {
                     let length = self.read_u16();
+                    ctx.allocate_list(length as usize, length as usize)?;
                     let mut list = NumbatList::with_capacity(length as usize);
 
                     for _ in 0..length {
@@ -1095,6 +1538,8 @@ impl Vm {
 
 #[test]
 fn vm_basic() {
+    use crate::module_importer::NullImporter;
+
     let mut vm = Vm::new();
     vm.add_constant(Constant::Scalar(42.0));
     vm.add_constant(Constant::Scalar(1.0));
@@ -1107,6 +1552,17 @@ fn vm_basic() {
     let mut print_fn = |_: &Markup| {};
     let mut ctx = ExecutionContext {
         print_fn: &mut print_fn,
+        files: &SimpleFiles::new(),
+        importer: &NullImporter::default(),
+        debugger: None,
+        limits: &ResourceLimits::default(),
+        allow_side_effects: true,
+        interrupt: None,
+        result_fn: None,
+        plot_fn: None,
+        arguments: &[],
+        read_line_fn: &mut |_: &str| None,
+        allocated_list_elements: 0,
     };
 
     assert_eq!(
@@ -0,0 +1,123 @@
+use std::fmt;
+use std::str::FromStr;
+
+use compact_str::CompactString;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::span::Span;
+
+/// Warnings are grouped into categories, which can be suppressed individually.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum WarningCategory {
+    /// Integer literals that can not be represented exactly as a floating point number
+    PrecisionLoss,
+    /// Redefinitions of constants from the same file or from a module
+    Redefinition,
+    /// Exponents that are implicitly assumed to be dimensionless
+    ImplicitScalarExponent,
+    /// Unit identifiers that could also be read as a prefix and a different unit
+    AmbiguousUnit,
+    /// Uses of units, functions and constants that are marked as `@deprecated`
+    Deprecated,
+}
+
+impl WarningCategory {
+    pub const ALL: &'static [WarningCategory] = &[
+        WarningCategory::PrecisionLoss,
+        WarningCategory::Redefinition,
+        WarningCategory::ImplicitScalarExponent,
+        WarningCategory::AmbiguousUnit,
+        WarningCategory::Deprecated,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            WarningCategory::PrecisionLoss => "precision-loss",
+            WarningCategory::Redefinition => "redefinition",
+            WarningCategory::ImplicitScalarExponent => "implicit-scalar-exponent",
+            WarningCategory::AmbiguousUnit => "ambiguous-unit",
+            WarningCategory::Deprecated => "deprecated",
+        }
+    }
+}
+
+impl fmt::Display for WarningCategory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for WarningCategory {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        WarningCategory::ALL
+            .iter()
+            .find(|category| category.name() == s)
+            .copied()
+            .ok_or_else(|| format!("Unknown warning category '{s}'"))
+    }
+}
+
+/// A problem in a program that does not prevent it from being run.
+#[derive(Debug, Clone, Error, PartialEq, Eq)]
+pub enum Warning {
+    #[error("Integer literal is larger than 2^53 and can not be represented exactly")]
+    PrecisionLoss(Span),
+
+    #[error("Redefinition of constant '{name}'")]
+    Redefinition {
+        name: CompactString,
+        span: Span,
+        original_span: Span,
+    },
+
+    #[error("Exponent is assumed to be a scalar")]
+    ImplicitScalarExponent(Span),
+
+    #[error("Unit identifier '{identifier}' is read as '{reading}', but could also mean '{alternative}'")]
+    AmbiguousUnit {
+        identifier: CompactString,
+        /// The unit the identifier refers to, e.g. 'minute' for 'min'
+        reading: CompactString,
+        /// The other way to read it, e.g. 'milli-inch' for 'min'
+        alternative: CompactString,
+        /// The definition that introduced the ambiguity
+        span: Span,
+        /// The definition of the other unit involved in the ambiguity
+        other_span: Span,
+    },
+
+    #[error("'{name}' is deprecated: {message}")]
+    Deprecated {
+        name: CompactString,
+        message: CompactString,
+        span: Span,
+        definition_span: Span,
+    },
+}
+
+impl Warning {
+    pub fn category(&self) -> WarningCategory {
+        match self {
+            Warning::PrecisionLoss(_) => WarningCategory::PrecisionLoss,
+            Warning::Redefinition { .. } => WarningCategory::Redefinition,
+            Warning::ImplicitScalarExponent(_) => WarningCategory::ImplicitScalarExponent,
+            Warning::AmbiguousUnit { .. } => WarningCategory::AmbiguousUnit,
+            Warning::Deprecated { .. } => WarningCategory::Deprecated,
+        }
+    }
+
+    /// The location of the code that caused the warning
+    pub fn span(&self) -> Span {
+        match self {
+            Warning::PrecisionLoss(span)
+            | Warning::Redefinition { span, .. }
+            | Warning::ImplicitScalarExponent(span)
+            | Warning::AmbiguousUnit { span, .. }
+            | Warning::Deprecated { span, .. } => *span,
+        }
+    }
+}
@@ -5,9 +5,12 @@ use common::get_test_context;
 use compact_str::CompactString;
 use insta::assert_snapshot;
 use numbat::markup::{Formatter, PlainTextFormatter};
+use numbat::module_importer::FileSystemImporter;
 use numbat::resolver::CodeSource;
-use numbat::NumbatError;
+use numbat::warning::WarningCategory;
 use numbat::{pretty_print::PrettyPrint, Context, InterpreterResult};
+use numbat::{InterpreterSettings, NumbatError, ResourceLimits, RuntimeError};
+use std::path::Path;
 
 #[track_caller]
 fn expect_output_with_context(ctx: &mut Context, code: &str, expected_output: impl AsRef<str>) {
@@ -649,6 +652,411 @@ fn test_conditionals() {
     );
 }
 
+#[track_caller]
+fn printed_output(code: &str) -> String {
+    use std::sync::{Arc, Mutex};
+
+    let output = Arc::new(Mutex::new(String::new()));
+    let mut settings = InterpreterSettings {
+        print_fn: Box::new({
+            let output = output.clone();
+            move |s| output.lock().unwrap().push_str(&format!("{s}\n"))
+        }),
+        ..Default::default()
+    };
+
+    let mut ctx = get_test_context();
+    if let Err(e) = ctx.interpret_with_settings(&mut settings, code, CodeSource::Internal) {
+        panic!("was supposed to succeed but instead got:\n{e}");
+    }
+
+    let output = output.lock().unwrap().clone();
+    output
+}
+
+#[test]
+fn test_for_loops() {
+    assert_eq!(
+        printed_output("for x in [1, 2, 3] { print(x) }"),
+        "1\n2\n3\n"
+    );
+    assert_eq!(printed_output("for x in [] { print(x) }"), "");
+    assert_eq!(
+        printed_output(
+            "for x in [1, 2] {
+                let y = 10 x
+                for u in [m, cm] {
+                    print(y u)
+                }
+            }"
+        ),
+        "10 m\n10 cm\n20 m\n20 cm\n"
+    );
+    assert_eq!(
+        printed_output(
+            "let offset = 1
+            fn shift(x) = x + offset
+            for x in map(shift, range(1, 3)) { print(x) }
+            print(offset)"
+        ),
+        "2\n3\n4\n1\n"
+    );
+
+    // The loop variable and the definitions in the loop body are not visible
+    // after the loop
+    expect_failure("for x in [1, 2] { let y = x }\nx", "Unknown identifier 'x'");
+    expect_failure("for x in [1, 2] { let y = x }\ny", "Unknown identifier 'y'");
+
+    expect_failure(
+        "for x in 3 { print(x) }",
+        "Expected a list to loop over, got Scalar instead",
+    );
+    expect_failure(
+        "for x in [1 m, 2 m] { let y: Time = x }",
+        "actual dimension: Length",
+    );
+    expect_failure("for x in [1, 2] { assert(x < 2) }", "Assertion failed");
+
+    let huge_body = "print(x + 1)\n".repeat(20_000);
+    expect_failure(
+        &format!("for x in [1] {{\n{huge_body}}}"),
+        "The body of a for loop is too large",
+    );
+}
+
+#[test]
+fn test_trace() {
+    assert_eq!(
+        printed_output("fn f(n) = if n == 0 then 1 else n × trace(\"f\", f(n - 1))\nprint(f(3))"),
+        "[<internal:3>:1:48] f = 1\n[<internal:3>:1:48] f = 1\n[<internal:3>:1:48] f = 2\n6\n"
+    );
+    assert_eq!(
+        printed_output("print([1, 2] |> map(sqr) |> trace(\"squares\") |> sum)"),
+        "[<internal:3>:1:7] squares = [1, 4]\n5\n"
+    );
+
+    expect_output("trace(\"x\", 2 m) + 1 m", "3 m");
+}
+
+#[test]
+fn test_scripting() {
+    fn run(
+        settings: &mut InterpreterSettings,
+        code: &str,
+    ) -> Result<InterpreterResult, Box<NumbatError>> {
+        let mut ctx = get_test_context();
+        let _ = ctx
+            .interpret("use extra::scripting", CodeSource::Internal)
+            .unwrap();
+        ctx.interpret_with_settings(settings, code, CodeSource::Internal)
+            .map(|(_, result)| result)
+    }
+
+    fn value(settings: &mut InterpreterSettings, code: &str) -> CompactString {
+        match run(settings, code) {
+            Ok(InterpreterResult::Value(val)) => {
+                let fmt = PlainTextFormatter {};
+                fmt.format(&val.pretty_print(), false).trim().into()
+            }
+            result => panic!("expected a value, got:\n{result:?}"),
+        }
+    }
+
+    fn error(settings: &mut InterpreterSettings, code: &str) -> RuntimeError {
+        match run(settings, code).map_err(|e| *e) {
+            Err(NumbatError::RuntimeError(e)) => e,
+            result => panic!("expected a runtime error, got:\n{result:?}"),
+        }
+    }
+
+    let mut settings = InterpreterSettings {
+        arguments: vec!["input.csv".into(), "-v".into()],
+        ..Default::default()
+    };
+    assert_eq!(value(&mut settings, "args()"), "[\"input.csv\", \"-v\"]");
+    assert_eq!(
+        value(&mut InterpreterSettings::default(), "len(args())"),
+        "0"
+    );
+
+    std::env::set_var("NUMBAT_TEST_ENV_VARIABLE", "some value");
+    assert_eq!(
+        value(
+            &mut InterpreterSettings::default(),
+            "env(\"NUMBAT_TEST_ENV_VARIABLE\")"
+        ),
+        "\"some value\""
+    );
+    assert_eq!(
+        error(
+            &mut InterpreterSettings::default(),
+            "env(\"NUMBAT_TEST_UNSET_ENV_VARIABLE\")"
+        ),
+        RuntimeError::EnvironmentVariableNotSet("NUMBAT_TEST_UNSET_ENV_VARIABLE".into())
+    );
+
+    let mut lines = vec!["2 and\n", " 3\r\n"].into_iter();
+    let mut settings = InterpreterSettings {
+        read_line_fn: Box::new(move |_| lines.next().map(|l| l.to_string())),
+        ..Default::default()
+    };
+    assert_eq!(
+        value(
+            &mut settings,
+            "str_append(read_line(\"a: \"), read_line(\"b: \"))"
+        ),
+        "\"2 and 3\""
+    );
+    assert_eq!(
+        error(&mut settings, "read_line(\"c: \")"),
+        RuntimeError::EndOfInput
+    );
+
+    let mut settings = InterpreterSettings::default();
+    assert_eq!(error(&mut settings, "exit(0)"), RuntimeError::Exit(0));
+    assert_eq!(
+        error(&mut settings, "exit(42)\nerror(\"not reached\")"),
+        RuntimeError::Exit(42)
+    );
+    assert_eq!(
+        error(&mut settings, "exit(256)"),
+        RuntimeError::InvalidExitCode(256.0)
+    );
+    assert_eq!(
+        error(&mut settings, "exit(1.5)"),
+        RuntimeError::InvalidExitCode(1.5)
+    );
+    assert_eq!(
+        error(&mut settings, "exit(300 percent)"),
+        RuntimeError::Exit(3)
+    );
+    assert!(matches!(
+        run(&mut settings, "exit(2 m)").map_err(|e| *e),
+        Err(NumbatError::TypeCheckError(_))
+    ));
+
+    // `exit` is not a reserved word
+    expect_output("let exit = 3\nexit + 1", "4");
+
+    for code in ["args()", "env(\"HOME\")", "read_line(\"\")"] {
+        let mut sandboxed = InterpreterSettings {
+            allow_side_effects: false,
+            ..Default::default()
+        };
+        assert!(matches!(
+            error(&mut sandboxed, code),
+            RuntimeError::SideEffectsDisabled(_)
+        ));
+    }
+    let mut sandboxed = InterpreterSettings {
+        allow_side_effects: false,
+        ..Default::default()
+    };
+    assert_eq!(
+        error(&mut sandboxed, "exit(1)"),
+        RuntimeError::SideEffectsDisabled("exit".into())
+    );
+}
+
+#[test]
+fn test_read_csv() {
+    let data_path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("data");
+    let measurements = data_path.join("measurements.csv");
+    let measurements = measurements.to_string_lossy();
+
+    let mut ctx = get_test_context();
+    let _ = ctx
+        .interpret("use extra::data", CodeSource::Internal)
+        .unwrap();
+
+    expect_output_with_context(
+        &mut ctx,
+        &format!("read_csv(\"{measurements}\", \"length\", cm)"),
+        "[12.5 cm, 13 cm, 14.25 cm]",
+    );
+    expect_output_with_context(
+        &mut ctx,
+        &format!("read_csv(\"{measurements}\", \"time\", 1)"),
+        "[0, 1, 2]",
+    );
+    expect_output_with_context(
+        &mut ctx,
+        &format!("mean(read_csv(\"{measurements}\", \"length\", mm)) -> mm"),
+        "13.25 mm",
+    );
+    expect_output_with_context(
+        &mut ctx,
+        &format!("read_csv_strings(\"{measurements}\", \"label\")"),
+        "[\"first\", \"second, with comma\", \"third\"]",
+    );
+
+    expect_failure_with_context(
+        &mut ctx,
+        &format!("read_csv(\"{measurements}\", \"mass\", kg)"),
+        "Column 'mass' not found in file",
+    );
+    expect_failure_with_context(
+        &mut ctx,
+        &format!(
+            "read_csv(\"{}\", \"length\", m)",
+            data_path.join("invalid_number.csv").to_string_lossy()
+        ),
+        "Could not parse 'n/a' as a number in file",
+    );
+    expect_failure_with_context(
+        &mut ctx,
+        &format!(
+            "read_csv(\"{}\", \"length\", m)",
+            data_path.join("invalid_number.csv").to_string_lossy()
+        ),
+        "(line 3, column 2)",
+    );
+    expect_failure_with_context(
+        &mut ctx,
+        "read_csv(\"does-not-exist.csv\", \"length\", m)",
+        "Could not read from file",
+    );
+    expect_failure_with_context(
+        &mut ctx,
+        "read_csv(\"measurements.csv\", \"length\", m)",
+        "Could not read from file",
+    );
+    expect_failure_with_context(
+        &mut ctx,
+        &format!("read_csv(\"{measurements}\", \"length\", true)"),
+        "Bool: DType",
+    );
+
+    // Relative paths are looked up in the module search paths
+    let mut importer = FileSystemImporter::default();
+    importer.add_path(Path::new(env!("CARGO_MANIFEST_DIR")).join("modules"));
+    importer.add_path(&data_path);
+    let mut ctx = Context::new(importer);
+    let _ = ctx
+        .interpret("use units::si\nuse extra::data", CodeSource::Internal)
+        .unwrap();
+    expect_output_with_context(
+        &mut ctx,
+        "read_csv(\"measurements.csv\", \"time\", s)",
+        "[0 s, 1 s, 2 s]",
+    );
+}
+
+#[test]
+fn test_write_csv_and_json() {
+    let directory = std::env::temp_dir().join(format!("numbat-test-{}", std::process::id()));
+    std::fs::create_dir_all(&directory).unwrap();
+    let csv_path = directory.join("output.csv");
+    let json_path = directory.join("output.json");
+    let (csv, json) = (csv_path.to_string_lossy(), json_path.to_string_lossy());
+
+    let mut ctx = get_test_context();
+    let _ = ctx
+        .interpret(
+            "use extra::data
+            struct Sample { label: String, length: Length, valid: Bool }
+            let times = [0 s, 30 s, 1 min]
+            let lengths = [1 m, 25 cm, 2 km/h × 3 min]",
+            CodeSource::Internal,
+        )
+        .unwrap();
+
+    expect_output_with_context(
+        &mut ctx,
+        &format!(
+            "write_csv(\"{csv}\", [\"time\", \"length\", \"label\"], [csv_column(times, min), csv_column(lengths, m), [\"a\", \"b, c\", \"d\"]])"
+        ),
+        format!("\"{csv}\""),
+    );
+    assert_eq!(
+        std::fs::read_to_string(&csv_path).unwrap(),
+        "time,length,label\n0,1,a\n0.5,0.25,\"b, c\"\n1,100,d\n"
+    );
+    expect_output_with_context(
+        &mut ctx,
+        &format!("read_csv(\"{csv}\", \"length\", m)"),
+        "[1 m, 0.25 m, 100 m]",
+    );
+
+    let _ = ctx
+        .interpret(
+            &format!("write_csv(\"{csv}\", [\"distance\"], [csv_column([1 km, 3 km], 100 m)])"),
+            CodeSource::Internal,
+        )
+        .unwrap();
+    assert_eq!(
+        std::fs::read_to_string(&csv_path).unwrap(),
+        "distance\n10\n30\n"
+    );
+
+    let _ = ctx
+        .interpret(
+            &format!("write_json(\"{json}\", [Sample {{ label: \"first\", length: 2 km/h × 3 h, valid: true }}, Sample {{ label: \"second\", length: 0 inch, valid: false }}])"),
+            CodeSource::Internal,
+        )
+        .unwrap();
+    let written: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&json_path).unwrap()).unwrap();
+    assert_eq!(
+        written,
+        serde_json::json!([
+            { "label": "first", "length": { "value": 6.0, "unit": "km" }, "valid": true },
+            { "label": "second", "length": { "value": 0.0, "unit": "in" }, "valid": false },
+        ])
+    );
+
+    expect_failure_with_context(
+        &mut ctx,
+        &format!("write_csv(\"{csv}\", [\"time\", \"length\"], [csv_column(times, s)])"),
+        "Got 2 header(s) for 1 column(s)",
+    );
+    expect_failure_with_context(
+        &mut ctx,
+        &format!("write_csv(\"{csv}\", [\"a\", \"b\"], [csv_column(times, s), [\"1\", \"2\"]])"),
+        "All columns must have the same length, got columns with 3 and 2 elements",
+    );
+    expect_failure_with_context(
+        &mut ctx,
+        "write_json(\"/does/not/exist/output.json\", 1)",
+        "Could not write to file",
+    );
+
+    // Quantity columns need a unit of the right dimension
+    for code in [
+        format!("write_csv(\"{csv}\", [\"time\"], [times])"),
+        format!("write_csv(\"{csv}\", [\"time\"], [csv_column(times, cm)])"),
+        "write_json(1, 2)".to_string(),
+    ] {
+        assert!(matches!(
+            *ctx.interpret(&code, CodeSource::Internal).unwrap_err(),
+            NumbatError::TypeCheckError(_)
+        ));
+    }
+
+    // The names are not reserved
+    expect_output("let write_csv = 2\nwrite_csv + 1", "3");
+
+    let mut sandboxed = InterpreterSettings {
+        allow_side_effects: false,
+        ..Default::default()
+    };
+    let error = ctx
+        .interpret_with_settings(
+            &mut sandboxed,
+            &format!("write_json(\"{json}\", times)"),
+            CodeSource::Internal,
+        )
+        .unwrap_err();
+    assert!(matches!(
+        *error,
+        NumbatError::RuntimeError(RuntimeError::SideEffectsDisabled(name)) if name == "write_json"
+    ));
+
+    std::fs::remove_dir_all(&directory).unwrap();
+}
+
 #[test]
 fn test_string_interpolation() {
     expect_output("\"pi = {pi}!\"", "\"pi = 3.14159!\"");
@@ -731,6 +1139,71 @@ fn test_override_constants() {
     expect_output("let pi = 4\npi", "4");
 }
 
+#[track_caller]
+fn warnings(ctx: &mut Context, code: &str) -> Vec<WarningCategory> {
+    let _ = ctx.interpret(code, CodeSource::Text);
+    ctx.take_warnings().iter().map(|w| w.category()).collect()
+}
+
+#[test]
+fn test_warnings() {
+    use WarningCategory::*;
+
+    let mut ctx = get_test_context();
+
+    assert_eq!(warnings(&mut ctx, "12345678901234567891"), [PrecisionLoss]);
+    assert_eq!(warnings(&mut ctx, "0x20000000000001"), [PrecisionLoss]);
+    assert_eq!(warnings(&mut ctx, "9007199254740992"), []);
+    assert_eq!(warnings(&mut ctx, "12345678901234567891.0"), []);
+    assert_eq!(warnings(&mut ctx, "1e30"), []);
+
+    assert_eq!(warnings(&mut ctx, "let x = 1\nlet x = 2"), [Redefinition]);
+    assert_eq!(warnings(&mut ctx, "let pi = 4"), [Redefinition]);
+    assert_eq!(warnings(&mut ctx, "let y = 1"), []);
+    assert_eq!(warnings(&mut ctx, "let y = 2"), []);
+
+    assert_eq!(
+        warnings(&mut ctx, "fn f(n) = 2^n"),
+        [ImplicitScalarExponent]
+    );
+    assert_eq!(warnings(&mut ctx, "fn g(n: Scalar) = 2^n"), []);
+
+    assert_eq!(warnings(&mut ctx, "unit kin"), [AmbiguousUnit]);
+    assert_eq!(
+        warnings(&mut ctx, "unit mfoo\n@aliases(foo: short) unit foo_unit"),
+        [AmbiguousUnit]
+    );
+    assert_eq!(warnings(&mut ctx, "@metric_prefixes unit bar_unit"), []);
+
+    assert_eq!(
+        warnings(
+            &mut ctx,
+            "@deprecated(\"use 'new_const'\") let old_const = 1
+             @deprecated(\"use 'meter'\") @metric_prefixes @aliases(old_u: short) unit old_unit = 2 m
+             @deprecated(\"use 'new_fn'\") fn old_fn(x) = x"
+        ),
+        []
+    );
+    assert_eq!(warnings(&mut ctx, "old_const + 1"), [Deprecated]);
+    assert_eq!(warnings(&mut ctx, "3 kold_u"), [Deprecated]);
+    assert_eq!(warnings(&mut ctx, "old_fn(2)"), [Deprecated]);
+    assert_eq!(
+        warnings(&mut ctx, "fn shadowing(old_const) = old_const"),
+        []
+    );
+
+    // Warnings are also reported if the code can not be run
+    assert_eq!(
+        warnings(&mut ctx, "12345678901234567891 + 1 meter + 1 second"),
+        [PrecisionLoss]
+    );
+
+    ctx.set_warning_enabled(PrecisionLoss, false);
+    assert_eq!(warnings(&mut ctx, "12345678901234567891"), []);
+    ctx.set_warning_enabled(PrecisionLoss, true);
+    assert_eq!(warnings(&mut ctx, "12345678901234567891"), [PrecisionLoss]);
+}
+
 #[test]
 fn test_overwrite_captured_constant() {
     expect_output(
@@ -817,6 +1290,258 @@ fn test_recovery_after_runtime_error() {
     }
 }
 
+#[test]
+fn test_resource_limits() {
+    fn run(ctx: &mut Context, settings: InterpreterSettings, code: &str) -> Option<RuntimeError> {
+        let mut settings = InterpreterSettings {
+            print_fn: Box::new(|_| {}),
+            ..settings
+        };
+        match ctx.interpret_with_settings(&mut settings, code, CodeSource::Internal) {
+            Err(e) => match *e {
+                NumbatError::RuntimeError(e) => Some(e),
+                e => panic!("unexpected error: {e}"),
+            },
+            Ok(_) => None,
+        }
+    }
+
+    fn with_limits(limits: ResourceLimits) -> InterpreterSettings {
+        InterpreterSettings {
+            limits,
+            ..Default::default()
+        }
+    }
+
+    let mut ctx = get_test_context();
+    let _ = ctx
+        .interpret("fn f(x) = f(x)", CodeSource::Internal)
+        .unwrap();
+
+    let max_call_depth = with_limits(ResourceLimits {
+        max_call_depth: Some(100),
+        ..Default::default()
+    });
+    assert_eq!(
+        run(&mut ctx, max_call_depth, "f(1)"),
+        Some(RuntimeError::CallDepthLimitExceeded(100))
+    );
+
+    let max_instructions = with_limits(ResourceLimits {
+        max_instructions: Some(10_000),
+        ..Default::default()
+    });
+    assert_eq!(
+        run(&mut ctx, max_instructions, "sum(range(1, 10000))"),
+        Some(RuntimeError::InstructionLimitExceeded(10_000))
+    );
+
+    let max_list_length = with_limits(ResourceLimits {
+        max_list_length: Some(10),
+        ..Default::default()
+    });
+    assert_eq!(
+        run(&mut ctx, max_list_length, "range(1, 100)"),
+        Some(RuntimeError::ListLengthLimitExceeded(10))
+    );
+    let max_list_length = with_limits(ResourceLimits {
+        max_list_length: Some(2),
+        ..Default::default()
+    });
+    assert_eq!(
+        run(&mut ctx, max_list_length, "[1, 2, 3]"),
+        Some(RuntimeError::ListLengthLimitExceeded(2))
+    );
+
+    let max_list_elements = with_limits(ResourceLimits {
+        max_list_elements: Some(50),
+        ..Default::default()
+    });
+    assert_eq!(
+        run(&mut ctx, max_list_elements, "range(1, 100)"),
+        Some(RuntimeError::ListElementLimitExceeded(50))
+    );
+    let max_list_elements = with_limits(ResourceLimits {
+        max_list_elements: Some(50),
+        ..Default::default()
+    });
+    assert_eq!(
+        run(&mut ctx, max_list_elements, "[1, 2, 3]\n[4, 5, 6]"),
+        None
+    );
+
+    let timeout = with_limits(ResourceLimits {
+        timeout: Some(std::time::Duration::from_millis(10)),
+        ..Default::default()
+    });
+    assert_eq!(
+        run(&mut ctx, timeout, "f(1)"),
+        Some(RuntimeError::TimeLimitExceeded(
+            std::time::Duration::from_millis(10)
+        ))
+    );
+
+    let generous_limits = with_limits(ResourceLimits {
+        max_instructions: Some(1_000_000),
+        max_call_depth: Some(1000),
+        max_list_length: Some(1000),
+        max_list_elements: Some(1000),
+        timeout: Some(std::time::Duration::from_secs(60)),
+    });
+    assert_eq!(run(&mut ctx, generous_limits, "sum(range(1, 100))"), None);
+
+    let no_side_effects = InterpreterSettings {
+        allow_side_effects: false,
+        ..Default::default()
+    };
+    assert_eq!(
+        run(
+            &mut ctx,
+            no_side_effects,
+            "use plot::line_plot\nshow(line_plot(cos, 0, 1))"
+        ),
+        Some(RuntimeError::SideEffectsDisabled("show".into()))
+    );
+
+    // The context is still usable after a limit has been hit
+    expect_output_with_context(&mut ctx, "sum(range(1, 100))", "5050");
+}
+
+#[test]
+fn test_interrupt() {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+
+    let mut ctx = get_test_context();
+    let interrupt = Arc::new(AtomicBool::new(true));
+    let mut settings = InterpreterSettings {
+        print_fn: Box::new(|_| {}),
+        interrupt: Some(interrupt.clone()),
+        ..Default::default()
+    };
+
+    let result =
+        ctx.interpret_with_settings(&mut settings, "sum(range(1, 1000))", CodeSource::Internal);
+    assert!(matches!(
+        *result.unwrap_err(),
+        NumbatError::RuntimeError(RuntimeError::Interrupted)
+    ));
+
+    interrupt.store(false, Ordering::Relaxed);
+    let (_, result) = ctx
+        .interpret_with_settings(&mut settings, "sum(range(1, 1000))", CodeSource::Internal)
+        .unwrap();
+    assert_eq!(result.value_as_string().unwrap(), "500_500");
+}
+
+#[test]
+fn test_register_function() {
+    use numbat::value::Value;
+    use numbat::Quantity;
+
+    let mut ctx = get_test_context();
+    ctx.register_function("twice", "(x: Length) -> Length", false, |args| {
+        let x = args.into_iter().next().unwrap().unsafe_as_quantity();
+        Ok(Value::Quantity(x * Quantity::from_scalar(2.0)))
+    })
+    .unwrap();
+    ctx.register_function(
+        "price_of",
+        "(item: String) -> Scalar",
+        true,
+        |args| match args[0].clone().unsafe_as_string().as_str() {
+            "apple" => Ok(Value::Quantity(Quantity::from_scalar(0.5))),
+            item => Err(Box::new(RuntimeError::UserError(format!(
+                "Unknown item: {item}"
+            )))),
+        },
+    )
+    .unwrap();
+
+    expect_output_with_context(&mut ctx, "twice(3 cm)", "6 cm");
+    expect_output_with_context(&mut ctx, "map(twice, [1 m, 2 ft])", "[2 m, 4 ft]");
+    expect_output_with_context(&mut ctx, "price_of(\"apple\")", "0.5");
+    expect_failure_with_context(
+        &mut ctx,
+        "price_of(\"pear\")",
+        "User error: Unknown item: pear",
+    );
+
+    let mut sandboxed = InterpreterSettings {
+        allow_side_effects: false,
+        ..Default::default()
+    };
+    expect_output_with_context(&mut ctx, "twice(3 cm)", "6 cm");
+    assert!(matches!(
+        *ctx.interpret_with_settings(&mut sandboxed, "price_of(\"apple\")", CodeSource::Internal)
+            .unwrap_err(),
+        NumbatError::RuntimeError(RuntimeError::SideEffectsDisabled(_))
+    ));
+
+    // Return values that do not match the declared type
+    ctx.register_function("broken", "(x: Scalar) -> List<Length>", false, |_| {
+        Ok(std::collections::VecDeque::from([Value::Boolean(true)]).into())
+    })
+    .unwrap();
+    expect_failure_with_context(
+        &mut ctx,
+        "head(broken(1))",
+        "Function 'broken' returned a value that is not of its declared return type 'List<Length>'",
+    );
+    ctx.register_function(
+        "wrong_dimension",
+        "(x: Length, t: Time) -> Length",
+        false,
+        |args| Ok(args[1].clone()),
+    )
+    .unwrap();
+    expect_failure_with_context(
+        &mut ctx,
+        "wrong_dimension(1 m, 2 s)",
+        "Function 'wrong_dimension' returned a value that is not of its declared return type 'Length'",
+    );
+    ctx.register_function("speed", "(x: Length, t: Time) -> Velocity", false, |args| {
+        let x = args[0].clone().unsafe_as_quantity();
+        let t = args[1].clone().unsafe_as_quantity();
+        Ok(Value::Quantity(x / t))
+    })
+    .unwrap();
+    expect_output_with_context(&mut ctx, "speed(6 km, 2 h)", "3 km/h");
+    ctx.register_function("identity", "<D: Dim>(x: D) -> D", false, |args| {
+        Ok(args[0].clone())
+    })
+    .unwrap();
+    expect_output_with_context(&mut ctx, "identity(2 s)", "2 s");
+
+    assert!(matches!(
+        *ctx.interpret("twice(2 s)", CodeSource::Internal)
+            .unwrap_err(),
+        NumbatError::TypeCheckError(_)
+    ));
+    assert!(matches!(
+        *ctx.interpret("twice(2 m, 3 m)", CodeSource::Internal)
+            .unwrap_err(),
+        NumbatError::TypeCheckError(_)
+    ));
+
+    // Invalid signatures
+    assert!(matches!(
+        *ctx.register_function("f", "(x: Scalar) -> Scalar = x", false, |_| unreachable!())
+            .unwrap_err(),
+        NumbatError::ResolverError(_)
+    ));
+    assert!(matches!(
+        *ctx.register_function("f", "(x) -> Scalar", false, |_| unreachable!())
+            .unwrap_err(),
+        NumbatError::TypeCheckError(_)
+    ));
+    assert!(matches!(
+        *ctx.interpret("fn f(x: Scalar) -> Scalar", CodeSource::Internal)
+            .unwrap_err(),
+        NumbatError::TypeCheckError(_)
+    ));
+}
+
 #[test]
 fn test_statement_pretty_printing() {
     // Let definitions
@@ -883,6 +1608,12 @@ fn test_statement_pretty_printing() {
         "fn f(x) = y where y = x",
         "fn f<A>(x: A) -> A = y\n  where y: A = x",
     );
+
+    // For loops
+    expect_pretty_print(
+        "for x in [1 m, 2 m] { let y = 2 x\nprint(y) }",
+        "for x in [1 metre, 2 metre] {\n    let y: Length = 2 x\n    print(y)\n}",
+    );
 }
 #[cfg(test)]
 mod tests {
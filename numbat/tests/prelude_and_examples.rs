@@ -3,6 +3,7 @@ mod common;
 use common::get_test_context;
 
 use numbat::resolver::{CodeSource, ResolverError};
+use numbat::warning::WarningCategory;
 use numbat::{InterpreterResult, NumbatError};
 
 use std::ffi::OsStr;
@@ -20,12 +21,19 @@ fn assert_runs(code: &str) {
 }
 
 fn assert_runs_without_prelude(code: &str) {
-    let result = get_test_context_without_prelude().interpret(code, CodeSource::Internal);
+    let mut context = get_test_context_without_prelude();
+    // Ambiguous units in modules are not reported when they are imported, only by
+    // `numbat audit-units`
+    context.set_warning_enabled(WarningCategory::AmbiguousUnit, false);
+    let result = context.interpret(code, CodeSource::Internal);
     assert!(result.is_ok(), "Failed with: {}", result.unwrap_err());
     assert!(matches!(
         result.unwrap().1,
         InterpreterResult::Value(_) | InterpreterResult::Continue
     ));
+
+    let warnings = context.take_warnings();
+    assert!(warnings.is_empty(), "Unexpected warnings: {warnings:#?}");
 }
 
 fn assert_parse_error(code: &str) {